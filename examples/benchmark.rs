@@ -0,0 +1,222 @@
+//! Benchmarks the folder search against a generated folder tree.
+//!
+//! cargo run --release --example benchmark -- --folders 8 --depth 4 --files 10 --runs 3
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use eyre::{eyre, Context, Report, Result};
+use owo_colors::OwoColorize;
+use tabled::{builder::Builder, settings::Style};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+const APP_NAME: &str = "app-lemonator";
+const TARGET_FILE_NAME: &str = "target.exe";
+/// Every nth folder gets a copy of the target file.
+const TARGET_FILE_INTERVAL: usize = 97;
+
+#[derive(Parser, Debug)]
+#[command(about = "Benchmarks the folder search against a generated folder tree.")]
+struct Args {
+    /// Sub folders in each folder.
+    #[arg(long, default_value = "8")]
+    folders: usize,
+    /// How many folders deep the tree goes.
+    #[arg(long, default_value = "4")]
+    depth: usize,
+    /// Files in each folder.
+    #[arg(long, default_value = "10")]
+    files: usize,
+    /// Times to run each search.
+    #[arg(long, default_value = "3")]
+    runs: usize,
+}
+
+struct BenchmarkResult {
+    walker: &'static str,
+    timings: Vec<Duration>,
+    matches: Vec<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+    match run(args.folders, args.depth, args.files, args.runs) {
+        Ok(result) => println!("{}", result),
+        Err(error) => {
+            eprintln!("Unable to run benchmark: {:?}", error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Times the folder search against a generated folder tree, comparing it to a plain
+/// single threaded walk.
+fn run(
+    folders_per_level: usize,
+    depth: usize,
+    files_per_folder: usize,
+    runs: usize,
+) -> Result<String, Report> {
+    let root = std::env::temp_dir().join(format!("{}-benchmark-{}", APP_NAME, Uuid::new_v4()));
+
+    println!("Generating folder tree in '{}'", root.display());
+    let generated = generate_tree(&root, folders_per_level, depth, files_per_folder);
+    let (folder_count, file_count) = match generated {
+        Ok(counts) => counts,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&root);
+            return Err(e);
+        }
+    };
+
+    let root_folder = root.display().to_string();
+    let results = vec![
+        time_walker("Sequential", runs, || walk_sequential(&root_folder)),
+        time_walker("Parallel", runs, || walk_parallel(&root_folder)),
+    ];
+
+    fs::remove_dir_all(&root).wrap_err(format!(
+        "Unable to remove benchmark folder '{}'",
+        root.display()
+    ))?;
+
+    if results[0].matches != results[1].matches {
+        return Err(eyre!(
+            "Benchmark walkers disagree, sequential found {} matches and parallel found {}",
+            results[0].matches.len(),
+            results[1].matches.len()
+        ));
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["Walker", "Runs", "Best", "Mean", "Matches"]);
+    for result in &results {
+        builder.push_record([
+            result.walker.to_string(),
+            result.timings.len().to_string(),
+            format!("{:.1?}", best(&result.timings)),
+            format!("{:.1?}", mean(&result.timings)),
+            result.matches.len().to_string(),
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern());
+
+    let speedup = best(&results[0].timings).as_secs_f64() / best(&results[1].timings).as_secs_f64();
+
+    Ok(format!(
+        "\nSearched {} folders and {} files\n{}\n{}",
+        folder_count,
+        file_count,
+        table,
+        format!("Parallel speedup {:.2}x", speedup).green()
+    ))
+}
+
+fn generate_tree(
+    root: &Path,
+    folders_per_level: usize,
+    depth: usize,
+    files_per_folder: usize,
+) -> Result<(usize, usize), Report> {
+    let mut folders: Vec<PathBuf> = vec![root.to_path_buf()];
+    let mut folder_count = 0;
+    let mut file_count = 0;
+
+    while let Some(folder) = folders.pop() {
+        fs::create_dir_all(&folder)
+            .wrap_err(format!("Unable to create folder '{}'", folder.display()))?;
+        folder_count += 1;
+
+        for file_number in 0..files_per_folder {
+            fs::write(folder.join(format!("file{}.dll", file_number)), [])
+                .wrap_err(format!("Unable to create file in '{}'", folder.display()))?;
+            file_count += 1;
+        }
+        if folder_count % TARGET_FILE_INTERVAL == 0 {
+            fs::write(folder.join(TARGET_FILE_NAME), [])
+                .wrap_err(format!("Unable to create file in '{}'", folder.display()))?;
+            file_count += 1;
+        }
+
+        let folder_depth = folder
+            .strip_prefix(root)
+            .map(|relative| relative.components().count())
+            .unwrap_or(0);
+        if folder_depth < depth {
+            for folder_number in 0..folders_per_level {
+                folders.push(folder.join(format!("folder{}", folder_number)));
+            }
+        }
+    }
+
+    Ok((folder_count, file_count))
+}
+
+fn time_walker<F>(walker: &'static str, runs: usize, walk: F) -> BenchmarkResult
+where
+    F: Fn() -> Vec<String>,
+{
+    let mut timings = Vec::new();
+    let mut matches = Vec::new();
+    for _ in 0..runs.max(1) {
+        let start = Instant::now();
+        matches = walk();
+        timings.push(start.elapsed());
+    }
+
+    BenchmarkResult {
+        walker,
+        timings,
+        matches,
+    }
+}
+
+fn walk_sequential(root_folder: &str) -> Vec<String> {
+    let mut results: Vec<String> = WalkDir::new(root_folder)
+        .into_iter()
+        .flatten()
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.file_name().to_string_lossy().to_lowercase() == TARGET_FILE_NAME
+        })
+        .map(|entry| entry.path().display().to_string())
+        .collect();
+    results.sort();
+    results
+}
+
+/// Walks the way `paths::find_file_in_folders` does with the default search options.
+fn walk_parallel(root_folder: &str) -> Vec<String> {
+    let mut results: Vec<String> = jwalk::WalkDir::new(root_folder)
+        .parallelism(jwalk::Parallelism::RayonNewPool(0))
+        .skip_hidden(false)
+        .sort(true)
+        .into_iter()
+        .flatten()
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.file_name().to_string_lossy().to_lowercase() == TARGET_FILE_NAME
+        })
+        .map(|entry| entry.path().display().to_string())
+        .collect();
+    results.sort();
+    results
+}
+
+fn best(timings: &[Duration]) -> Duration {
+    timings.iter().min().copied().unwrap_or_default()
+}
+
+fn mean(timings: &[Duration]) -> Duration {
+    match timings.is_empty() {
+        true => Duration::default(),
+        false => timings.iter().sum::<Duration>() / timings.len() as u32,
+    }
+}
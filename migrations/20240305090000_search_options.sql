@@ -0,0 +1,5 @@
+ALTER TABLE apps ADD COLUMN search_max_depth INTEGER NULL;
+ALTER TABLE apps ADD COLUMN search_excludes TEXT NULL;
+ALTER TABLE apps ADD COLUMN search_follow_symlinks BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE apps ADD COLUMN search_max_matches INTEGER NULL;
+ALTER TABLE apps ADD COLUMN search_time_budget INTEGER NULL;
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS index_roots
+(
+    base_folder             TEXT PRIMARY KEY NOT NULL,
+    last_indexed            DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS index_folders
+(
+    id                      INTEGER PRIMARY KEY NOT NULL,
+    base_folder             TEXT NOT NULL,
+    folder_path             TEXT NOT NULL,
+    depth                   INTEGER NOT NULL,
+    modified                INTEGER NOT NULL,
+    UNIQUE (base_folder, folder_path)
+);
+
+CREATE TABLE IF NOT EXISTS index_files
+(
+    id                      INTEGER PRIMARY KEY NOT NULL,
+    base_folder             TEXT NOT NULL,
+    folder_path             TEXT NOT NULL,
+    file_path               TEXT NOT NULL,
+    file_name               TEXT NOT NULL,
+    depth                   INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS index_files_folder ON index_files (base_folder, folder_path);
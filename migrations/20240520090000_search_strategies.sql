@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS app_search_strategies
+(
+    id                      INTEGER PRIMARY KEY NOT NULL,
+    app_id                  INTEGER NOT NULL REFERENCES apps (id) ON DELETE CASCADE,
+    position                INTEGER NOT NULL,
+    search_method           TEXT NOT NULL,
+    search_term             TEXT NOT NULL
+);
+
+ALTER TABLE apps ADD COLUMN matched_search_method TEXT NULL;
+ALTER TABLE apps ADD COLUMN matched_search_term TEXT NULL;
@@ -0,0 +1,6 @@
+ALTER TABLE apps ADD COLUMN app_publisher TEXT NULL;
+ALTER TABLE apps ADD COLUMN app_product_name TEXT NULL;
+ALTER TABLE apps ADD COLUMN app_bundle_id TEXT NULL;
+ALTER TABLE apps ADD COLUMN app_copyright TEXT NULL;
+ALTER TABLE apps ADD COLUMN app_architecture TEXT NULL;
+ALTER TABLE apps ADD COLUMN app_build_number TEXT NULL;
@@ -0,0 +1 @@
+ALTER TABLE apps ADD COLUMN search_arch TEXT NOT NULL DEFAULT 'Native';
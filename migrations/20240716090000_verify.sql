@@ -0,0 +1,3 @@
+ALTER TABLE apps ADD COLUMN verify_policy TEXT NOT NULL DEFAULT 'Off';
+ALTER TABLE apps ADD COLUMN verify_signer TEXT;
+ALTER TABLE apps ADD COLUMN app_sha256 TEXT;
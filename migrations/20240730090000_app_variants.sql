@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS app_variants
+(
+    id                      INTEGER PRIMARY KEY NOT NULL,
+    app_id                  INTEGER NOT NULL REFERENCES apps (id) ON DELETE CASCADE,
+    operating_system        TEXT NOT NULL,
+    exe_name                TEXT,
+    search_method           TEXT NOT NULL,
+    search_term             TEXT NOT NULL,
+    params                  TEXT,
+    UNIQUE (app_id, operating_system)
+);
@@ -1,17 +1,28 @@
-use std::{fs::File, io::Write};
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
 
 use color_eyre::{eyre::Context, owo_colors::OwoColorize, Report, Result};
-use dialoguer::{theme::ColorfulTheme, Confirm};
-use log::{debug, error, info};
+use dialoguer::{theme::ColorfulTheme, Confirm, MultiSelect};
+use indicatif::MultiProgress;
+use log::{debug, error, info, warn};
 use tabled::{
     builder::Builder,
     settings::{object::Rows, Modify, Style, Width},
 };
 
 use crate::{
-    data,
+    backup, cancel, data,
     db::{self},
-    finder, paths, runner,
+    discover, export, finder, icon, import, index, launchers, lnk, manifest, paths, runner,
+    schedule, spreadsheet, streamdeck, verify,
 };
 
 pub enum ListType {
@@ -19,19 +30,92 @@ pub enum ListType {
     Summary,
 }
 
+pub struct SearchOptionsEdit {
+    /// 0 searches the base folder only, like on add. clear_max_depth removes the limit.
+    pub max_depth: Option<u32>,
+    pub clear_max_depth: bool,
+    pub excludes: Option<String>,
+    pub follow_symlinks: Option<bool>,
+    pub max_matches: Option<u32>,
+    pub time_budget: Option<u32>,
+    pub arch: Option<data::ArchPreference>,
+}
+
+impl SearchOptionsEdit {
+    fn apply(&self, search_options: &mut data::SearchOptions) {
+        // Passing 0 removes a limit, apart from the max depth where 0 is a depth
+        let limit = |value: Option<u32>, current: Option<u32>| match value {
+            Some(0) => None,
+            Some(value) => Some(value),
+            None => current,
+        };
+
+        if self.clear_max_depth {
+            search_options.search_max_depth = None;
+        } else if let Some(max_depth) = self.max_depth {
+            search_options.search_max_depth = Some(max_depth);
+        }
+        if let Some(excludes) = &self.excludes {
+            search_options.search_excludes = match excludes.is_empty() {
+                true => None,
+                false => Some(excludes.clone()),
+            };
+        }
+        if let Some(follow_symlinks) = self.follow_symlinks {
+            search_options.search_follow_symlinks = follow_symlinks;
+        }
+        search_options.search_max_matches =
+            limit(self.max_matches, search_options.search_max_matches);
+        search_options.search_time_budget =
+            limit(self.time_budget, search_options.search_time_budget);
+        if let Some(arch) = &self.arch {
+            search_options.search_arch = arch.clone();
+        }
+    }
+}
+
+pub struct VerifyOptionsEdit {
+    pub policy: Option<data::VerifyPolicy>,
+    pub signer: Option<String>,
+}
+
+impl VerifyOptionsEdit {
+    fn apply(&self, verify_options: &mut data::VerifyOptions) {
+        if let Some(policy) = &self.policy {
+            verify_options.verify_policy = policy.clone();
+        }
+        // Passing an empty signer removes it
+        if let Some(signer) = &self.signer {
+            verify_options.verify_signer = match signer.is_empty() {
+                true => None,
+                false => Some(signer.clone()),
+            };
+        }
+    }
+}
+
+pub enum OutputType {
+    Table,
+    Json,
+}
+
 pub fn create_db() -> Result<bool, Report> {
     db::create_db()
 }
 
-pub fn open_app(app_name: &str) -> Result<String, Report> {
-    let app = db::get_app(app_name).wrap_err("Unable to open app".to_string())?;
+pub fn open_app(app_name: &str, operating_system: data::OperatingSystem) -> Result<String, Report> {
+    let app = db::get_app(app_name)
+        .wrap_err("Unable to open app".to_string())?
+        .for_operating_system(&operating_system);
 
     let current_app_file_version = if let Some(app_path) = &app.app_path {
-        Some(data::FileVersion::new(
+        let mut current_app_file_version = data::FileVersion::new(
             app.app_description.clone().unwrap_or_default(),
             app_path.to_string(),
             app.app_version.clone().unwrap_or_default(),
-        ))
+        );
+        current_app_file_version.metadata = app.file_metadata.clone();
+        Some(current_app_file_version)
     } else {
         None
     };
@@ -42,13 +126,16 @@ pub fn open_app(app_name: &str) -> Result<String, Report> {
 
     // Just getting the latest app path/file version regardless is not a quick process
     // so lets check if the app path exists first. If it doesn't, then we'll get the latest info.
-    let update_app_file_version = if !paths::check_app_exists(&update_app_file_version.path) {
+    let mut update_app_file_version = if !paths::check_app_exists(&update_app_file_version.path) {
         finder::get_app_file_version(app.clone(), None)
             .wrap_err("Unable to open app".to_string())?
     } else {
         update_app_file_version
     };
 
+    // Check the file before anything is recorded against it or it is run
+    let warnings = verify_app_file(&app, &mut update_app_file_version)?;
+
     // Check the app exists, update last run date/time and file version information
     if paths::check_app_exists(&update_app_file_version.path) {
         db::update_app_file_version(app.id, &update_app_file_version).wrap_err(format!(
@@ -80,9 +167,86 @@ pub fn open_app(app_name: &str) -> Result<String, Report> {
         }
     }
 
-    Ok(open_result)
+    match warnings.is_empty() {
+        true => Ok(open_result),
+        false => Ok(format!("{}\n{}", warnings.join("\n"), open_result)),
+    }
+}
+
+/// Checks the file against the hash recorded when the app path was last updated, and the
+/// signer the app expects, according to the app's verify policy.
+fn verify_app_file(
+    app: &data::App,
+    app_file_version: &mut data::FileVersion,
+) -> Result<Vec<String>, Report> {
+    let file_changed = verify::is_file_changed(app, app_file_version);
+
+    if app.verify_options.verify_policy == data::VerifyPolicy::Off {
+        // Without hashing the file, the recorded hash only still applies to the same file
+        app_file_version.sha256 = match file_changed {
+            true => None,
+            false => app.app_sha256.clone(),
+        };
+        return Ok(Vec::new());
+    }
+
+    let actual_hash = verify::hash_file(&app_file_version.path)
+        .wrap_err(format!("Unable to verify app '{}'", app.app_name))?;
+    let actual_signer = match &app.verify_options.verify_signer {
+        Some(_) => verify::get_signer(&app_file_version.path),
+        None => None,
+    };
+    debug!(
+        "Verifying app '{}' - SHA-256 '{}', recorded '{}', signer '{}'",
+        app.app_name,
+        actual_hash,
+        data::display_option_string(&app.app_sha256),
+        data::display_option_string(&actual_signer)
+    );
+
+    let problems = verify::find_problems(&verify::FileCheck {
+        recorded_hash: app.app_sha256.as_deref(),
+        actual_hash: &actual_hash,
+        file_changed,
+        expected_signer: app.verify_options.verify_signer.as_deref(),
+        actual_signer: actual_signer.as_deref(),
+    });
+    let warnings =
+        verify::apply_policy(&app.verify_options.verify_policy, &app.app_name, &problems)?;
+
+    // A new file at the same path keeps warning until update records its hash
+    app_file_version.sha256 = match (&app.app_sha256, file_changed) {
+        (Some(recorded_hash), false) => Some(recorded_hash.clone()),
+        _ => Some(actual_hash),
+    };
+    Ok(warnings)
+}
+
+pub fn close_app(app_name: &str, force: bool, timeout: u64) -> Result<String, Report> {
+    // Apps have no groups to close together, so '@' names are flagged rather than looked up
+    if app_name.starts_with('@') {
+        return Err(eyre::eyre!(
+            "Unable to close '{}', closing a group of apps isn't supported. Close each app by name",
+            app_name
+        ));
+    }
+    let app = db::get_app(app_name).wrap_err("Unable to close app".to_string())?;
+
+    let app_path = match &app.app_path {
+        Some(app_path) => app_path.clone(),
+        None => {
+            return Ok(format!(
+                "App '{}' has no app path, it has not been opened or updated yet",
+                app.app_name.blue()
+            ))
+        }
+    };
+
+    runner::close_process(app, &app_path, force, Duration::from_secs(timeout))
+        .wrap_err("Unable to close app".to_string())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn add_app(
     app_name: String,
     exe_name: String,
@@ -90,6 +254,10 @@ pub fn add_app(
     search_term: String,
     search_method: data::SearchMethod,
     operating_system: data::OperatingSystem,
+    search_options: data::SearchOptions,
+    fallback_strategies: Vec<data::SearchStrategy>,
+    verify_options: data::VerifyOptions,
+    variants: Vec<data::AppVariant>,
 ) -> Result<String, Report> {
     // If the app already exists, this is "OK". Report back the details of what is stored.
     if (db::get_app(&app_name)).is_ok() {
@@ -105,7 +273,7 @@ pub fn add_app(
         ));
     }
 
-    let new_app = data::App::new(
+    let mut new_app = data::App::new(
         app_name,
         exe_name,
         params,
@@ -113,6 +281,10 @@ pub fn add_app(
         search_method,
         operating_system,
     );
+    new_app.search_options = search_options;
+    new_app.fallback_strategies = fallback_strategies;
+    new_app.verify_options = verify_options;
+    new_app.variants = variants;
 
     if let Err(error) = new_app.validate() {
         return Err(eyre::eyre!(
@@ -121,6 +293,7 @@ pub fn add_app(
         ));
     }
 
+    warn_implied_regex(&new_app);
     db::add_app(&new_app).wrap_err("Error adding app".to_string())?;
 
     let app = db::get_app(&new_app.app_name)
@@ -129,6 +302,60 @@ pub fn add_app(
     Ok(format!("Successfully added {}", app.to_description()))
 }
 
+pub fn add_app_from_lnk(
+    lnk_file: String,
+    app_name: Option<String>,
+    params: Option<String>,
+    operating_system: data::OperatingSystem,
+    fallback_strategies: Vec<data::SearchStrategy>,
+    verify_options: data::VerifyOptions,
+    variants: Vec<data::AppVariant>,
+) -> Result<String, Report> {
+    let lnk_file = paths::get_base_folder(&lnk_file);
+    let shell_link = lnk::read(&lnk_file).wrap_err("Unable to add app from shortcut")?;
+
+    let lnk_path = env::current_dir()
+        .wrap_err("Unable to add app from shortcut, error getting current folder")?
+        .join(&lnk_file);
+    let (Some(lnk_folder), Some(lnk_file_name), Some(lnk_name)) = (
+        lnk_path.parent(),
+        lnk_path.file_name(),
+        lnk_path.file_stem(),
+    ) else {
+        return Err(eyre::eyre!(
+            "Unable to add app from shortcut, '{}' is not a file",
+            lnk_file
+        ));
+    };
+
+    info!(
+        "Shortcut '{}' - Target '{}', Arguments '{}', Working Folder '{}', Icon '{}' ({}), Comment '{}'",
+        lnk_file.blue(),
+        data::display_option_string(&shell_link.target_path).magenta(),
+        data::display_option_string(&shell_link.arguments).magenta(),
+        data::display_option_string(&shell_link.working_dir).magenta(),
+        data::display_option_string(&shell_link.icon_location).magenta(),
+        shell_link.icon_index,
+        data::display_option_string(&shell_link.name).magenta()
+    );
+
+    add_app(
+        app_name.unwrap_or(lnk_name.to_string_lossy().to_string()),
+        lnk_file_name.to_string_lossy().to_string(),
+        params.or(shell_link
+            .arguments
+            .filter(|arguments| !arguments.is_empty())),
+        paths::to_path_variable(&lnk_folder.display().to_string()),
+        data::SearchMethod::LnkFile,
+        operating_system,
+        data::SearchOptions::default(),
+        fallback_strategies,
+        verify_options,
+        variants,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn edit_app(
     lookup_app_name: String,
     app_name: Option<String>,
@@ -136,6 +363,10 @@ pub fn edit_app(
     params: Option<String>,
     search_term: Option<String>,
     search_method: Option<data::SearchMethod>,
+    search_options_edit: SearchOptionsEdit,
+    fallback_strategies: Option<Vec<data::SearchStrategy>>,
+    verify_options_edit: VerifyOptionsEdit,
+    variants: Option<Vec<data::AppVariant>>,
 ) -> Result<String, Report> {
     let mut app = db::get_app(&lookup_app_name).wrap_err("Unable to edit app".to_string())?;
 
@@ -150,6 +381,14 @@ pub fn edit_app(
     }
     app.search_term = search_term.unwrap_or(app.search_term);
     app.search_method = search_method.unwrap_or(app.search_method);
+    search_options_edit.apply(&mut app.search_options);
+    if let Some(fallback_strategies) = fallback_strategies {
+        app.fallback_strategies = fallback_strategies;
+    }
+    verify_options_edit.apply(&mut app.verify_options);
+    if let Some(variants) = variants {
+        app.variants = variants;
+    }
     debug!(
         "After editing - lookup app name '{}', app record '{:?}'",
         lookup_app_name, app
@@ -162,30 +401,139 @@ pub fn edit_app(
         ));
     }
 
+    warn_implied_regex(&app);
     db::edit_app(&lookup_app_name, &app).wrap_err("Unable to edit app".to_string())?;
 
     Ok(format!("Successfully edited {}", app.to_description()))
 }
 
+/// Exe names with regex syntax are matched as a regex even without "re:", which may not be what
+/// was meant, so say so.
+fn warn_implied_regex(app: &data::App) {
+    let is_folder_search = app
+        .get_search_strategies()
+        .iter()
+        .any(|strategy| strategy.search_method == data::SearchMethod::FolderSearch);
+    if is_folder_search && paths::is_implied_regex(&app.exe_name) {
+        warn!(
+            "Exe name '{}' of app '{}' is matched as a regular expression, as it has syntax only a regular expression has. Start it with 're:' to say so",
+            app.exe_name.magenta(),
+            app.app_name.blue()
+        );
+    }
+}
+
 pub fn delete_app(app_name: &str) -> Result<String, Report> {
-    if (db::get_app(app_name)).is_err() {
+    let Ok(app) = db::get_app(app_name) else {
         return Ok(format!(
             "App '{}' does not exist, so cannot be deleted",
             app_name.blue()
         ));
-    }
+    };
     db::delete_app(app_name)?;
+    icon::remove_cached_icon(&app);
     Ok(format!("Successfully deleted app '{}'", app_name.blue()))
 }
 
-fn update_app_file_version_for_list(apps: Vec<data::App>) -> Result<String, Report> {
-    let (success, failed) = {
+pub fn icon(app_name: &str, out: Option<String>) -> Result<String, Report> {
+    let app = db::get_app(app_name).wrap_err("Unable to get icon".to_string())?;
+    let icon_path = icon::get_cached_icon(&app)?;
+
+    let Some(out) = out else {
+        return Ok(format!(
+            "Icon for app '{}' is at '{}'",
+            app.app_name.blue(),
+            icon_path.display().to_string().green()
+        ));
+    };
+    fs::copy(&icon_path, &out).wrap_err(format!("Unable to save icon to '{}'", out))?;
+    Ok(format!(
+        "Saved icon for app '{}' to '{}'",
+        app.app_name.blue(),
+        out.green()
+    ))
+}
+
+fn update_app_file_version_for_list(
+    apps: Vec<data::App>,
+    jobs: usize,
+    operating_system: &data::OperatingSystem,
+) -> Result<String, Report> {
+    cancel::install_handler();
+
+    // Search the way open does, with the variant for this operating system
+    let apps: Vec<data::App> = apps
+        .into_iter()
+        .map(|app| app.for_operating_system(operating_system))
+        .collect();
+
+    let multi_progress = MultiProgress::new();
+    let queue = Mutex::new(apps.iter().collect::<VecDeque<&data::App>>());
+    let (sender, receiver) = mpsc::channel();
+
+    let (success, failed) = thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, apps.len().max(1)) {
+            let sender = sender.clone();
+            let queue = &queue;
+            let multi_progress = &multi_progress;
+            scope.spawn(move || loop {
+                if cancel::is_cancelled() {
+                    break;
+                }
+                let Some(app) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+
+                let pb = multi_progress.add(paths::new_search_progress_bar());
+                pb.set_prefix(format!("{} ", app.app_name.blue()));
+                pb.enable_steady_tick(Duration::from_millis(120));
+
+                // I want this process to continue to run, even if one or more apps fail to update
+                let result = finder::search_app_file_version(app.clone(), &pb).map(
+                    |mut app_file_version| {
+                        // Recorded so open can tell when the file changes unexpectedly
+                        app_file_version.sha256 = match verify::hash_file(&app_file_version.path) {
+                            Ok(hash) => Some(hash),
+                            Err(error) => {
+                                warn!("Unable to hash app '{}': {:?}", app.app_name, error);
+                                None
+                            }
+                        };
+                        if let (Some(recorded_hash), Some(hash)) =
+                            (&app.app_sha256, &app_file_version.sha256)
+                        {
+                            if !verify::is_file_changed(app, &app_file_version)
+                                && !recorded_hash.eq_ignore_ascii_case(hash)
+                            {
+                                warn!(
+                                    "Replacing the recorded SHA-256 of app '{}' at '{}', it was '{}' and is now '{}'",
+                                    app.app_name, app_file_version.path, recorded_hash, hash
+                                );
+                            }
+                        }
+                        app_file_version
+                    },
+                );
+
+                // Anything still searching when Ctrl-C was pressed may be incomplete, so drop it
+                if cancel::is_cancelled() {
+                    pb.finish_with_message("Cancelled".yellow().to_string());
+                    break;
+                }
+                match &result {
+                    Ok(_) => pb.finish_with_message("Found".green().to_string()),
+                    Err(_) => pb.finish_with_message("Failed".red().to_string()),
+                }
+                sender.send((app, result)).unwrap();
+            });
+        }
+        drop(sender);
+
+        // Save results as they come in, so finished apps are kept if the update is cancelled
         let mut success = 0;
         let mut failed = 0;
-
-        for app in &apps {
-            // I want this process to continue to run, even if one or more apps fail to update
-            match finder::get_app_file_version(app.clone(), None) {
+        for (app, result) in receiver {
+            multi_progress.suspend(|| match result {
                 Ok(app_file_version) => {
                     match db::update_app_file_version(app.id, &app_file_version) {
                         Ok(_) => {
@@ -210,11 +558,13 @@ fn update_app_file_version_for_list(apps: Vec<data::App>) -> Result<String, Repo
                     error!("Error getting app path for '{}': {:?}", app.app_name, error);
                     failed += 1;
                 }
-            }
+            });
         }
 
         (success, failed)
-    };
+    });
+
+    let cancelled = apps.len() - success - failed;
 
     let message = {
         if success == apps.len() {
@@ -223,6 +573,13 @@ fn update_app_file_version_for_list(apps: Vec<data::App>) -> Result<String, Repo
             } else {
                 "Successfully updated all apps".green().to_string()
             }
+        } else if cancelled > 0 {
+            format!(
+                "{}\n{}\n{}",
+                format!("Successfully updated {} apps", success).green(),
+                format!("Failed to update {} apps", failed).red(),
+                format!("Cancelled update of {} apps", cancelled).yellow()
+            )
         } else {
             format!(
                 "{}\n{}",
@@ -234,7 +591,12 @@ fn update_app_file_version_for_list(apps: Vec<data::App>) -> Result<String, Repo
     Ok(message)
 }
 
-pub fn update_app(app_name: Option<String>, force: bool) -> Result<String, Report> {
+pub fn update_app(
+    app_name: Option<String>,
+    force: bool,
+    jobs: usize,
+    operating_system: data::OperatingSystem,
+) -> Result<String, Report> {
     let apps = match app_name {
         Some(app_name) => {
             vec![db::get_app(&app_name)
@@ -252,11 +614,177 @@ pub fn update_app(app_name: Option<String>, force: bool) -> Result<String, Repor
                 return Ok("Aborted app path update".to_string());
             }
 
+            let backup_path = backup::create(backup::BackupReason::Update, backup::BACKUPS_KEPT)
+                .wrap_err("Unable to update app path for all apps".to_string())?;
+            info!("Backed up database to '{}'", backup_path.display());
+
             db::get_apps().wrap_err("Unable to update app path for all apps".to_string())?
         }
     };
 
-    update_app_file_version_for_list(apps).wrap_err("Unable to update app path")
+    update_app_file_version_for_list(apps, jobs, &operating_system)
+        .wrap_err("Unable to update app path")
+}
+
+pub fn refresh(
+    stale_after: chrono::Duration,
+    jobs: usize,
+    operating_system: data::OperatingSystem,
+) -> Result<String, Report> {
+    let stale_before = chrono::Utc::now() - stale_after;
+    let apps: Vec<data::App> = db::get_apps()
+        .wrap_err("Unable to refresh app paths".to_string())?
+        .into_iter()
+        .filter(|app| match app.last_updated {
+            Some(last_updated) => last_updated < stale_before,
+            None => true,
+        })
+        .collect();
+
+    if apps.is_empty() {
+        return Ok(format!(
+            "No apps need refreshing, all were updated in the last {}",
+            data::display_duration(&stale_after)
+        ));
+    }
+
+    let backup_path = backup::create(backup::BackupReason::Refresh, backup::BACKUPS_KEPT)
+        .wrap_err("Unable to refresh app paths".to_string())?;
+    info!("Backed up database to '{}'", backup_path.display());
+
+    info!(
+        "Refreshing {} apps not updated in the last {}",
+        apps.len(),
+        data::display_duration(&stale_after)
+    );
+    update_app_file_version_for_list(apps, jobs, &operating_system)
+        .wrap_err("Unable to refresh app paths")
+}
+
+pub fn schedule(
+    scheduler: data::ScheduleType,
+    every: chrono::Duration,
+    stale_after: chrono::Duration,
+    folder_out: Option<String>,
+    force: bool,
+) -> Result<String, Report> {
+    // The database lives in the working folder, so the scheduled task needs to run from here
+    let working_dir = env::current_dir()
+        .wrap_err("Unable to generate schedule, error getting current folder".to_string())?
+        .display()
+        .to_string();
+
+    let files = schedule::generate(
+        &scheduler,
+        &paths::get_current_exe(),
+        &working_dir,
+        &every,
+        &stale_after,
+    )
+    .wrap_err("Unable to generate schedule".to_string())?;
+
+    let folder = match folder_out {
+        Some(folder) if paths::folder_exists(&folder) => folder,
+        _ => dirs::document_dir().unwrap().display().to_string(),
+    };
+
+    for file in &files {
+        let output_file = paths::get_full_path(&folder, &file.file_name);
+        if paths::file_exists(&output_file)
+            && !force
+            && !Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Schedule file '{}' already exists, do you want to overwrite it?",
+                    output_file
+                ))
+                .interact()
+                .unwrap()
+        {
+            return Ok("Aborted schedule".to_string());
+        }
+
+        let mut output = File::create(&output_file).wrap_err(format!(
+            "Unable to generate schedule, error creating file '{}'",
+            output_file
+        ))?;
+        output
+            .write_all(file.contents.as_bytes())
+            .wrap_err(format!(
+                "Unable to generate schedule, error writing to file '{}'",
+                output_file
+            ))?;
+        info!("Saved '{}'", output_file.magenta());
+    }
+
+    Ok(format!(
+        "{}\n{}",
+        format!(
+            "Successfully generated {} schedule to refresh every {}",
+            scheduler,
+            data::display_duration(&every)
+        )
+        .green(),
+        schedule::get_install_hint(&scheduler, &folder)
+    ))
+}
+
+pub fn index(clear: bool) -> Result<String, Report> {
+    if clear {
+        db::clear_index().wrap_err("Unable to clear index".to_string())?;
+        return Ok("Successfully cleared index".green().to_string());
+    }
+
+    // Each app's search options decide how much of its base folder is indexed
+    let apps: Vec<data::App> = db::get_apps()
+        .wrap_err("Unable to refresh index".to_string())?
+        .into_iter()
+        .filter(|app| {
+            app.search_method == data::SearchMethod::FolderSearch
+                && !app.search_options.search_follow_symlinks
+        })
+        .collect();
+
+    if apps.is_empty() {
+        return Ok("No FolderSearch apps to index.".to_string());
+    }
+
+    cancel::install_handler();
+    let mut failed = 0;
+    for app in &apps {
+        let base_folder = paths::get_base_folder(&app.search_term);
+        let pb = paths::new_search_progress_bar();
+        pb.enable_steady_tick(Duration::from_millis(120));
+        match index::refresh(&base_folder, &app.search_options, &pb) {
+            Ok(_) => {
+                pb.finish_and_clear();
+                info!(
+                    "Indexed '{}' for app '{}'",
+                    base_folder.magenta(),
+                    app.app_name.blue()
+                );
+            }
+            Err(error) => {
+                pb.finish_and_clear();
+                error!(
+                    "Unable to index '{}' for app '{}': {:?}",
+                    base_folder, app.app_name, error
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed == 0 {
+        return Ok(format!(
+            "{}",
+            format!("Successfully indexed {} apps", apps.len()).green()
+        ));
+    }
+    Ok(format!(
+        "{}\n{}",
+        format!("Successfully indexed {} apps", apps.len() - failed).green(),
+        format!("Failed to index {} apps", failed).red()
+    ))
 }
 
 pub fn list_app(app_name: Option<String>, list_type: ListType) -> Result<String, Report> {
@@ -305,7 +833,307 @@ pub fn list_app(app_name: Option<String>, list_type: ListType) -> Result<String,
     }
 }
 
-pub fn reset(force: bool) -> Result<String, Report> {
+pub fn status(output_type: OutputType) -> Result<String, Report> {
+    let apps = db::get_apps().wrap_err("Unable to get app status".to_string())?;
+    let statuses = runner::get_app_statuses(apps);
+
+    if let OutputType::Json = output_type {
+        return serde_json::to_string_pretty(&statuses)
+            .wrap_err("Unable to get app status, error serializing status".to_string());
+    }
+
+    if statuses.is_empty() {
+        return Ok("No apps to list.".to_string());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record([
+        "App Name",
+        "Running",
+        "PIDs",
+        "Uptime",
+        "Memory",
+        "App Path",
+        "Path Exists",
+    ]);
+    for status in statuses {
+        let running = match status.running {
+            true => "Yes".green().to_string(),
+            false => "No".to_string(),
+        };
+        let path_exists = match status.path_exists {
+            true => "Yes".green().to_string(),
+            false => "No".red().to_string(),
+        };
+        let pids: Vec<String> = status.pids.iter().map(|p| p.to_string()).collect();
+        builder.push_record([
+            status.app_name.blue().to_string(),
+            running,
+            pids.join(", "),
+            data::display_option_uptime(&status.uptime),
+            data::display_option_memory(&status.memory),
+            data::display_option_string(&status.app_path),
+            path_exists,
+        ]);
+    }
+    let mut table = builder.build();
+    table
+        .with(Modify::new(Rows::new(1..)).with(Width::wrap(50).keep_words()))
+        .with(Style::modern());
+
+    Ok(format!("\n{}", table))
+}
+
+pub fn check_apps(
+    app_name: Option<String>,
+    operating_system: data::OperatingSystem,
+) -> Result<String, Report> {
+    let apps = match app_name {
+        Some(app_name) => {
+            vec![db::get_app(&app_name).wrap_err("Unable to check app".to_string())?]
+        }
+        None => db::get_apps().wrap_err("Unable to check apps".to_string())?,
+    };
+    let apps: Vec<data::App> = apps
+        .into_iter()
+        .map(|app| app.for_operating_system(&operating_system))
+        .collect();
+
+    if apps.is_empty() {
+        return Ok("No apps to check.".to_string());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record([
+        "App Name",
+        "App Path",
+        "Search",
+        "Version",
+        "Path Variables",
+        "Params",
+    ]);
+    let mut failures: Vec<String> = Vec::new();
+    let mut failed_apps = 0;
+
+    for app in &apps {
+        let checks = check_app(app);
+
+        let mut record = vec![app.app_name.clone()];
+        for (check_name, check_result) in &checks {
+            record.push(check_result.display());
+            if let data::CheckResult::Failed(reason) = check_result {
+                failures.push(format!(
+                    "'{}' {} - {}",
+                    app.app_name.blue(),
+                    check_name,
+                    reason
+                ));
+            }
+        }
+        builder.push_record(record);
+
+        if checks
+            .iter()
+            .any(|(_, check_result)| check_result.is_failed())
+        {
+            failed_apps += 1;
+        }
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern());
+
+    if failed_apps > 0 {
+        return Err(eyre::eyre!(
+            "\n{}\n{}\n{}",
+            table,
+            failures.join("\n"),
+            format!("{} of {} apps failed checks", failed_apps, apps.len()).red()
+        ));
+    }
+
+    Ok(format!("\n{}\n{}", table, "All apps passed checks".green()))
+}
+
+fn check_app(app: &data::App) -> Vec<(&'static str, data::CheckResult)> {
+    let app_path_check = match &app.app_path {
+        Some(app_path) => match paths::check_app_exists(app_path) {
+            true => data::CheckResult::Passed,
+            false => data::CheckResult::Failed(format!("App path '{}' does not exist", app_path)),
+        },
+        None => data::CheckResult::Failed("No app path saved, run update".to_string()),
+    };
+
+    let (search_check, version_check) = match finder::get_app_file_version(app.clone(), None) {
+        Ok(app_file_version) => {
+            let search_check = match &app.app_path {
+                Some(app_path) if !paths::is_same_path(app_path, &app_file_version.path) => {
+                    data::CheckResult::Failed(format!(
+                        "Search now resolves to '{}', saved app path is '{}'",
+                        app_file_version.path, app_path
+                    ))
+                }
+                _ => data::CheckResult::Passed,
+            };
+
+            let version_check = match &app.app_version {
+                Some(app_version) => {
+                    let saved_file_version =
+                        data::FileVersion::new(String::new(), String::new(), app_version.clone());
+                    match app_file_version.is_older_than(&saved_file_version) {
+                        true => data::CheckResult::Failed(format!(
+                            "Version has regressed from '{}' to '{}'",
+                            saved_file_version.display_version(),
+                            app_file_version.display_version()
+                        )),
+                        false => data::CheckResult::Passed,
+                    }
+                }
+                None => data::CheckResult::Skipped,
+            };
+
+            (search_check, version_check)
+        }
+        Err(error) => (
+            data::CheckResult::Failed(format!("{:#}", error)),
+            data::CheckResult::Skipped,
+        ),
+    };
+
+    let mut unresolved: Vec<String> = Vec::new();
+    for strategy in app.get_search_strategies() {
+        for variable in paths::get_unresolved_path_variables(&strategy.search_term) {
+            if !unresolved.contains(&variable) {
+                unresolved.push(variable);
+            }
+        }
+    }
+    let path_variables_check = match unresolved.is_empty() {
+        true => data::CheckResult::Passed,
+        false => data::CheckResult::Failed(format!(
+            "Unable to expand path variables {}",
+            unresolved.join(", ")
+        )),
+    };
+
+    let params_check = match &app.params {
+        Some(params) => match paths::try_parse_arguments(params) {
+            Some(_) => data::CheckResult::Passed,
+            None => data::CheckResult::Failed(format!("Unable to parse params '{}'", params)),
+        },
+        None => data::CheckResult::Skipped,
+    };
+
+    vec![
+        ("App Path", app_path_check),
+        ("Search", search_check),
+        ("Version", version_check),
+        ("Path Variables", path_variables_check),
+        ("Params", params_check),
+    ]
+}
+
+pub fn discover(operating_system: data::OperatingSystem, list: bool) -> Result<String, Report> {
+    let existing_apps = db::get_apps().wrap_err("Unable to discover apps".to_string())?;
+
+    let pb = paths::new_search_progress_bar();
+    pb.enable_steady_tick(Duration::from_millis(120));
+    let candidates = discover::find_candidates(&existing_apps, &operating_system, &pb);
+    pb.finish_and_clear();
+
+    if candidates.is_empty() {
+        return Ok("No new apps found.".to_string());
+    }
+
+    if list {
+        let mut builder = Builder::default();
+        builder.push_record([
+            "App Name",
+            "Exe Name",
+            "Search Term",
+            "Search Method",
+            "Source",
+        ]);
+        for candidate in &candidates {
+            builder.push_record([
+                candidate.app.app_name.clone(),
+                candidate.app.exe_name.clone(),
+                candidate.app.search_term.clone(),
+                candidate.app.search_method.to_string(),
+                candidate.source.to_string(),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(Style::modern());
+        return Ok(format!("\n{}\nFound {} new apps", table, candidates.len()));
+    }
+
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|candidate| {
+            format!(
+                "{} - {} ({})",
+                candidate.app.app_name, candidate.app_path, candidate.source
+            )
+        })
+        .collect();
+    let selections = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Pick the apps to add, space to select and enter to confirm")
+        .items(&items)
+        .interact()
+        .wrap_err("Unable to discover apps, error picking apps")?;
+
+    if selections.is_empty() {
+        return Ok("No apps picked, nothing added.".to_string());
+    }
+
+    let backup_path = backup::create(backup::BackupReason::Discover, backup::BACKUPS_KEPT)
+        .wrap_err("Unable to discover apps".to_string())?;
+    info!("Backed up database to '{}'", backup_path.display());
+
+    let mut success = 0;
+    let mut failed = 0;
+    for index in &selections {
+        let app = &candidates[*index].app;
+        if let Err(error) = app.validate() {
+            error!(
+                "{} '{}', {} - {:?}",
+                "Unable to add app".red(),
+                app.app_name.blue(),
+                "validation error".red(),
+                error
+            );
+            failed += 1;
+            continue;
+        }
+
+        match db::add_app(app) {
+            Ok(_) => {
+                info!("Successfully added {}", app.to_description());
+                success += 1;
+            }
+            Err(error) => {
+                error!("Unable to add app '{}': {:?}", app.app_name, error);
+                failed += 1;
+            }
+        }
+    }
+
+    let message = match failed {
+        0 => format!("Successfully added {} apps", success)
+            .green()
+            .to_string(),
+        _ => format!(
+            "{}\n{}",
+            format!("Successfully added {} apps", success).green(),
+            format!("Failed to add {} apps", failed).red()
+        ),
+    };
+
+    Ok(message)
+}
+
+pub fn reset(force: bool, keep_backup: bool) -> Result<String, Report> {
     if !db::database_exists() {
         return Ok("Database does not exist, nothing to reset.".to_string());
     }
@@ -317,21 +1145,226 @@ pub fn reset(force: bool) -> Result<String, Report> {
             .interact()
             .unwrap()
     {
+        let mut backup_path = backup::create(backup::BackupReason::Reset, backup::BACKUPS_KEPT)
+            .wrap_err("Failed to reset database.")?;
+        if keep_backup {
+            backup_path = backup::keep(&backup_path).wrap_err("Failed to reset database.")?;
+        }
         db::reset_db().wrap_err("Failed to reset database.")?;
-        Ok("Successfully reset database.".green().to_string())
+        icon::clear_cached_icons();
+        Ok(format!(
+            "{} Backed up to '{}'.",
+            "Successfully reset database.".green(),
+            backup_path.display()
+        ))
     } else {
         Ok("Database reset not confirmed.".to_string())
     }
 }
 
-pub fn export(file_out: Option<String>, force: bool) -> Result<String, Report> {
-    let apps = db::get_apps().wrap_err("Unable to export".to_string())?;
+pub fn backup(
+    file_out: Option<String>,
+    force: bool,
+    keep: usize,
+    list: bool,
+) -> Result<String, Report> {
+    if list {
+        let folder = backup::get_backups_folder();
+        let backups = backup::list(&folder);
+        if backups.is_empty() {
+            return Ok(format!("No backups in '{}'", folder.display()));
+        }
+        return Ok(format!(
+            "Backups in '{}', oldest first:\n{}",
+            folder.display(),
+            backups
+                .iter()
+                .map(|backup| backup.file_name().unwrap_or_default().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+
+    let Some(file_out) = file_out else {
+        let backup_path = backup::create(backup::BackupReason::Manual, keep)
+            .wrap_err("Unable to back up database")?;
+        return Ok(format!(
+            "{}",
+            format!(
+                "Successfully backed up database to '{}'",
+                backup_path.display()
+            )
+            .green()
+        ));
+    };
+
+    if paths::file_exists(&file_out)
+        && !force
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Backup file '{}' already exists, do you want to overwrite it?",
+                file_out
+            ))
+            .interact()
+            .unwrap()
+    {
+        return Ok("Aborted backup".to_string());
+    }
+    // The file might not be a database, which the backup can't copy into
+    if paths::file_exists(&file_out) {
+        fs::remove_file(&file_out).wrap_err(format!(
+            "Unable to back up database, error removing '{}'",
+            file_out
+        ))?;
+    }
+    db::backup_db(&db::get_db_path(), Path::new(&file_out))
+        .wrap_err("Unable to back up database")?;
+    Ok(format!(
+        "{}",
+        format!("Successfully backed up database to '{}'", file_out).green()
+    ))
+}
+
+pub fn restore(backup_file: &str, force: bool) -> Result<String, Report> {
+    let backup_path = backup::find(backup_file).wrap_err("Unable to restore database")?;
+
+    if !force
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Do you want to restore the database from '{}'? The apps in the database now will be replaced.",
+                backup_path.display()
+            ))
+            .interact()
+            .unwrap()
+    {
+        return Ok("Aborted restore".to_string());
+    }
+
+    let previous_path = backup::create(backup::BackupReason::Restore, backup::BACKUPS_KEPT)
+        .wrap_err("Unable to restore database")?;
+    db::restore_db(&backup_path, &db::get_db_path()).wrap_err("Unable to restore database")?;
+    Ok(format!(
+        "{} The database before restoring is backed up to '{}'.",
+        format!(
+            "Successfully restored database from '{}'.",
+            backup_path.display()
+        )
+        .green(),
+        previous_path.display()
+    ))
+}
+
+pub fn apply(
+    manifest_file: String,
+    operating_system: data::OperatingSystem,
+    prune: bool,
+    dry_run: bool,
+    force: bool,
+) -> Result<String, Report> {
+    let contents = fs::read_to_string(&manifest_file).wrap_err(format!(
+        "Unable to apply, error opening manifest '{}'",
+        manifest_file
+    ))?;
+    let manifest = manifest::parse(&manifest_file, &contents)
+        .wrap_err(format!("Unable to apply manifest '{}'", manifest_file))?;
+    let desired_apps = manifest::get_desired_apps(&manifest, &operating_system)
+        .wrap_err(format!("Unable to apply manifest '{}'", manifest_file))?;
+    let current_apps =
+        db::get_apps().wrap_err("Unable to apply manifest, error getting current apps")?;
+
+    let changes = manifest::plan(&current_apps, desired_apps, prune);
+    if changes.is_empty() {
+        return Ok("Nothing to apply, the database matches the manifest"
+            .green()
+            .to_string());
+    }
+
+    let plan = manifest::display_plan(&changes);
+    if dry_run {
+        return Ok(format!("{}\n{}", plan, "Dry run, no changes made".yellow()));
+    }
+    info!("Plan for manifest '{}':\n{}", manifest_file, plan);
+    if !force
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Do you want to make these {} changes?",
+                changes.len()
+            ))
+            .interact()
+            .unwrap()
+    {
+        return Ok("Aborted apply".to_string());
+    }
+    let backup_path = backup::create(backup::BackupReason::Apply, backup::BACKUPS_KEPT)
+        .wrap_err("Unable to apply manifest")?;
+    info!("Backed up database to '{}'", backup_path.display());
+
+    let mut success = 0;
+    let mut failed = 0;
+    for change in &changes {
+        let result = match change {
+            manifest::ManifestChange::Add(app) => {
+                db::add_app(app).map(|_| format!("Successfully added {}", app.to_description()))
+            }
+            manifest::ManifestChange::Update { current, desired } => {
+                db::edit_app(&current.app_name, desired)
+                    .map(|_| format!("Successfully updated {}", desired.to_description()))
+            }
+            manifest::ManifestChange::Remove(app) => db::delete_app(&app.app_name).map(|_| {
+                icon::remove_cached_icon(app);
+                format!("Successfully removed app '{}'", app.app_name.blue())
+            }),
+        };
+        match result {
+            Ok(description) => {
+                info!("{}", description);
+                success += 1;
+            }
+            Err(error) => {
+                error!("Unable to apply change to app: {:?}", error);
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(format!("Successfully applied {} changes", success)
+            .green()
+            .to_string()),
+        _ => Ok(format!(
+            "{}\n{}",
+            format!("Successfully applied {} changes", success).green(),
+            format!("Failed to apply {} changes", failed).red()
+        )),
+    }
+}
+
+pub fn export(
+    file_out: Option<String>,
+    force: bool,
+    include_history: bool,
+    format: data::ExportFormat,
+    app_names: Option<Vec<String>>,
+    icons: bool,
+) -> Result<String, Report> {
+    let mut apps = db::get_apps().wrap_err("Unable to export".to_string())?;
+    if let Some(app_names) = app_names {
+        apps = app_names
+            .iter()
+            .map(|app_name| {
+                apps.iter()
+                    .find(|app| app.app_name.eq_ignore_ascii_case(app_name))
+                    .cloned()
+                    .ok_or_else(|| eyre::eyre!("Unable to export, no app named '{}'", app_name))
+            })
+            .collect::<Result<Vec<data::App>, Report>>()?;
+    }
 
     let file_checked: String = file_out.unwrap_or_default();
     let output_file = paths::get_export_file_name(
         &file_checked,
         dirs::document_dir().unwrap(),
-        &paths::get_unique_export_file_name(),
+        &paths::get_unique_export_file_name(format.get_extension()),
     );
 
     if paths::file_exists(&output_file)
@@ -347,13 +1380,38 @@ pub fn export(file_out: Option<String>, force: bool) -> Result<String, Report> {
         return Ok("Aborted export".to_string());
     }
 
-    let serialized = serde_json::to_string(&apps)
-        .wrap_err("Unable to export, error serializing apps to export:".to_string())?;
+    if include_history && format != data::ExportFormat::Json {
+        warn!(
+            "{} exports don't include history, ignoring --include-history",
+            format
+        );
+    }
+    if icons && format != data::ExportFormat::Streamdeck {
+        warn!("{} exports don't include icons, ignoring --icons", format);
+    }
+    let serialized = match format {
+        data::ExportFormat::Json => {
+            serde_json::to_vec(&export::ExportFile::new(apps, include_history))
+                .wrap_err("Unable to export, error serializing apps to export:".to_string())?
+        }
+        data::ExportFormat::Csv => spreadsheet::write(&apps)
+            .wrap_err("Unable to export".to_string())?
+            .into_bytes(),
+        data::ExportFormat::Streamdeck => streamdeck::write_profile(
+            &apps,
+            &streamdeck::get_lemonator_path(&paths::get_current_exe()),
+            &match icons {
+                true => get_icons(&apps),
+                false => HashMap::new(),
+            },
+        )
+        .wrap_err("Unable to export".to_string())?,
+    };
     let mut file = File::create(&output_file).wrap_err(format!(
         "Unable to export, error creating file to export: '{}'",
         output_file
     ))?;
-    file.write_all(serialized.as_bytes()).wrap_err(format!(
+    file.write_all(&serialized).wrap_err(format!(
         "Unable to export, error writing to file to export: '{}'",
         output_file
     ))?;
@@ -364,26 +1422,67 @@ pub fn export(file_out: Option<String>, force: bool) -> Result<String, Report> {
     ))
 }
 
-pub fn import(file_in: String) -> Result<String, Report> {
-    let file = File::open(&file_in).wrap_err(format!(
-        "Unable to import, error opening file '{}'",
-        file_in
-    ))?;
-    let deserialized: Vec<data::App> = serde_json::from_reader(file).wrap_err(format!(
-        "Unable to import, error deserializing file '{}'",
-        file_in
-    ))?;
+/// Icons for the apps by app name. Apps without an icon are left out, their keys just show the
+/// title.
+fn get_icons(apps: &[data::App]) -> HashMap<String, Vec<u8>> {
+    let mut icons = HashMap::new();
+    for app in apps {
+        match icon::get_cached_icon(app).and_then(|icon_path| {
+            fs::read(&icon_path).wrap_err(format!("Unable to read '{}'", icon_path.display()))
+        }) {
+            Ok(png) => {
+                icons.insert(app.app_name.clone(), png);
+            }
+            Err(error) => warn!("No icon for app '{}' - {:?}", app.app_name, error),
+        }
+    }
+
+    icons
+}
+
+pub fn import(
+    file_in: String,
+    from: Option<data::Launcher>,
+    on_conflict: data::ImportConflict,
+    dry_run: bool,
+    operating_system: data::OperatingSystem,
+) -> Result<String, Report> {
+    let items = read_import_items(&file_in, from, &operating_system)?;
+    let total = items.len();
+    if !dry_run {
+        let backup_path = backup::create(backup::BackupReason::Import, backup::BACKUPS_KEPT)
+            .wrap_err("Unable to import")?;
+        info!("Backed up database to '{}'", backup_path.display());
+    }
 
+    let mut state = import::ImportState::new(
+        db::get_apps().wrap_err("Unable to import, error getting current apps")?,
+    );
+    let mut changes = Vec::new();
     let mut success = 0;
     let mut skipped = 0;
     let mut failed = 0;
 
-    for app in &deserialized {
+    for item in items {
+        let (location, app) = match item {
+            import::ImportItem::App { location, app } => (location, *app),
+            import::ImportItem::Invalid { location, error } => {
+                error!("{}{} - {}", "Unable to import app".red(), location, error);
+                failed += 1;
+                continue;
+            }
+            import::ImportItem::Unmapped { source, reason } => {
+                warn!("Skipped '{}', not an app - {}", source.blue(), reason);
+                skipped += 1;
+                continue;
+            }
+        };
         if let Err(error) = app.validate() {
             error!(
-                "{} '{}', {} - {:?}",
+                "{} '{}'{}, {} - {:?}",
                 "Unable to import app".red(),
                 app.app_name.blue(),
+                location,
                 "validation error".red(),
                 error
             );
@@ -391,25 +1490,61 @@ pub fn import(file_in: String) -> Result<String, Report> {
             continue;
         }
 
-        if (db::get_app(&app.app_name)).is_ok() {
-            info!("Skipped app '{}', already exists", app.app_name.blue());
-            skipped += 1;
-        } else {
-            match db::add_app(app) {
-                Ok(_) => {
-                    info!("Successfully added {}", app.to_description());
-                    success += 1;
-                }
-                Err(error) => {
-                    error!("Unable to add app '{}': {:?}", app.app_name, error);
-                    failed += 1;
-                }
+        let change = state.plan(app, &on_conflict);
+        if dry_run {
+            state.apply(&change);
+            changes.push(change);
+            continue;
+        }
+
+        let result = match &change {
+            import::ImportChange::Skip(app) => {
+                info!("Skipped app '{}', already exists", app.app_name.blue());
+                skipped += 1;
+                continue;
+            }
+            import::ImportChange::Add(app) | import::ImportChange::Rename { renamed: app, .. } => {
+                db::add_app(app).map(|_| format!("Successfully added {}", app.to_description()))
+            }
+            import::ImportChange::Overwrite { current, imported } => {
+                // A path found by the old search may not be what the new one finds
+                db::replace_app(imported, !current.is_same_search(imported)).map(|_| {
+                    icon::remove_cached_icon(current);
+                    format!("Successfully overwrote {}", imported.to_description())
+                })
+            }
+            import::ImportChange::Merge { current, merged } => {
+                db::edit_app(&current.app_name, merged)
+                    .map(|_| format!("Successfully merged {}", merged.to_description()))
+            }
+        };
+        match result {
+            Ok(description) => {
+                info!("{}", description);
+                state.apply(&change);
+                success += 1;
+            }
+            Err(error) => {
+                error!(
+                    "Unable to import app '{}': {:?}",
+                    change.get_app().app_name,
+                    error
+                );
+                failed += 1;
             }
         }
     }
 
+    if dry_run {
+        return Ok(format!(
+            "{}\n{}",
+            import::display_changes(&changes),
+            "Dry run, no changes made".yellow()
+        ));
+    }
+
     let message = {
-        if success == deserialized.len() {
+        if success == total {
             "Successfully imported all apps".green().to_string()
         } else {
             format!(
@@ -423,3 +1558,73 @@ pub fn import(file_in: String) -> Result<String, Report> {
 
     Ok(message)
 }
+
+/// Reads the apps to import from an export, a CSV file or another launcher's config.
+fn read_import_items(
+    file_in: &str,
+    from: Option<data::Launcher>,
+    operating_system: &data::OperatingSystem,
+) -> Result<Vec<import::ImportItem>, Report> {
+    if let Some(launcher) = from {
+        info!("Importing '{}', from {}", file_in, launcher);
+        let entries = launchers::read(&launcher, file_in, operating_system).wrap_err(format!(
+            "Unable to import, error reading {} from '{}'",
+            launcher, file_in
+        ))?;
+        return Ok(entries
+            .into_iter()
+            .map(|entry| match entry.app {
+                Ok(app) => import::ImportItem::App {
+                    location: format!(" from '{}'", entry.source),
+                    app: Box::new(app),
+                },
+                Err(reason) => import::ImportItem::Unmapped {
+                    source: entry.source,
+                    reason,
+                },
+            })
+            .collect());
+    }
+
+    let contents = fs::read_to_string(file_in).wrap_err(format!(
+        "Unable to import, error opening file '{}'",
+        file_in
+    ))?;
+
+    // Apps from CSV files have the line they are on, so problems can be found in the spreadsheet
+    if spreadsheet::is_csv_file(file_in) {
+        info!("Importing '{}', CSV file", file_in);
+        let rows = spreadsheet::parse(&contents, operating_system).wrap_err(format!(
+            "Unable to import, error reading CSV file '{}'",
+            file_in
+        ))?;
+        return Ok(rows
+            .into_iter()
+            .map(|row| {
+                let location = format!(" on line {}", row.line);
+                match row.app {
+                    Ok(app) => import::ImportItem::App {
+                        location,
+                        app: Box::new(app),
+                    },
+                    Err(error) => import::ImportItem::Invalid { location, error },
+                }
+            })
+            .collect());
+    }
+
+    let export_file = export::parse(&contents).wrap_err(format!(
+        "Unable to import, error deserializing file '{}'",
+        file_in
+    ))?;
+    info!("Importing '{}', {}", file_in, export_file.describe());
+    // Apps are stored as exported, their variant for this operating system is used when searching
+    Ok(export_file
+        .into_apps()
+        .into_iter()
+        .map(|app| import::ImportItem::App {
+            location: String::new(),
+            app: Box::new(app),
+        })
+        .collect())
+}
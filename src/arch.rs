@@ -0,0 +1,351 @@
+use std::{env, fs::File, io::Read, path::Path};
+
+use log::warn;
+use strum_macros::Display;
+
+use crate::{bundle, data};
+
+/// Enough of the file to find the headers of any of the formats.
+const HEADER_READ_SIZE: u64 = 64 * 1024;
+/// Java class files share the fat Mach-O magic number, but have a much larger count after it.
+const MAX_FAT_ARCHITECTURES: u32 = 32;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const MACH_O_MAGIC_32: u32 = 0xFEED_FACE;
+const MACH_O_MAGIC_64: u32 = 0xFEED_FACF;
+const FAT_MAGIC: u32 = 0xCAFE_BABE;
+const FAT_MAGIC_64: u32 = 0xCAFE_BABF;
+const MACH_O_ABI64: i32 = 0x0100_0000;
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum Architecture {
+    #[strum(serialize = "x86")]
+    X86,
+    #[strum(serialize = "x64")]
+    X64,
+    #[strum(serialize = "arm")]
+    Arm,
+    #[strum(serialize = "arm64")]
+    Arm64,
+}
+
+pub fn native() -> Option<Architecture> {
+    match env::consts::ARCH {
+        "x86" => Some(Architecture::X86),
+        "x86_64" => Some(Architecture::X64),
+        "arm" => Some(Architecture::Arm),
+        "aarch64" => Some(Architecture::Arm64),
+        _ => None,
+    }
+}
+
+/// Reads the architectures an executable was built for. Universal macOS binaries have more than
+/// one, and anything that isn't a PE, ELF or Mach-O executable has none.
+pub fn detect(path: &str) -> Vec<Architecture> {
+    let Some(executable) = bundle::get_executable_path(Path::new(path)) else {
+        return Vec::new();
+    };
+    let Ok(file) = File::open(executable) else {
+        return Vec::new();
+    };
+
+    let mut bytes = Vec::new();
+    if file.take(HEADER_READ_SIZE).read_to_end(&mut bytes).is_err() {
+        return Vec::new();
+    }
+    parse(&bytes)
+}
+
+pub fn display_architectures(architectures: &[Architecture]) -> String {
+    architectures
+        .iter()
+        .map(|architecture| architecture.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub fn parse(bytes: &[u8]) -> Vec<Architecture> {
+    if bytes.starts_with(b"MZ") {
+        return parse_pe(bytes).into_iter().collect();
+    }
+    if bytes.starts_with(ELF_MAGIC) {
+        return parse_elf(bytes).into_iter().collect();
+    }
+
+    match read_u32(bytes, 0, false) {
+        Some(MACH_O_MAGIC_32) | Some(MACH_O_MAGIC_64) => read_u32(bytes, 4, false)
+            .and_then(|cpu_type| get_mach_o_architecture(cpu_type as i32))
+            .into_iter()
+            .collect(),
+        _ => match read_u32(bytes, 0, true) {
+            Some(FAT_MAGIC) => parse_fat(bytes, 20),
+            Some(FAT_MAGIC_64) => parse_fat(bytes, 32),
+            // Mach-O files written on a big endian machine
+            Some(MACH_O_MAGIC_32) | Some(MACH_O_MAGIC_64) => read_u32(bytes, 4, true)
+                .and_then(|cpu_type| get_mach_o_architecture(cpu_type as i32))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn parse_pe(bytes: &[u8]) -> Option<Architecture> {
+    let pe_offset = read_u32(bytes, 0x3C, false)? as usize;
+    if bytes.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    match read_u16(bytes, pe_offset + 4, false)? {
+        0x014C => Some(Architecture::X86),
+        0x8664 => Some(Architecture::X64),
+        0x01C4 => Some(Architecture::Arm),
+        0xAA64 => Some(Architecture::Arm64),
+        _ => None,
+    }
+}
+
+fn parse_elf(bytes: &[u8]) -> Option<Architecture> {
+    let big_endian = *bytes.get(5)? == 2;
+
+    match read_u16(bytes, 0x12, big_endian)? {
+        0x03 => Some(Architecture::X86),
+        0x3E => Some(Architecture::X64),
+        0x28 => Some(Architecture::Arm),
+        0xB7 => Some(Architecture::Arm64),
+        _ => None,
+    }
+}
+
+fn parse_fat(bytes: &[u8], entry_size: usize) -> Vec<Architecture> {
+    let Some(count) = read_u32(bytes, 4, true) else {
+        return Vec::new();
+    };
+    if count > MAX_FAT_ARCHITECTURES {
+        return Vec::new();
+    }
+
+    (0..count as usize)
+        .filter_map(|index| read_u32(bytes, 8 + index * entry_size, true))
+        .filter_map(|cpu_type| get_mach_o_architecture(cpu_type as i32))
+        .collect()
+}
+
+fn get_mach_o_architecture(cpu_type: i32) -> Option<Architecture> {
+    match cpu_type {
+        7 => Some(Architecture::X86),
+        c if c == 7 | MACH_O_ABI64 => Some(Architecture::X64),
+        12 => Some(Architecture::Arm),
+        c if c == 12 | MACH_O_ABI64 => Some(Architecture::Arm64),
+        _ => None,
+    }
+}
+
+pub fn read_u16(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let value: [u8; 2] = bytes.get(offset..offset + 2)?.try_into().ok()?;
+    match big_endian {
+        true => Some(u16::from_be_bytes(value)),
+        false => Some(u16::from_le_bytes(value)),
+    }
+}
+
+pub fn read_u32(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let value: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    match big_endian {
+        true => Some(u32::from_be_bytes(value)),
+        false => Some(u32::from_le_bytes(value)),
+    }
+}
+
+/// Keeps the files that best suit the preference. Preferring native also accepts builds this
+/// machine can emulate, like x64 on arm64, when there is no native build. An explicit
+/// architecture with no matching build keeps nothing.
+pub fn filter_by_preference(
+    files: Vec<(String, Vec<Architecture>)>,
+    preference: &data::ArchPreference,
+    native: Option<Architecture>,
+) -> Vec<String> {
+    let wanted = match preference {
+        data::ArchPreference::Any => {
+            return files.into_iter().map(|(file, _)| file).collect();
+        }
+        data::ArchPreference::Native => native,
+        data::ArchPreference::X64 => Some(Architecture::X64),
+        data::ArchPreference::Arm64 => Some(Architecture::Arm64),
+    };
+
+    let rank = |architectures: &[Architecture]| -> u8 {
+        match wanted {
+            Some(wanted) if architectures.contains(&wanted) => 0,
+            // Unknown architectures, like scripts, are neither a match or a mismatch
+            _ if architectures.is_empty() => 1,
+            Some(wanted)
+                if *preference == data::ArchPreference::Native
+                    && architectures
+                        .iter()
+                        .any(|architecture| can_emulate(wanted, *architecture)) =>
+            {
+                1
+            }
+            _ => 2,
+        }
+    };
+
+    let best_rank = files
+        .iter()
+        .map(|(_, architectures)| rank(architectures))
+        .min()
+        .unwrap_or(0);
+    if best_rank == 2 && *preference != data::ArchPreference::Native {
+        warn!(
+            "None of the {} matches are {} builds, use --arch any to allow them",
+            files.len(),
+            preference
+        );
+        return Vec::new();
+    }
+    files
+        .into_iter()
+        .filter(|(_, architectures)| rank(architectures) == best_rank)
+        .map(|(file, _)| file)
+        .collect()
+}
+
+fn can_emulate(native: Architecture, architecture: Architecture) -> bool {
+    matches!(
+        (native, architecture),
+        (Architecture::X64, Architecture::X86)
+            | (Architecture::Arm64, Architecture::X64)
+            | (Architecture::Arm64, Architecture::X86)
+            | (Architecture::Arm64, Architecture::Arm)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arch::{detect, filter_by_preference, parse, Architecture},
+        data::ArchPreference,
+    };
+
+    fn get_fixture(file_name: &str) -> String {
+        format!(
+            "{}/tests/fixtures/arch/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            file_name
+        )
+    }
+
+    #[test]
+    fn detect_fixture_executables() {
+        // Arrange
+        let fixtures = [
+            ("pe_x86.exe", vec![Architecture::X86]),
+            ("pe_x64.exe", vec![Architecture::X64]),
+            ("pe_arm64.exe", vec![Architecture::Arm64]),
+            ("elf_x64", vec![Architecture::X64]),
+            ("elf_arm64", vec![Architecture::Arm64]),
+            ("macho_arm64", vec![Architecture::Arm64]),
+            (
+                "macho_universal",
+                vec![Architecture::X64, Architecture::Arm64],
+            ),
+        ];
+
+        for (file_name, expected) in fixtures {
+            // Act
+            let actual = detect(&get_fixture(file_name));
+
+            // Assert
+            assert_eq!(actual, expected, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn detect_app_bundle_reads_bundle_executable() {
+        // Arrange
+        let bundle = format!(
+            "{}/tests/fixtures/bundles/Applications/Utilities/Lime.app",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        // Act
+        let actual = detect(&bundle);
+
+        // Assert
+        assert_eq!(actual, vec![Architecture::X64, Architecture::Arm64]);
+    }
+
+    #[test]
+    fn parse_java_class_is_not_fat_mach_o() {
+        // Arrange
+        let bytes = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x41];
+
+        // Act
+        let actual = parse(&bytes);
+
+        // Assert
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn filter_by_preference_explicit_arch() {
+        // Arrange
+        let files = vec![
+            ("x64.exe".to_string(), vec![Architecture::X64]),
+            ("arm64.exe".to_string(), vec![Architecture::Arm64]),
+        ];
+
+        // Act
+        let actual = filter_by_preference(files, &ArchPreference::Arm64, Some(Architecture::X64));
+
+        // Assert
+        assert_eq!(actual, vec!["arm64.exe".to_string()]);
+    }
+
+    #[test]
+    fn filter_by_preference_native_falls_back_to_emulated() {
+        // Arrange
+        let files = vec![
+            ("x86.exe".to_string(), vec![Architecture::X86]),
+            ("x64.exe".to_string(), vec![Architecture::X64]),
+        ];
+
+        // Act
+        let actual =
+            filter_by_preference(files, &ArchPreference::Native, Some(Architecture::Arm64));
+
+        // Assert
+        assert_eq!(actual, vec!["x86.exe".to_string(), "x64.exe".to_string()]);
+    }
+
+    #[test]
+    fn filter_by_preference_explicit_arch_no_match() {
+        // Arrange
+        let files = vec![
+            ("x86.exe".to_string(), vec![Architecture::X86]),
+            ("x64.exe".to_string(), vec![Architecture::X64]),
+        ];
+
+        // Act
+        let actual = filter_by_preference(files, &ArchPreference::Arm64, Some(Architecture::X64));
+
+        // Assert
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn filter_by_preference_native_no_match_keeps_all() {
+        // Arrange
+        let files = vec![
+            ("arm.exe".to_string(), vec![Architecture::Arm]),
+            ("arm64.exe".to_string(), vec![Architecture::Arm64]),
+        ];
+
+        // Act
+        let actual = filter_by_preference(files, &ArchPreference::Native, Some(Architecture::X64));
+
+        // Assert
+        assert_eq!(actual.len(), 2);
+    }
+}
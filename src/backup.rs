@@ -0,0 +1,211 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use eyre::{eyre, Context, Report};
+use log::{debug, warn};
+use strum_macros::Display;
+
+use crate::{constants, db};
+
+/// Backups kept in the backups folder, the oldest are removed after each new backup.
+pub const BACKUPS_KEPT: usize = 10;
+
+/// Marks a backup that is never removed, like the one taken by `reset --keep-backup`.
+const KEPT_SUFFIX: &str = "-kept";
+const BACKUP_EXTENSION: &str = "db";
+
+/// Why a backup was taken, which ends its file name.
+#[derive(Debug, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum BackupReason {
+    Manual,
+    Reset,
+    Import,
+    Apply,
+    Restore,
+    Update,
+    Refresh,
+    Discover,
+}
+
+/// Backups go next to the database, as each folder App Lemonator is run from has its own.
+pub fn get_backups_folder() -> PathBuf {
+    let db_path = db::get_db_path();
+    db_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(format!("{}-backups", constants::APP_NAME))
+}
+
+/// Backs up the database to the backups folder, then removes the oldest backups past the number
+/// to keep.
+pub fn create(reason: BackupReason, keep: usize) -> Result<PathBuf, Report> {
+    let folder = get_backups_folder();
+    fs::create_dir_all(&folder).wrap_err(format!(
+        "Unable to create backups folder '{}'",
+        folder.display()
+    ))?;
+
+    let backup_path = folder.join(get_backup_file_name(&reason, Local::now()));
+    db::backup_db(&db::get_db_path(), &backup_path)
+        .wrap_err(format!("Unable to back up the database ({})", reason))?;
+    debug!("Backed up database to '{}'", backup_path.display());
+
+    // The new backup is always kept
+    remove_old(&folder, keep.max(1));
+    Ok(backup_path)
+}
+
+/// Names sort oldest first, so the timestamp goes before the reason.
+fn get_backup_file_name(reason: &BackupReason, now: DateTime<Local>) -> String {
+    format!(
+        "{}-{}-{}.{}",
+        constants::APP_NAME,
+        now.format("%Y-%m-%d-%H%M%S-%3f"),
+        reason,
+        BACKUP_EXTENSION
+    )
+}
+
+/// Renames a backup so it is never removed.
+pub fn keep(backup_path: &Path) -> Result<PathBuf, Report> {
+    let file_stem = backup_path
+        .file_stem()
+        .ok_or_else(|| eyre!("Backup '{}' has no file name", backup_path.display()))?;
+    let kept_path = backup_path.with_file_name(format!(
+        "{}{}.{}",
+        file_stem.to_string_lossy(),
+        KEPT_SUFFIX,
+        BACKUP_EXTENSION
+    ));
+    fs::rename(backup_path, &kept_path)
+        .wrap_err(format!("Unable to keep backup '{}'", backup_path.display()))?;
+    Ok(kept_path)
+}
+
+/// The backups in the folder, oldest first. Other files in the folder are left out.
+pub fn list(folder: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}-", constants::APP_NAME);
+    let mut backups: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            file_name.starts_with(&prefix)
+                && path
+                    .extension()
+                    .map(|extension| extension == BACKUP_EXTENSION)
+                    .unwrap_or(false)
+        })
+        .collect();
+    backups.sort();
+    backups
+}
+
+fn is_kept(backup_path: &Path) -> bool {
+    backup_path
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().ends_with(KEPT_SUFFIX))
+        .unwrap_or(false)
+}
+
+fn remove_old(folder: &Path, keep: usize) {
+    let backups: Vec<PathBuf> = list(folder)
+        .into_iter()
+        .filter(|backup| !is_kept(backup))
+        .collect();
+    for backup in &backups[..backups.len().saturating_sub(keep)] {
+        match fs::remove_file(backup) {
+            Ok(_) => debug!("Removed old backup '{}'", backup.display()),
+            Err(error) => warn!(
+                "Unable to remove old backup '{}' - {}",
+                backup.display(),
+                error
+            ),
+        }
+    }
+}
+
+/// A backup's path, or the file name of one in the backups folder.
+pub fn find(backup_file: &str) -> Result<PathBuf, Report> {
+    let backup_path = Path::new(backup_file);
+    if backup_path.is_file() {
+        return Ok(backup_path.to_path_buf());
+    }
+    let backup_path = get_backups_folder().join(backup_file);
+    match backup_path.is_file() {
+        true => Ok(backup_path),
+        false => Err(eyre!(
+            "Unable to find backup '{}', in the current folder or '{}'",
+            backup_file,
+            get_backups_folder().display()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use chrono::{Local, TimeZone};
+    use uuid::Uuid;
+
+    use crate::backup::{get_backup_file_name, keep, list, remove_old, BackupReason};
+
+    #[test]
+    fn get_backup_file_name_sorts_by_time() {
+        // Arrange
+        let earlier = Local.with_ymd_and_hms(2024, 8, 9, 9, 30, 0).unwrap();
+        let later = Local.with_ymd_and_hms(2024, 8, 10, 8, 0, 0).unwrap();
+
+        // Act
+        let earlier_name = get_backup_file_name(&BackupReason::Reset, earlier);
+        let later_name = get_backup_file_name(&BackupReason::Apply, later);
+
+        // Assert
+        assert_eq!(earlier_name, "app-lemonator-2024-08-09-093000-000-reset.db");
+        assert!(earlier_name < later_name);
+    }
+
+    #[test]
+    fn remove_old_keeps_newest_and_kept_backups() {
+        // Arrange
+        let folder = env::temp_dir().join(format!("app-lemonator-backups-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        for day in 1..=4 {
+            let time = Local.with_ymd_and_hms(2024, 8, day, 12, 0, 0).unwrap();
+            fs::write(
+                folder.join(get_backup_file_name(&BackupReason::Import, time)),
+                "",
+            )
+            .unwrap();
+        }
+        keep(&list(&folder)[0]).unwrap();
+        fs::write(folder.join("notes.txt"), "").unwrap();
+
+        // Act
+        remove_old(&folder, 2);
+
+        // Assert
+        let actual: Vec<String> = list(&folder)
+            .iter()
+            .map(|backup| backup.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            actual,
+            [
+                "app-lemonator-2024-08-01-120000-000-import-kept.db",
+                "app-lemonator-2024-08-03-120000-000-import.db",
+                "app-lemonator-2024-08-04-120000-000-import.db",
+            ]
+        );
+        assert!(folder.join("notes.txt").exists());
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use plist::Value;
+
+use crate::{finder, paths};
+
+/// Folders macOS installs app bundles into.
+const BUNDLE_FOLDERS: [&str; 3] = ["/Applications", "~/Applications", "/System/Applications"];
+
+pub fn get_bundle_folders() -> Vec<String> {
+    BUNDLE_FOLDERS
+        .iter()
+        .map(|folder| paths::resolve_path(folder))
+        .collect()
+}
+
+/// Lists the app bundles in the folders, including those one folder down like
+/// /Applications/Utilities.
+pub fn find_bundles(folders: &[String]) -> Vec<PathBuf> {
+    let mut bundles = Vec::new();
+
+    for folder in folders {
+        let Ok(entries) = fs::read_dir(folder) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+
+        for path in paths {
+            if is_bundle(&path) {
+                bundles.push(path);
+            } else if path.is_dir() {
+                let Ok(sub_entries) = fs::read_dir(&path) else {
+                    continue;
+                };
+                let mut sub_paths: Vec<PathBuf> = sub_entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|sub_path| is_bundle(sub_path))
+                    .collect();
+                sub_paths.sort();
+                bundles.extend(sub_paths);
+            }
+        }
+    }
+
+    bundles
+}
+
+pub fn is_bundle(path: &Path) -> bool {
+    path.is_dir()
+        && path
+            .extension()
+            .map(|extension| extension.eq_ignore_ascii_case("app"))
+            .unwrap_or(false)
+}
+
+fn read_info_plist(bundle: &Path) -> Option<plist::Dictionary> {
+    let plist_path = bundle.join("Contents").join("Info.plist");
+    let file = File::open(&plist_path).ok()?;
+    match plist::from_reader::<_, Value>(file) {
+        Ok(value) => value.into_dictionary(),
+        Err(e) => {
+            debug!("Unable to read '{}': {:?}", plist_path.display(), e);
+            None
+        }
+    }
+}
+
+fn get_plist_string(info_plist: &plist::Dictionary, key: &str) -> Option<String> {
+    info_plist
+        .get(key)
+        .and_then(|value| value.as_string())
+        .map(|value| value.to_string())
+}
+
+pub fn get_bundle_identifier(bundle: &Path) -> Option<String> {
+    read_info_plist(bundle)
+        .and_then(|info_plist| get_plist_string(&info_plist, "CFBundleIdentifier"))
+}
+
+pub fn get_bundle_executable(bundle: &Path) -> Option<String> {
+    read_info_plist(bundle)
+        .and_then(|info_plist| get_plist_string(&info_plist, "CFBundleExecutable"))
+}
+
+/// The icon file in the bundle's Resources folder. Info.plist can leave off the extension.
+pub fn get_bundle_icon_path(bundle: &Path) -> Option<PathBuf> {
+    let icon_file = read_info_plist(bundle)
+        .and_then(|info_plist| get_plist_string(&info_plist, "CFBundleIconFile"))?;
+    let mut icon_path = bundle.join("Contents").join("Resources").join(icon_file);
+    if icon_path.extension().is_none() {
+        icon_path.set_extension("icns");
+    }
+    Some(icon_path)
+}
+
+/// App bundles are folders, the executable is named in Info.plist. Anything else is the
+/// executable itself.
+pub fn get_executable_path(path: &Path) -> Option<PathBuf> {
+    if !path.is_dir() {
+        return Some(path.to_path_buf());
+    }
+    let executable = get_bundle_executable(path)?;
+    Some(path.join("Contents").join("MacOS").join(executable))
+}
+
+/// Finds the bundle with the bundle identifier, ignoring case. When there is more than one, the
+/// highest version wins.
+pub fn find_bundle_by_id(folders: &[String], bundle_id: &str) -> Option<String> {
+    let mut matches: Vec<(PathBuf, (u32, u32, u32, u32))> = find_bundles(folders)
+        .into_iter()
+        .filter_map(|bundle| {
+            let info_plist = read_info_plist(&bundle)?;
+            let identifier = get_plist_string(&info_plist, "CFBundleIdentifier")?;
+            if !identifier.eq_ignore_ascii_case(bundle_id) {
+                return None;
+            }
+            let version = get_plist_string(&info_plist, "CFBundleShortVersionString")
+                .and_then(|version| finder::parse_file_version_to_i32(&version))
+                .unwrap_or_default();
+            Some((bundle, version))
+        })
+        .collect();
+    debug!("Bundles matching '{}' - {:?}", bundle_id, matches);
+
+    // Stable sort, so for the same version the first folder searched wins
+    matches.sort_by_key(|(_, version)| std::cmp::Reverse(*version));
+    matches
+        .into_iter()
+        .next()
+        .map(|(bundle, _)| bundle.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::bundle::{find_bundle_by_id, find_bundles, get_bundle_identifier};
+
+    fn get_fixture_folders() -> Vec<String> {
+        ["Applications", "UserApplications"]
+            .iter()
+            .map(|folder| {
+                format!(
+                    "{}/tests/fixtures/bundles/{}",
+                    env!("CARGO_MANIFEST_DIR"),
+                    folder
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_bundles_includes_sub_folders() {
+        // Arrange
+        let folders = get_fixture_folders();
+
+        // Act
+        let actual: Vec<String> = find_bundles(&folders)
+            .iter()
+            .filter_map(|bundle| bundle.file_name())
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .collect();
+
+        // Assert
+        assert_eq!(
+            actual,
+            vec![
+                "Broken.app",
+                "Lemon Editor.app",
+                "Lime.app",
+                "Lemon Editor Beta.app"
+            ]
+        );
+    }
+
+    #[test]
+    fn find_bundle_by_id_highest_version() {
+        // Arrange
+        let folders = get_fixture_folders();
+
+        // Act
+        let actual = find_bundle_by_id(&folders, "com.example.lemoneditor").unwrap();
+
+        // Assert
+        assert!(actual.ends_with("Lemon Editor Beta.app"));
+    }
+
+    #[test]
+    fn find_bundle_by_id_in_sub_folder() {
+        // Arrange
+        let folders = get_fixture_folders();
+
+        // Act
+        let actual = find_bundle_by_id(&folders, "com.example.Lime").unwrap();
+
+        // Assert
+        assert!(Path::new(&actual).ends_with("Utilities/Lime.app"));
+    }
+
+    #[test]
+    fn find_bundle_by_id_no_match() {
+        // Arrange
+        let folders = get_fixture_folders();
+
+        // Act
+        let actual = find_bundle_by_id(&folders, "com.example.Missing");
+
+        // Assert
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn get_bundle_identifier_missing_info_plist() {
+        // Arrange
+        let bundle = format!(
+            "{}/tests/fixtures/bundles/Applications/Broken.app",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        // Act
+        let actual = get_bundle_identifier(Path::new(&bundle));
+
+        // Assert
+        assert_eq!(actual, None);
+    }
+}
@@ -0,0 +1,33 @@
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+};
+
+use log::{error, warn};
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static INSTALL_HANDLER: Once = Once::new();
+
+/// Catches Ctrl-C so long running searches can stop cleanly and keep what they have done so far.
+/// Pressing Ctrl-C a second time exits straight away.
+pub fn install_handler() {
+    INSTALL_HANDLER.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            if CANCELLED.swap(true, Ordering::SeqCst) {
+                process::exit(130);
+            }
+            warn!("Cancelling... press Ctrl-C again to exit immediately.");
+        });
+
+        if let Err(error) = result {
+            error!("Failed to set Ctrl-C handler: {:?}", error);
+        }
+    });
+}
+
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
@@ -3,6 +3,7 @@ use std::env;
 use clap::Parser;
 
 use crate::actions;
+use crate::backup;
 use crate::constants;
 use crate::data;
 
@@ -28,21 +29,75 @@ pub enum Action {
         app_name: String,
     },
 
+    /// Closes a running app. Asks the app to close, then kills it if --force is passed.
+    #[clap(short_flag = 'c')]
+    Close {
+        /// Nice name of app to close.
+        app_name: String,
+
+        /// Force kill the app if it has not closed before the timeout.
+        #[arg(long, default_value = "false")]
+        force: bool,
+
+        /// Seconds to wait for the app to close.
+        #[arg(long, default_value = "10")]
+        timeout: u64,
+    },
+
     /// Adds an app to the database.
     #[clap(short_flag = 'a')]
     Add {
-        /// Nice name for app.
-        app_name: String,
+        /// Nice name for app. With --from-lnk, defaults to the shortcut name.
+        #[arg(required_unless_present = "from_lnk")]
+        app_name: Option<String>,
         /// Executable to find and run. For Shortcuts, can be full path and exe.
-        exe_name: String,
+        #[arg(required_unless_present = "from_lnk", conflicts_with = "from_lnk")]
+        exe_name: Option<String>,
         /// Search text for app.
-        search_term: String,
+        #[arg(required_unless_present = "from_lnk", conflicts_with = "from_lnk")]
+        search_term: Option<String>,
         /// Search method to find app.
         #[clap(value_enum)]
-        search_method: data::SearchMethod,
+        #[arg(required_unless_present = "from_lnk", conflicts_with = "from_lnk")]
+        search_method: Option<data::SearchMethod>,
+        /// Adds the app from a Windows shortcut (.lnk) file, using its arguments as params.
+        #[arg(long)]
+        from_lnk: Option<String>,
         /// Parameters to pass to app.
         #[arg(long)]
         params: Option<Vec<String>>,
+        /// FolderSearch only. How many folders deep to search.
+        #[arg(long)]
+        max_depth: Option<u32>,
+        /// FolderSearch only. Folder or file name to skip, glob patterns allowed. Can be repeated.
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+        /// FolderSearch only. Follow symbolic links when searching.
+        #[arg(long, default_value = "false")]
+        follow_symlinks: bool,
+        /// FolderSearch only. Stop searching after this many matches.
+        #[arg(long)]
+        max_matches: Option<u32>,
+        /// FolderSearch only. Seconds to search for before using the matches found so far.
+        #[arg(long)]
+        time_budget: Option<u32>,
+        /// FolderSearch only. Which build to use when builds for more than one architecture are found.
+        #[clap(value_enum)]
+        #[arg(long, default_value = "native")]
+        arch: data::ArchPreference,
+        /// Search to try when the search method finds nothing, as method:term. Can be repeated, tried in order.
+        #[arg(long, value_parser = data::parse_search_strategy)]
+        fallback: Option<Vec<data::SearchStrategy>>,
+        /// What to do when the file fails its checks before opening.
+        #[clap(value_enum)]
+        #[arg(long, default_value = "off")]
+        verify: data::VerifyPolicy,
+        /// Authenticode signer on Windows, or codesign team ID on macOS, the file must be signed by.
+        #[arg(long)]
+        signer: Option<String>,
+        /// How to find the app on another operating system, as os:method:term. Can be repeated.
+        #[arg(long, value_parser = data::parse_variant)]
+        variant: Option<Vec<data::AppVariant>>,
     },
 
     /// Edits individual properties of an app in the database.
@@ -67,6 +122,47 @@ pub enum Action {
         /// Parameters to pass to app.
         #[arg(long, group = "edit")]
         params: Option<Vec<String>>,
+        /// FolderSearch only. How many folders deep to search, 0 searches the base folder only.
+        #[arg(long, group = "edit")]
+        max_depth: Option<u32>,
+        /// FolderSearch only. Removes the max depth, so the whole folder is searched.
+        #[arg(long, group = "edit", conflicts_with = "max_depth")]
+        clear_max_depth: bool,
+        /// FolderSearch only. Folder or file name to skip, glob patterns allowed. Can be repeated.
+        #[arg(long, group = "edit")]
+        exclude: Option<Vec<String>>,
+        /// FolderSearch only. Follow symbolic links when searching.
+        #[arg(long, group = "edit")]
+        follow_symlinks: Option<bool>,
+        /// FolderSearch only. Stop searching after this many matches, 0 removes the limit.
+        #[arg(long, group = "edit")]
+        max_matches: Option<u32>,
+        /// FolderSearch only. Seconds to search for, 0 removes the limit.
+        #[arg(long, group = "edit")]
+        time_budget: Option<u32>,
+        /// FolderSearch only. Which build to use when builds for more than one architecture are found.
+        #[clap(value_enum)]
+        #[arg(long, group = "edit")]
+        arch: Option<data::ArchPreference>,
+        /// Replaces the fallback searches, as method:term. Can be repeated, tried in order.
+        #[arg(long, group = "edit", value_parser = data::parse_search_strategy)]
+        fallback: Option<Vec<data::SearchStrategy>>,
+        /// Removes all fallback searches.
+        #[arg(long, group = "edit", conflicts_with = "fallback")]
+        clear_fallbacks: bool,
+        /// What to do when the file fails its checks before opening.
+        #[clap(value_enum)]
+        #[arg(long, group = "edit")]
+        verify: Option<data::VerifyPolicy>,
+        /// Signer the file must be signed by, an empty value removes it.
+        #[arg(long, group = "edit")]
+        signer: Option<String>,
+        /// Replaces the variants for other operating systems, as os:method:term. Can be repeated.
+        #[arg(long, group = "edit", value_parser = data::parse_variant)]
+        variant: Option<Vec<data::AppVariant>>,
+        /// Removes all variants for other operating systems.
+        #[arg(long, group = "edit", conflicts_with = "variant")]
+        clear_variants: bool,
     },
 
     /// Deletes the app from the database.
@@ -82,6 +178,50 @@ pub enum Action {
         /// Force update of all apps in database and skip confirmation prompt.
         #[arg(long, default_value = "false")]
         force: bool,
+
+        /// Number of apps to search for at the same time.
+        #[arg(long, default_value = "4")]
+        jobs: usize,
+    },
+
+    /// Updates the app path only for apps that have not been updated recently.
+    Refresh {
+        /// Refresh apps last updated longer ago than this, like 12h, 7d or 2w.
+        #[arg(long, default_value = "7d", value_parser = data::parse_duration)]
+        stale_after: chrono::Duration,
+
+        /// Number of apps to search for at the same time.
+        #[arg(long, default_value = "4")]
+        jobs: usize,
+    },
+
+    /// Generates a scheduled task that runs refresh in the background.
+    Schedule {
+        /// Scheduler to generate the task for.
+        #[clap(value_enum)]
+        scheduler: data::ScheduleType,
+
+        /// How often the scheduled task runs, like 12h or 1d.
+        #[arg(long, default_value = "1d", value_parser = data::parse_duration)]
+        every: chrono::Duration,
+
+        /// Refresh apps last updated longer ago than this, like 12h, 7d or 2w.
+        #[arg(long, default_value = "7d", value_parser = data::parse_duration)]
+        stale_after: chrono::Duration,
+
+        /// Folder to save the files to. Can be left blank, app will save to Documents folder.
+        folder_out: Option<String>,
+
+        /// Force overwrite of existing files and skip confirmation prompt.
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+
+    /// Refreshes the index of executables in the base folders of FolderSearch apps.
+    Index {
+        /// Clear the index instead of refreshing it.
+        #[arg(long, default_value = "false")]
+        clear: bool,
     },
 
     /// Lists all apps in the database. Pass app name for full app details.
@@ -95,15 +235,97 @@ pub enum Action {
         full: bool,
     },
 
+    /// Shows which apps are running, with their PIDs, uptime and memory use.
+    #[clap(short_flag = 's')]
+    Status {
+        /// Output the status as JSON.
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+
+    /// Checks apps are healthy without opening them. No app named means all in database.
+    #[clap(short_flag = 'k', visible_alias = "doctor")]
+    Check {
+        /// App name to check.
+        app_name: Option<String>,
+    },
+
+    /// Extracts the app's icon as a PNG, for Stream Deck keys or anything else that shows apps.
+    Icon {
+        /// App name to get the icon for.
+        app_name: String,
+
+        /// File to save the icon to. Can be left blank, the cached icon's path is shown.
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Finds apps installed on this machine that aren't in the database, and adds the ones picked.
+    Discover {
+        /// Only list the apps found, without adding any.
+        #[arg(long, default_value = "false")]
+        list: bool,
+    },
+
     /// Resets the database.
     #[clap(short_flag = 'r')]
     Reset {
         /// Force database reset and skip confirmation prompt.
         #[arg(long, default_value = "false")]
         force: bool,
+
+        /// Keep the backup taken before resetting, instead of letting newer backups replace it.
+        #[arg(long, default_value = "false")]
+        keep_backup: bool,
+    },
+
+    /// Backs up the database, safely even while it is in use.
+    Backup {
+        /// File name to back up to. Can be left blank, app will save to its backups folder.
+        file_out: Option<String>,
+
+        /// Force backup to overwrite existing file and skip confirmation prompt.
+        #[arg(long, default_value = "false")]
+        force: bool,
+
+        /// Backups to keep in the backups folder, the oldest are removed.
+        #[arg(long, default_value_t = backup::BACKUPS_KEPT)]
+        keep: usize,
+
+        /// Lists the backups in the backups folder, instead of backing up.
+        #[arg(long, default_value = "false", conflicts_with_all = ["file_out", "force"])]
+        list: bool,
     },
 
-    /// Exports the database to a JSON file.
+    /// Replaces the database with a backup. The database is backed up first.
+    Restore {
+        /// Backup file, or the name of one in the backups folder.
+        backup_file: String,
+
+        /// Skip the confirmation prompt.
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+
+    /// Makes the database match a TOML or YAML manifest of apps, showing the plan first.
+    Apply {
+        /// Manifest file, ending .toml, .yaml or .yml.
+        manifest: String,
+
+        /// Remove apps that aren't in the manifest.
+        #[arg(long, default_value = "false")]
+        prune: bool,
+
+        /// Shows the plan, without changing the database.
+        #[arg(long, default_value = "false")]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt.
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+
+    /// Exports the database to a JSON or CSV file, or a Stream Deck profile.
     #[clap(short_flag = 'x')]
     Export {
         /// File name to export to. Can be left blank, app will save to Documents folder.
@@ -112,40 +334,120 @@ pub enum Action {
         /// Force export to overwrite existing file and skip confirmation prompt.
         #[arg(long, default_value = "false")]
         force: bool,
+
+        /// Include when each app was last opened. JSON only.
+        #[arg(long, default_value = "false")]
+        include_history: bool,
+
+        /// File format to export to.
+        #[clap(value_enum)]
+        #[arg(long, default_value = "json")]
+        format: data::ExportFormat,
+
+        /// Only export this app. Can be repeated, apps are exported in the order given.
+        #[arg(long)]
+        app: Option<Vec<String>>,
+
+        /// Put each app's icon on its key. Stream Deck only.
+        #[arg(long, default_value = "false")]
+        icons: bool,
     },
 
-    /// Imports a JSON file to the database. Existing records will be skipped.
+    /// Imports a JSON or CSV file, or another launcher's config, to the database. What happens to
+    /// apps that already exist is set by --on-conflict.
     #[clap(short_flag = 'i')]
     Import {
-        /// File name to import from.
+        /// File name to import from, or a folder when importing from another launcher.
         file_in: String,
+        /// Reads the apps from another launcher's config, instead of an export.
+        #[clap(value_enum)]
+        #[arg(long)]
+        from: Option<data::Launcher>,
+        /// What to do with apps that already exist.
+        #[clap(value_enum)]
+        #[arg(long, default_value = "skip")]
+        on_conflict: data::ImportConflict,
+        /// Shows what would change, without changing the database.
+        #[arg(long, default_value = "false")]
+        dry_run: bool,
     },
 }
 
 pub fn run_cli_action(args: Args) -> Result<String, eyre::Report> {
     // If we are not resetting the database, make sure it exists and is ready to use
     match args.action {
-        Action::Reset { force: _ } => {}
+        Action::Reset { .. } => {}
         _ => {
             actions::create_db()?;
         }
     }
 
     match args.action {
-        Action::Open { app_name } => Ok(actions::open_app(&app_name)?),
+        Action::Open { app_name } => Ok(actions::open_app(&app_name, get_operating_system())?),
+        Action::Close {
+            app_name,
+            force,
+            timeout,
+        } => Ok(actions::close_app(&app_name, force, timeout)?),
+        Action::Add {
+            app_name,
+            from_lnk: Some(lnk_file),
+            params,
+            fallback,
+            verify,
+            signer,
+            variant,
+            ..
+        } => Ok(actions::add_app_from_lnk(
+            lnk_file,
+            app_name,
+            params.map(|p| p.join(" ")),
+            get_operating_system(),
+            fallback.unwrap_or_default(),
+            data::VerifyOptions {
+                verify_policy: verify,
+                verify_signer: signer,
+            },
+            variant.unwrap_or_default(),
+        )?),
         Action::Add {
             app_name,
             exe_name,
             params,
             search_term,
             search_method,
+            max_depth,
+            exclude,
+            follow_symlinks,
+            max_matches,
+            time_budget,
+            arch,
+            fallback,
+            verify,
+            signer,
+            variant,
+            ..
         } => Ok(actions::add_app(
-            app_name,
-            exe_name,
+            app_name.unwrap_or_default(),
+            exe_name.unwrap_or_default(),
             params.map(|p| p.join(" ")),
-            search_term,
-            search_method,
+            search_term.unwrap_or_default(),
+            search_method.unwrap_or(data::SearchMethod::Shortcut),
             get_operating_system(),
+            data::SearchOptions {
+                search_max_depth: max_depth,
+                search_excludes: exclude.map(|e| e.join(";")),
+                search_follow_symlinks: follow_symlinks,
+                search_max_matches: max_matches,
+                search_time_budget: time_budget,
+                search_arch: arch,
+            },
+            fallback.unwrap_or_default(),
+            data::VerifyOptions {
+                verify_policy: verify,
+                verify_signer: signer,
+            },
+            variant.unwrap_or_default(),
         )?),
         Action::Edit {
             lookup_app_name,
@@ -154,6 +456,19 @@ pub fn run_cli_action(args: Args) -> Result<String, eyre::Report> {
             params,
             search_term,
             search_method,
+            max_depth,
+            clear_max_depth,
+            exclude,
+            follow_symlinks,
+            max_matches,
+            time_budget,
+            arch,
+            fallback,
+            clear_fallbacks,
+            verify,
+            signer,
+            variant,
+            clear_variants,
         } => Ok(actions::edit_app(
             lookup_app_name,
             app_name,
@@ -161,9 +476,56 @@ pub fn run_cli_action(args: Args) -> Result<String, eyre::Report> {
             params.map(|p| p.join(" ")),
             search_term,
             search_method,
+            actions::SearchOptionsEdit {
+                max_depth,
+                clear_max_depth,
+                excludes: exclude.map(|e| e.join(";")),
+                follow_symlinks,
+                max_matches,
+                time_budget,
+                arch,
+            },
+            match clear_fallbacks {
+                true => Some(Vec::new()),
+                false => fallback,
+            },
+            actions::VerifyOptionsEdit {
+                policy: verify,
+                signer,
+            },
+            match clear_variants {
+                true => Some(Vec::new()),
+                false => variant,
+            },
         )?),
         Action::Delete { app_name } => Ok(actions::delete_app(&app_name)?),
-        Action::Update { app_name, force } => Ok(actions::update_app(app_name, force)?),
+        Action::Update {
+            app_name,
+            force,
+            jobs,
+        } => Ok(actions::update_app(
+            app_name,
+            force,
+            jobs,
+            get_operating_system(),
+        )?),
+        Action::Refresh { stale_after, jobs } => {
+            Ok(actions::refresh(stale_after, jobs, get_operating_system())?)
+        }
+        Action::Schedule {
+            scheduler,
+            every,
+            stale_after,
+            folder_out,
+            force,
+        } => Ok(actions::schedule(
+            scheduler,
+            every,
+            stale_after,
+            folder_out,
+            force,
+        )?),
+        Action::Index { clear } => Ok(actions::index(clear)?),
         Action::List { app_name, full } => {
             let list_type = match full {
                 true => actions::ListType::Full,
@@ -171,12 +533,63 @@ pub fn run_cli_action(args: Args) -> Result<String, eyre::Report> {
             };
             Ok(actions::list_app(app_name, list_type)?)
         }
-        Action::Reset { force } => Ok(actions::reset(force)?),
+        Action::Status { json } => {
+            let output_type = match json {
+                true => actions::OutputType::Json,
+                false => actions::OutputType::Table,
+            };
+            Ok(actions::status(output_type)?)
+        }
+        Action::Check { app_name } => Ok(actions::check_apps(app_name, get_operating_system())?),
+        Action::Icon { app_name, out } => Ok(actions::icon(&app_name, out)?),
+        Action::Discover { list } => Ok(actions::discover(get_operating_system(), list)?),
+        Action::Reset { force, keep_backup } => Ok(actions::reset(force, keep_backup)?),
+        Action::Backup {
+            file_out,
+            force,
+            keep,
+            list,
+        } => Ok(actions::backup(file_out, force, keep, list)?),
+        Action::Restore { backup_file, force } => Ok(actions::restore(&backup_file, force)?),
+        Action::Apply {
+            manifest,
+            prune,
+            dry_run,
+            force,
+        } => Ok(actions::apply(
+            manifest,
+            get_operating_system(),
+            prune,
+            dry_run,
+            force,
+        )?),
         Action::Export {
             file_out: file,
             force,
-        } => Ok(actions::export(file, force)?),
-        Action::Import { file_in: file } => Ok(actions::import(file)?),
+            include_history,
+            format,
+            app,
+            icons,
+        } => Ok(actions::export(
+            file,
+            force,
+            include_history,
+            format,
+            app,
+            icons,
+        )?),
+        Action::Import {
+            file_in: file,
+            from,
+            on_conflict,
+            dry_run,
+        } => Ok(actions::import(
+            file,
+            from,
+            on_conflict,
+            dry_run,
+            get_operating_system(),
+        )?),
     }
 }
 
@@ -184,6 +597,7 @@ fn get_operating_system() -> data::OperatingSystem {
     match env::consts::OS {
         constants::OS_WINDOWS => data::OperatingSystem::Windows,
         constants::OS_MACOS => data::OperatingSystem::MacOS,
+        constants::OS_LINUX => data::OperatingSystem::Linux,
         _ => data::OperatingSystem::Unknown,
     }
 }
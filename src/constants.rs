@@ -6,3 +6,4 @@ pub const CRATE_AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
 
 pub const OS_MACOS: &str = "macos";
 pub const OS_WINDOWS: &str = "windows";
+pub const OS_LINUX: &str = "linux";
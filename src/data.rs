@@ -1,4 +1,6 @@
-use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Duration, Local, Timelike, Utc};
 use clap::ValueEnum;
 use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
@@ -7,7 +9,7 @@ use strum_macros::Display;
 use strum_macros::EnumString;
 use tabled::Tabled;
 
-use crate::finder;
+use crate::{finder, paths};
 
 #[derive(Clone, Debug)]
 pub struct FileVersion {
@@ -17,6 +19,11 @@ pub struct FileVersion {
     pub minor: u32,
     pub build: u32,
     pub revision: u32,
+    /// The search strategy that found the file, when it was found by a search.
+    pub matched_strategy: Option<SearchStrategy>,
+    pub metadata: FileMetadata,
+    /// SHA-256 of the file, when it has been hashed.
+    pub sha256: Option<String>,
 }
 
 impl FileVersion {
@@ -29,6 +36,9 @@ impl FileVersion {
                 minor,
                 build,
                 revision,
+                matched_strategy: None,
+                metadata: FileMetadata::default(),
+                sha256: None,
             },
             None => Self {
                 app_description,
@@ -37,10 +47,18 @@ impl FileVersion {
                 minor: 0,
                 build: 0,
                 revision: 0,
+                matched_strategy: None,
+                metadata: FileMetadata::default(),
+                sha256: None,
             },
         }
     }
 
+    pub fn is_older_than(&self, other: &FileVersion) -> bool {
+        (self.major, self.minor, self.build, self.revision)
+            < (other.major, other.minor, other.build, other.revision)
+    }
+
     pub fn display_version(&self) -> String {
         format!(
             "{}.{}.{}.{}",
@@ -62,6 +80,15 @@ pub enum SearchMethod {
     /// Just runs the app directly. No lookups, you provide the full path.
     #[value(alias("Shortcut"))]
     Shortcut,
+    /// Reads the app path from a Windows shortcut (.lnk) file, named by the exe name.
+    #[value(alias("LnkFile"))]
+    LnkFile,
+    /// Finds a macOS app bundle by its bundle identifier, like com.google.Chrome.
+    #[value(alias("BundleId"))]
+    BundleId,
+    /// Finds the program named by the search term in the folders on the PATH.
+    #[value(alias("PathLookup"))]
+    PathLookup,
 }
 
 #[derive(
@@ -74,19 +101,162 @@ pub enum OperatingSystem {
     /// MacOs
     #[value(alias("MacOS"))]
     MacOS,
+    /// Linux
+    #[value(alias("Linux"))]
+    Linux,
     /// Unknown! Sorry Linux.
     #[value(alias("Unknown"))]
     Unknown,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, FromRow, Tabled)]
+/// Which build of an app to use, when a folder search finds builds for more than one architecture.
+#[derive(
+    ValueEnum,
+    Clone,
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Display,
+    EnumString,
+    PartialEq,
+    sqlx::Type,
+)]
+pub enum ArchPreference {
+    /// The build for this machine, or one it can emulate.
+    #[default]
+    #[value(alias("Native"))]
+    Native,
+    /// 64-bit Intel and AMD builds.
+    #[value(alias("X64"))]
+    X64,
+    /// 64-bit ARM builds.
+    #[value(alias("Arm64"))]
+    Arm64,
+    /// Whichever build has the highest version.
+    #[value(alias("Any"))]
+    Any,
+}
+
+/// What to do when the file about to be opened fails its checks.
+#[derive(
+    ValueEnum,
+    Clone,
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Display,
+    EnumString,
+    PartialEq,
+    sqlx::Type,
+)]
+pub enum VerifyPolicy {
+    /// Open the app without checking the file.
+    #[default]
+    #[value(alias("Off"))]
+    Off,
+    /// Open the app, but warn about the failed checks.
+    #[value(alias("Warn"))]
+    Warn,
+    /// Don't open the app.
+    #[value(alias("Refuse"))]
+    Refuse,
+}
+
+/// What to do when an imported app has the same name as an app in the database.
+#[derive(ValueEnum, Clone, Debug, Default, Display, PartialEq)]
+pub enum ImportConflict {
+    /// Keep the app in the database.
+    #[default]
+    #[value(alias("Skip"))]
+    Skip,
+    /// Replace the app in the database with the imported app.
+    #[value(alias("Overwrite"))]
+    Overwrite,
+    /// Fill the empty settings of the app in the database from the imported app.
+    #[value(alias("Merge"))]
+    Merge,
+    /// Add the imported app with a number after its name.
+    #[value(alias("Rename"))]
+    Rename,
+}
+
+/// Another launcher to import apps from.
+#[derive(ValueEnum, Clone, Debug, Display, PartialEq)]
+pub enum Launcher {
+    /// Stream Deck profile manifest.json or .streamDeckProfile, or a folder of profiles.
+    #[value(alias("StreamDeck"))]
+    StreamDeck,
+    /// Folder of Windows shortcut (.lnk) files, like the Start Menu.
+    #[value(alias("LnkFolder"))]
+    LnkFolder,
+    /// Freedesktop .desktop file, or a folder of them.
+    #[value(alias("Desktop"))]
+    Desktop,
+    /// Alfred or Raycast style JSON list of apps.
+    #[value(alias("Json"), alias("alfred"), alias("raycast"))]
+    Json,
+}
+
+impl Launcher {
+    /// The end of the file names the launcher keeps its apps in.
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            Launcher::StreamDeck => "manifest.json",
+            Launcher::LnkFolder => ".lnk",
+            Launcher::Desktop => ".desktop",
+            Launcher::Json => ".json",
+        }
+    }
+}
+
+/// File format to export apps to.
+#[derive(ValueEnum, Clone, Debug, Default, Display, PartialEq)]
+pub enum ExportFormat {
+    /// JSON, which can hold everything about an app, including its history.
+    #[default]
+    #[value(alias("Json"))]
+    Json,
+    /// CSV with a header row, for editing in a spreadsheet.
+    #[value(alias("Csv"))]
+    Csv,
+    /// Stream Deck profile with a key that opens each app.
+    #[value(alias("StreamDeck"), alias("stream-deck"))]
+    Streamdeck,
+}
+
+impl ExportFormat {
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => ".json",
+            ExportFormat::Csv => ".csv",
+            ExportFormat::Streamdeck => ".streamDeckProfile",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug, Display, PartialEq)]
+pub enum ScheduleType {
+    /// systemd user service and timer, for Linux.
+    #[value(alias("Systemd"))]
+    Systemd,
+    /// Windows Task Scheduler XML, import with schtasks.
+    #[value(alias("TaskScheduler"))]
+    TaskScheduler,
+    /// launchd agent plist, for macOS.
+    #[value(alias("Launchd"))]
+    Launchd,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow, Tabled)]
 pub struct App {
     #[serde(skip)]
     #[tabled(skip)]
     pub id: i32,
     #[tabled(rename = "App Name")]
     pub app_name: String,
-    #[tabled(rename = "Exe Name")]
+    #[tabled(rename = "Exe Name", display_with("Self::display_exe_name", self))]
     pub exe_name: String,
     #[tabled(rename = "Params", display_with = "display_option_string")]
     pub params: Option<String>,
@@ -100,6 +270,10 @@ pub struct App {
     pub app_description: Option<String>,
     #[tabled(rename = "App Version", display_with = "display_option_string")]
     pub app_version: Option<String>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    #[tabled(inline)]
+    pub file_metadata: FileMetadata,
     #[serde(skip)]
     #[tabled(
         rename = "Last Opened",
@@ -114,6 +288,196 @@ pub struct App {
     pub last_updated: Option<DateTime<Utc>>,
     #[tabled(rename = "Operating System")]
     pub operating_system: OperatingSystem,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    #[tabled(rename = "Search Options", display_with = "display_search_options")]
+    pub search_options: SearchOptions,
+    /// Strategies tried in order when the search method and search term find nothing.
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(rename = "Fallbacks", display_with = "display_search_strategies")]
+    pub fallback_strategies: Vec<SearchStrategy>,
+    #[tabled(
+        rename = "Matched Search",
+        display_with("Self::display_matched_strategy", self)
+    )]
+    pub matched_search_method: Option<SearchMethod>,
+    #[tabled(skip)]
+    pub matched_search_term: Option<String>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    #[tabled(rename = "Verify", display_with = "display_verify_options")]
+    pub verify_options: VerifyOptions,
+    /// SHA-256 of the executable, recorded when the app path is updated.
+    #[tabled(skip)]
+    pub app_sha256: Option<String>,
+    /// How to find the app on other operating systems, so one definition can be shared.
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(rename = "Variants", display_with = "display_variants")]
+    pub variants: Vec<AppVariant>,
+}
+
+/// How to find an app on one operating system.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct AppVariant {
+    pub operating_system: OperatingSystem,
+    /// Overrides the app's exe name, when it differs on this operating system.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe_name: Option<String>,
+    pub search_method: SearchMethod,
+    pub search_term: String,
+    /// Overrides the app's params, when they differ on this operating system.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<String>,
+}
+
+impl std::fmt::Display for AppVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} '{}'",
+            self.operating_system, self.search_method, self.search_term
+        )?;
+        match &self.exe_name {
+            Some(exe_name) => write!(f, " ({})", exe_name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A search method and search term pair, used to find an app.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct SearchStrategy {
+    pub search_method: SearchMethod,
+    pub search_term: String,
+}
+
+impl SearchStrategy {
+    pub fn new(search_method: SearchMethod, search_term: String) -> Self {
+        Self {
+            search_method,
+            search_term,
+        }
+    }
+}
+
+impl std::fmt::Display for SearchStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} '{}'", self.search_method, self.search_term)
+    }
+}
+
+/// Details read from the version resource of an exe on Windows, or Info.plist on macOS.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromRow, Tabled)]
+#[serde(default)]
+pub struct FileMetadata {
+    #[tabled(rename = "Publisher", display_with = "display_option_string")]
+    pub app_publisher: Option<String>,
+    #[tabled(rename = "Product Name", display_with = "display_option_string")]
+    pub app_product_name: Option<String>,
+    #[tabled(rename = "Bundle Id", display_with = "display_option_string")]
+    pub app_bundle_id: Option<String>,
+    #[tabled(rename = "Copyright", display_with = "display_option_string")]
+    pub app_copyright: Option<String>,
+    #[tabled(rename = "Architecture", display_with = "display_option_string")]
+    pub app_architecture: Option<String>,
+    #[tabled(rename = "Build Number", display_with = "display_option_string")]
+    pub app_build_number: Option<String>,
+}
+
+/// Checks made on the file before an app is opened.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(default)]
+pub struct VerifyOptions {
+    pub verify_policy: VerifyPolicy,
+    /// Authenticode signer on Windows, or codesign team ID on macOS, the file must be signed by.
+    pub verify_signer: Option<String>,
+}
+
+/// Limits for FolderSearch, so large folders can be searched without walking every file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub search_max_depth: Option<u32>,
+    /// Folder and file names to skip, as glob patterns separated by ';'.
+    pub search_excludes: Option<String>,
+    pub search_follow_symlinks: bool,
+    pub search_max_matches: Option<u32>,
+    /// Seconds to search for before giving up with the matches found so far.
+    pub search_time_budget: Option<u32>,
+    pub search_arch: ArchPreference,
+}
+
+impl SearchOptions {
+    pub fn get_excludes(&self) -> Vec<String> {
+        match &self.search_excludes {
+            Some(excludes) => excludes
+                .split(';')
+                .map(|e| e.trim().to_string())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct IndexedFolder {
+    pub folder_path: String,
+    /// Folders below the base folder, the base folder itself is 0.
+    pub depth: u32,
+    /// Last modified time in nanoseconds since the Unix epoch.
+    pub modified: i64,
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct IndexedFile {
+    pub folder_path: String,
+    pub file_path: String,
+    pub file_name: String,
+    pub depth: u32,
+}
+
+/// A folder whose contents changed since it was last indexed.
+#[derive(Clone, Debug)]
+pub struct IndexedFolderChange {
+    pub folder: IndexedFolder,
+    pub files: Vec<IndexedFile>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckResult {
+    Passed,
+    Failed(String),
+    Skipped,
+}
+
+impl CheckResult {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, CheckResult::Failed(_))
+    }
+
+    pub fn display(&self) -> String {
+        match self {
+            CheckResult::Passed => "OK".green().to_string(),
+            CheckResult::Failed(_) => "FAIL".red().to_string(),
+            CheckResult::Skipped => "N/A".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AppStatus {
+    pub app_name: String,
+    pub app_path: Option<String>,
+    pub path_exists: bool,
+    pub running: bool,
+    pub pids: Vec<u32>,
+    /// Seconds since the longest running process started.
+    pub uptime: Option<u64>,
+    /// Bytes of memory used across all processes.
+    pub memory: Option<u64>,
 }
 
 impl App {
@@ -135,9 +499,124 @@ impl App {
             app_path: None,
             app_description: None,
             app_version: None,
+            file_metadata: FileMetadata::default(),
             last_opened: None,
             last_updated: None,
             operating_system,
+            search_options: SearchOptions::default(),
+            fallback_strategies: Vec::new(),
+            matched_search_method: None,
+            matched_search_term: None,
+            verify_options: VerifyOptions::default(),
+            app_sha256: None,
+            variants: Vec::new(),
+        }
+    }
+
+    /// The search method and search term first, followed by any fallbacks.
+    pub fn get_search_strategies(&self) -> Vec<SearchStrategy> {
+        let mut strategies = vec![SearchStrategy::new(
+            self.search_method.clone(),
+            self.search_term.clone(),
+        )];
+        strategies.extend(self.fallback_strategies.iter().cloned());
+        strategies
+    }
+
+    /// Whether the other app is searched for the same way, so the path found for one is the path
+    /// of the other.
+    pub fn is_same_search(&self, other: &App) -> bool {
+        self.exe_name == other.exe_name
+            && self.search_term == other.search_term
+            && self.search_method == other.search_method
+            && self.operating_system == other.operating_system
+            && self.search_options == other.search_options
+            && self.fallback_strategies == other.fallback_strategies
+            && self.variants == other.variants
+    }
+
+    /// A copy of the app that searches with the given strategy.
+    pub fn with_search_strategy(&self, strategy: &SearchStrategy) -> App {
+        let mut app = self.clone();
+        app.search_method = strategy.search_method.clone();
+        app.search_term = strategy.search_term.clone();
+        app
+    }
+
+    /// The app as it should be found on the operating system. When there is a variant for it, the
+    /// variant's search replaces the app's, and the app's own search is kept as a variant for its
+    /// operating system. Only for searching, the fallbacks of the other operating system are
+    /// dropped so the result must not be saved.
+    pub fn for_operating_system(&self, operating_system: &OperatingSystem) -> App {
+        let Some(position) = self
+            .variants
+            .iter()
+            .position(|variant| variant.operating_system == *operating_system)
+        else {
+            return self.clone();
+        };
+
+        let mut app = self.clone();
+        let variant = app.variants.remove(position);
+        if self.operating_system != *operating_system {
+            app.variants.push(AppVariant {
+                operating_system: self.operating_system.clone(),
+                exe_name: Some(self.exe_name.clone()),
+                search_method: self.search_method.clone(),
+                search_term: self.search_term.clone(),
+                params: self.params.clone(),
+            });
+            // Fallbacks were for the other operating system
+            app.fallback_strategies = Vec::new();
+        }
+
+        app.operating_system = operating_system.clone();
+        if let Some(exe_name) = variant.exe_name {
+            app.exe_name = exe_name;
+        }
+        app.search_method = variant.search_method;
+        app.search_term = variant.search_term;
+        if variant.params.is_some() {
+            app.params = variant.params;
+        }
+        app
+    }
+
+    /// Fills the settings that are empty, or left at their defaults, from the other app. The
+    /// name, exe name and search are always kept.
+    pub fn fill_empty_from(&mut self, other: &App) {
+        if self.params.is_none() {
+            self.params = other.params.clone();
+        }
+
+        let options = &mut self.search_options;
+        let other_options = &other.search_options;
+        options.search_max_depth = options.search_max_depth.or(other_options.search_max_depth);
+        if options.search_excludes.is_none() {
+            options.search_excludes = other_options.search_excludes.clone();
+        }
+        options.search_follow_symlinks |= other_options.search_follow_symlinks;
+        options.search_max_matches = options
+            .search_max_matches
+            .or(other_options.search_max_matches);
+        options.search_time_budget = options
+            .search_time_budget
+            .or(other_options.search_time_budget);
+        if options.search_arch == ArchPreference::Native {
+            options.search_arch = other_options.search_arch.clone();
+        }
+
+        if self.fallback_strategies.is_empty() {
+            self.fallback_strategies = other.fallback_strategies.clone();
+        }
+        if self.verify_options.verify_policy == VerifyPolicy::Off {
+            self.verify_options.verify_policy = other.verify_options.verify_policy.clone();
+        }
+        if self.verify_options.verify_signer.is_none() {
+            self.verify_options.verify_signer = other.verify_options.verify_signer.clone();
+        }
+        if self.variants.is_empty() {
+            self.variants = other.variants.clone();
         }
     }
 
@@ -148,35 +627,149 @@ impl App {
         if self.exe_name.is_empty() {
             return Err("Exe Name is empty.".to_owned());
         }
-        if self.search_term.is_empty() {
+        let strategies = self.get_search_strategies();
+        if strategies
+            .iter()
+            .any(|strategy| strategy.search_term.is_empty())
+        {
             return Err("Search Term is empty.".to_owned());
         }
-        if self.search_method == SearchMethod::PSGetApp
-            && self.operating_system != OperatingSystem::Windows
+        if strategies
+            .iter()
+            .any(|strategy| strategy.search_method == SearchMethod::FolderSearch)
+        {
+            paths::FileNameMatcher::new(&self.exe_name)?;
+        }
+        for exclude in self.search_options.get_excludes() {
+            if let Err(error) = glob::Pattern::new(&exclude) {
+                return Err(format!(
+                    "Search exclude '{}' is not a valid pattern - {}",
+                    exclude, error
+                ));
+            }
+        }
+        if self.operating_system != OperatingSystem::Windows
+            && strategies
+                .iter()
+                .any(|strategy| strategy.search_method == SearchMethod::PSGetApp)
         {
             return Err(format!(
                 "Search method '{}' is only supported on Windows",
-                &self.search_method
+                SearchMethod::PSGetApp
             ));
         }
+        if self.operating_system != OperatingSystem::MacOS
+            && strategies
+                .iter()
+                .any(|strategy| strategy.search_method == SearchMethod::BundleId)
+        {
+            return Err(format!(
+                "Search method '{}' is only supported on MacOS",
+                SearchMethod::BundleId
+            ));
+        }
+        for (index, variant) in self.variants.iter().enumerate() {
+            if variant.search_term.is_empty() {
+                return Err(format!(
+                    "Search Term is empty for the {} variant.",
+                    variant.operating_system
+                ));
+            }
+            if variant.operating_system == self.operating_system {
+                return Err(format!(
+                    "The {} variant is for the app's own operating system, set its search method instead.",
+                    variant.operating_system
+                ));
+            }
+            if self.variants[..index]
+                .iter()
+                .any(|other| other.operating_system == variant.operating_system)
+            {
+                return Err(format!(
+                    "There is more than one {} variant.",
+                    variant.operating_system
+                ));
+            }
+            let supported = match variant.search_method {
+                SearchMethod::PSGetApp => variant.operating_system == OperatingSystem::Windows,
+                SearchMethod::BundleId => variant.operating_system == OperatingSystem::MacOS,
+                _ => true,
+            };
+            if !supported {
+                return Err(format!(
+                    "Search method '{}' is not supported by the {} variant.",
+                    variant.search_method, variant.operating_system
+                ));
+            }
+        }
+        if self.verify_options.verify_signer.is_some()
+            && self.verify_options.verify_policy == VerifyPolicy::Off
+        {
+            return Err(
+                "Signer is only checked when the verify policy is Warn or Refuse.".to_owned(),
+            );
+        }
 
         Ok(())
     }
 
+    fn display_exe_name(&self) -> String {
+        // For patterns, show the file name that was matched
+        let is_pattern = paths::FileNameMatcher::new(&self.exe_name)
+            .map(|matcher| matcher.is_pattern())
+            .unwrap_or(false);
+        let matched_file_name = self
+            .app_path
+            .as_ref()
+            .and_then(|app_path| Path::new(app_path).file_name())
+            .map(|file_name| file_name.to_string_lossy().to_string());
+
+        match (is_pattern, matched_file_name) {
+            (true, Some(matched_file_name)) => {
+                format!("{} (matched '{}')", self.exe_name, matched_file_name)
+            }
+            _ => self.exe_name.clone(),
+        }
+    }
+
+    fn display_matched_strategy(&self) -> String {
+        match (&self.matched_search_method, &self.matched_search_term) {
+            (Some(search_method), Some(search_term)) => {
+                SearchStrategy::new(search_method.clone(), search_term.clone()).to_string()
+            }
+            _ => "N/A".to_string(),
+        }
+    }
+
     pub fn to_description(&self) -> String {
         let param_info = if let Some(unwrapped_params) = self.params.clone() {
             format!(" Params '{}'", unwrapped_params.magenta())
         } else {
             String::new()
         };
+        let fallback_info = if self.fallback_strategies.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " Fallbacks '{}'",
+                display_search_strategies(&self.fallback_strategies).magenta()
+            )
+        };
+        let variant_info = if self.variants.is_empty() {
+            String::new()
+        } else {
+            format!(" Variants '{}'", display_variants(&self.variants).magenta())
+        };
 
         format!(
-            "App Name '{}', Exe Name '{}', Search Term '{}', Search Method '{}'{}",
+            "App Name '{}', Exe Name '{}', Search Term '{}', Search Method '{}'{}{}{}",
             self.app_name.blue(),
             self.exe_name.magenta(),
             self.search_term.magenta(),
             self.search_method.to_string().magenta(),
-            param_info
+            param_info,
+            fallback_info,
+            variant_info
         )
     }
 }
@@ -188,6 +781,139 @@ pub fn display_option_string(value: &Option<String>) -> String {
     }
 }
 
+pub fn display_search_options(value: &SearchOptions) -> String {
+    let mut options: Vec<String> = Vec::new();
+    if let Some(max_depth) = value.search_max_depth {
+        options.push(format!("Max Depth {}", max_depth));
+    }
+    if value.search_excludes.is_some() {
+        options.push(format!("Excludes '{}'", value.get_excludes().join(", ")));
+    }
+    if value.search_follow_symlinks {
+        options.push("Follow Symlinks".to_string());
+    }
+    if let Some(max_matches) = value.search_max_matches {
+        options.push(format!("Max Matches {}", max_matches));
+    }
+    if let Some(time_budget) = value.search_time_budget {
+        options.push(format!("Time Budget {}s", time_budget));
+    }
+    if value.search_arch != ArchPreference::Native {
+        options.push(format!("Arch {}", value.search_arch));
+    }
+
+    match options.is_empty() {
+        true => "N/A".to_string(),
+        false => options.join(", "),
+    }
+}
+
+pub fn display_verify_options(value: &VerifyOptions) -> String {
+    match &value.verify_signer {
+        Some(signer) => format!("{}, Signer '{}'", value.verify_policy, signer),
+        None => value.verify_policy.to_string(),
+    }
+}
+
+pub fn display_variants(value: &[AppVariant]) -> String {
+    match value.is_empty() {
+        true => "N/A".to_string(),
+        false => value
+            .iter()
+            .map(|variant| variant.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+/// Parses a variant written as os:method:term, like windows:ps-get-app:Microsoft.WindowsTerminal.
+/// For shortcuts and path lookups, the term names the app itself, so it gives the exe name too.
+pub fn parse_variant(input: &str) -> Result<AppVariant, String> {
+    let (operating_system, strategy) = input.split_once(':').ok_or(format!(
+        "Invalid variant '{}', expected os:method:term, like macos:shortcut:~/Applications/iTerm.app",
+        input
+    ))?;
+    let operating_system = <OperatingSystem as ValueEnum>::from_str(operating_system.trim(), true)
+        .map_err(|_| {
+            format!(
+                "Invalid operating system '{}' in '{}', expected one of {}",
+                operating_system,
+                input,
+                OperatingSystem::value_variants()
+                    .iter()
+                    .map(|operating_system| operating_system.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })?;
+    let strategy = parse_search_strategy(strategy)?;
+    let file_name = strategy
+        .search_term
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let (exe_name, search_term) = match strategy.search_method {
+        SearchMethod::Shortcut => {
+            let folder = strategy.search_term.trim_end_matches(['/', '\\']);
+            let folder = &folder[..folder.len() - file_name.len()];
+            (
+                Some(file_name),
+                folder.trim_end_matches(['/', '\\']).to_string(),
+            )
+        }
+        SearchMethod::PathLookup => (Some(file_name), strategy.search_term),
+        _ => (None, strategy.search_term),
+    };
+
+    Ok(AppVariant {
+        operating_system,
+        exe_name,
+        search_method: strategy.search_method,
+        search_term,
+        params: None,
+    })
+}
+
+pub fn display_search_strategies(value: &[SearchStrategy]) -> String {
+    match value.is_empty() {
+        true => "N/A".to_string(),
+        false => value
+            .iter()
+            .map(|strategy| strategy.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+/// Parses a search strategy written as method:term, like FolderSearch:%localappdata%\Programs.
+pub fn parse_search_strategy(input: &str) -> Result<SearchStrategy, String> {
+    let (search_method, search_term) = input.split_once(':').ok_or(format!(
+        "Invalid search strategy '{}', expected method:term, like folder-search:%programfiles%",
+        input
+    ))?;
+    let search_method =
+        <SearchMethod as ValueEnum>::from_str(search_method.trim(), true).map_err(|_| {
+            format!(
+                "Invalid search method '{}' in '{}', expected one of {}",
+                search_method,
+                input,
+                SearchMethod::value_variants()
+                    .iter()
+                    .map(|method| method.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })?;
+    let search_term = search_term.trim();
+    if search_term.is_empty() {
+        return Err(format!("Search Term is empty in '{}'", input));
+    }
+
+    Ok(SearchStrategy::new(search_method, search_term.to_string()))
+}
+
 pub fn display_option_utc_datetime_to_local(value: &Option<DateTime<Utc>>) -> String {
     if let Some(d) = value {
         let converted: DateTime<Local> = DateTime::from(*d);
@@ -197,6 +923,81 @@ pub fn display_option_utc_datetime_to_local(value: &Option<DateTime<Utc>>) -> St
     "N/A".to_string()
 }
 
+pub fn display_option_uptime(value: &Option<u64>) -> String {
+    match value {
+        Some(seconds) => {
+            let days = seconds / 86400;
+            let hours = (seconds % 86400) / 3600;
+            let minutes = (seconds % 3600) / 60;
+            let seconds = seconds % 60;
+            if days > 0 {
+                format!("{}d {:02}:{:02}:{:02}", days, hours, minutes, seconds)
+            } else {
+                format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+            }
+        }
+        None => "N/A".to_string(),
+    }
+}
+
+pub fn display_option_memory(value: &Option<u64>) -> String {
+    match value {
+        Some(bytes) => format!("{:.1} MB", *bytes as f64 / 1024.0 / 1024.0),
+        None => "N/A".to_string(),
+    }
+}
+
+/// Durations are for schedules and staleness, anything under a minute would run or refresh
+/// continuously.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let unit_index = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(unit_index);
+
+    let number = number.parse::<i64>().map_err(|_| {
+        format!(
+            "Invalid duration '{}', expected a number and unit, like 30m, 12h or 7d",
+            input
+        )
+    })?;
+    let unit_seconds = match unit.to_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit in '{}', expected one of s, m, h, d or w",
+                input
+            ))
+        }
+    };
+
+    let seconds = number
+        .checked_mul(unit_seconds)
+        .ok_or(format!("Duration '{}' is too large", input))?;
+    if seconds < 60 {
+        return Err(format!(
+            "Duration '{}' is too short, it must be at least a minute",
+            input
+        ));
+    }
+    Duration::try_seconds(seconds).ok_or(format!("Duration '{}' is too large", input))
+}
+
+pub fn display_duration(duration: &Duration) -> String {
+    let seconds = duration.num_seconds();
+    for (unit, unit_seconds) in [("w", 604800), ("d", 86400), ("h", 3600), ("m", 60)] {
+        if seconds != 0 && seconds % unit_seconds == 0 {
+            return format!("{}{}", seconds / unit_seconds, unit);
+        }
+    }
+    format!("{}s", seconds)
+}
+
 fn format_local_datetime(local_datetime: &DateTime<Local>) -> String {
     format!(
         "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
@@ -211,7 +1012,13 @@ fn format_local_datetime(local_datetime: &DateTime<Local>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{App, OperatingSystem, SearchMethod};
+    use chrono::Duration;
+
+    use super::{
+        display_duration, display_option_memory, display_option_uptime, parse_duration,
+        parse_search_strategy, parse_variant, App, FileVersion, OperatingSystem, SearchMethod,
+        SearchOptions, SearchStrategy,
+    };
 
     #[test]
     fn app_validate_psgetapp_on_macos_fails() {
@@ -314,4 +1121,318 @@ mod tests {
         // Assert
         assert!(actual.is_ok());
     }
+
+    #[test]
+    fn display_option_uptime_under_a_day() {
+        // Arrange
+        let uptime = Some(3 * 3600 + 25 * 60 + 7);
+
+        // Act
+        let actual = display_option_uptime(&uptime);
+
+        // Assert
+        assert_eq!(actual, "03:25:07");
+    }
+
+    #[test]
+    fn display_option_uptime_over_a_day() {
+        // Arrange
+        let uptime = Some(2 * 86400 + 5 * 3600 + 9);
+
+        // Act
+        let actual = display_option_uptime(&uptime);
+
+        // Assert
+        assert_eq!(actual, "2d 05:00:09");
+    }
+
+    #[test]
+    fn display_option_memory_in_megabytes() {
+        // Arrange
+        let memory = Some(1536 * 1024);
+
+        // Act
+        let actual = display_option_memory(&memory);
+
+        // Assert
+        assert_eq!(actual, "1.5 MB");
+    }
+
+    #[test]
+    fn file_version_is_older_than() {
+        // Arrange
+        let older = FileVersion::new(String::new(), String::new(), "1.9.12".to_string());
+        let newer = FileVersion::new(String::new(), String::new(), "1.10.0".to_string());
+
+        // Act
+        let actual_older = older.is_older_than(&newer);
+        let actual_newer = newer.is_older_than(&older);
+
+        // Assert
+        assert!(actual_older);
+        assert!(!actual_newer);
+    }
+
+    #[test]
+    fn parse_duration_days() {
+        // Arrange
+        let input = "7d";
+
+        // Act
+        let actual = parse_duration(input);
+
+        // Assert
+        assert_eq!(actual, Ok(Duration::try_days(7).unwrap()));
+    }
+
+    #[test]
+    fn parse_duration_missing_unit() {
+        // Arrange
+        let input = "7";
+
+        // Act
+        let actual = parse_duration(input);
+
+        // Assert
+        assert!(actual.is_err());
+        assert!(actual.unwrap_err().contains("Invalid duration unit"));
+    }
+
+    #[test]
+    fn parse_duration_under_a_minute() {
+        // Arrange
+        let inputs = ["0d", "59s"];
+
+        // Act
+        let actual: Vec<Result<Duration, String>> =
+            inputs.iter().map(|input| parse_duration(input)).collect();
+
+        // Assert
+        assert!(actual
+            .iter()
+            .all(|result| result.as_ref().unwrap_err().contains("at least a minute")));
+        assert_eq!(parse_duration("60s"), Ok(Duration::try_minutes(1).unwrap()));
+    }
+
+    #[test]
+    fn display_duration_uses_largest_whole_unit() {
+        // Arrange
+        let duration = Duration::try_hours(36).unwrap();
+
+        // Act
+        let actual = display_duration(&duration);
+
+        // Assert
+        assert_eq!(actual, "36h");
+    }
+
+    #[test]
+    fn app_validate_invalid_search_exclude() {
+        // Arrange
+        let mut new_app = App::new(
+            "app_name".to_string(),
+            "exe_name".to_string(),
+            None,
+            "search_term".to_string(),
+            SearchMethod::FolderSearch,
+            OperatingSystem::Windows,
+        );
+        new_app.search_options = SearchOptions {
+            search_excludes: Some("node_modules;[Temp".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let actual = new_app.validate();
+
+        // Assert
+        assert!(actual.is_err());
+        assert!(actual
+            .unwrap_err()
+            .contains("Search exclude '[Temp' is not a valid pattern"));
+    }
+
+    #[test]
+    fn parse_search_strategy_method_and_term() {
+        // Arrange
+        let input = r"folder-search:C:\Program Files";
+
+        // Act
+        let actual = parse_search_strategy(input).unwrap();
+
+        // Assert
+        assert_eq!(
+            actual,
+            SearchStrategy::new(SearchMethod::FolderSearch, r"C:\Program Files".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_variant_os_method_and_term() {
+        // Arrange
+        let input = "macos:shortcut:~/Applications/iTerm.app";
+
+        // Act
+        let actual = parse_variant(input).unwrap();
+        let actual_invalid = parse_variant("beos:shortcut:/boot/apps");
+
+        // Assert
+        assert_eq!(actual.operating_system, OperatingSystem::MacOS);
+        assert_eq!(actual.search_method, SearchMethod::Shortcut);
+        assert_eq!(actual.exe_name, Some("iTerm.app".to_string()));
+        assert_eq!(actual.search_term, "~/Applications");
+        assert!(actual_invalid.is_err());
+    }
+
+    #[test]
+    fn app_is_same_search_ignores_params() {
+        // Arrange
+        let app = App::new(
+            "Rider".to_string(),
+            "rider64.exe".to_string(),
+            None,
+            "%localappdata%\\Programs".to_string(),
+            SearchMethod::FolderSearch,
+            OperatingSystem::Windows,
+        );
+        let mut new_params = app.clone();
+        new_params.params = Some("--wait".to_string());
+        let mut new_depth = app.clone();
+        new_depth.search_options.search_max_depth = Some(2);
+        let mut new_term = app.clone();
+        new_term.search_term = "%programfiles%".to_string();
+
+        // Act
+        let actual_new_params = app.is_same_search(&new_params);
+        let actual_new_depth = app.is_same_search(&new_depth);
+        let actual_new_term = app.is_same_search(&new_term);
+
+        // Assert
+        assert!(actual_new_params);
+        assert!(!actual_new_depth);
+        assert!(!actual_new_term);
+    }
+
+    #[test]
+    fn app_for_operating_system_swaps_in_variant() {
+        // Arrange
+        let mut app = App::new(
+            "Terminal".to_string(),
+            "wt.exe".to_string(),
+            None,
+            "Microsoft.WindowsTerminal".to_string(),
+            SearchMethod::PSGetApp,
+            OperatingSystem::Windows,
+        );
+        app.variants = vec![
+            parse_variant("macos:shortcut:~/Applications/iTerm.app").unwrap(),
+            parse_variant("linux:path-lookup:alacritty").unwrap(),
+        ];
+
+        // Act
+        let actual = app.for_operating_system(&OperatingSystem::Linux);
+        let actual_back = actual.for_operating_system(&OperatingSystem::Windows);
+
+        // Assert
+        assert_eq!(actual.operating_system, OperatingSystem::Linux);
+        assert_eq!(actual.search_method, SearchMethod::PathLookup);
+        assert_eq!(actual.search_term, "alacritty");
+        assert_eq!(actual.validate(), Ok(()));
+        assert_eq!(actual.variants.len(), 2);
+        assert_eq!(actual.exe_name, "alacritty");
+        assert_eq!(actual_back.search_method, SearchMethod::PSGetApp);
+        assert_eq!(actual_back.exe_name, "wt.exe");
+    }
+
+    #[test]
+    fn app_validate_variant_unsupported_method_fails() {
+        // Arrange
+        let mut app = App::new(
+            "Terminal".to_string(),
+            "wt.exe".to_string(),
+            None,
+            "Microsoft.WindowsTerminal".to_string(),
+            SearchMethod::PSGetApp,
+            OperatingSystem::Windows,
+        );
+        app.variants = vec![parse_variant("linux:ps-get-app:alacritty").unwrap()];
+
+        // Act
+        let actual = app.validate();
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn parse_search_strategy_invalid() {
+        // Arrange
+        let inputs = ["FolderSearch", "Unknown:%programfiles%", "Shortcut: "];
+
+        // Act
+        let actual: Vec<bool> = inputs
+            .iter()
+            .map(|input| parse_search_strategy(input).is_err())
+            .collect();
+
+        // Assert
+        assert_eq!(actual, vec![true, true, true]);
+    }
+
+    #[test]
+    fn app_get_search_strategies_primary_first() {
+        // Arrange
+        let mut app = App::new(
+            "app_name".to_string(),
+            "app.exe".to_string(),
+            None,
+            "%programfiles%".to_string(),
+            SearchMethod::Shortcut,
+            OperatingSystem::Windows,
+        );
+        app.fallback_strategies = vec![
+            SearchStrategy::new(SearchMethod::FolderSearch, "%localappdata%".to_string()),
+            SearchStrategy::new(SearchMethod::PSGetApp, "App".to_string()),
+        ];
+
+        // Act
+        let actual: Vec<SearchMethod> = app
+            .get_search_strategies()
+            .into_iter()
+            .map(|strategy| strategy.search_method)
+            .collect();
+
+        // Assert
+        assert_eq!(
+            actual,
+            vec![
+                SearchMethod::Shortcut,
+                SearchMethod::FolderSearch,
+                SearchMethod::PSGetApp
+            ]
+        );
+    }
+
+    #[test]
+    fn app_validate_psgetapp_fallback_on_macos_fails() {
+        // Arrange
+        let mut app = App::new(
+            "app_name".to_string(),
+            "app_name.app".to_string(),
+            None,
+            "/Applications".to_string(),
+            SearchMethod::Shortcut,
+            OperatingSystem::MacOS,
+        );
+        app.fallback_strategies = vec![SearchStrategy::new(
+            SearchMethod::PSGetApp,
+            "App".to_string(),
+        )];
+
+        // Act
+        let actual = app.validate();
+
+        // Assert
+        assert!(actual.is_err());
+    }
 }
@@ -1,19 +1,32 @@
 use crate::data;
-use chrono::Utc;
-use eyre::{Context, Report};
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Context, Report};
+use libsqlite3_sys as ffi;
 use log::debug;
 use sqlx::{
     migrate::{MigrateDatabase, Migrator},
-    Sqlite, SqlitePool,
+    sqlite::SqliteConnectOptions,
+    ConnectOptions, Sqlite, SqliteConnection, SqlitePool,
 };
 use std::{
+    ffi::CStr,
     fs::{self},
     io,
+    path::{self, Path, PathBuf},
+    thread,
+    time::Duration,
 };
 
 const DB_URL: &str = "sqlite://app-lemonator.db";
 const DB_FILE: &str = "app-lemonator.db";
 
+/// Pages copied by each backup step, so another instance using the database is only locked out
+/// for a moment at a time.
+const BACKUP_PAGES_PER_STEP: i32 = 100;
+const BACKUP_BUSY_WAIT: Duration = Duration::from_millis(50);
+/// Gives up when the database stays busy for 10 seconds.
+const BACKUP_BUSY_RETRIES: u32 = 200;
+
 static MIGRATOR: Migrator = sqlx::migrate!(); // this will pick up migrations from the ./migrations directory
 
 async fn get_db() -> sqlx::Pool<Sqlite> {
@@ -25,10 +38,152 @@ pub fn database_exists() -> bool {
     runtime.block_on(async { Sqlite::database_exists(DB_URL).await.unwrap_or(false) })
 }
 
+/// The database is in the folder App Lemonator is run from, this is its full path.
+pub fn get_db_path() -> PathBuf {
+    path::absolute(DB_FILE).unwrap_or_else(|_| PathBuf::from(DB_FILE))
+}
+
 pub fn reset_db() -> Result<(), io::Error> {
     fs::remove_file(DB_FILE)
 }
 
+/// Copies a database to a file with SQLite's online backup, which is safe while another
+/// instance is using the database.
+pub fn backup_db(database_file: &Path, backup_file: &Path) -> Result<(), Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let mut source = SqliteConnectOptions::new()
+            .filename(database_file)
+            .connect()
+            .await
+            .wrap_err(format!(
+                "Unable to open database '{}'",
+                database_file.display()
+            ))?;
+        let mut destination = SqliteConnectOptions::new()
+            .filename(backup_file)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .wrap_err(format!(
+                "Unable to create backup '{}'",
+                backup_file.display()
+            ))?;
+
+        copy_database(&mut source, &mut destination).await
+    })
+}
+
+/// Replaces a database with a backup, using the online backup the other way round. Backups
+/// from older versions are migrated once restored.
+pub fn restore_db(backup_file: &Path, database_file: &Path) -> Result<(), Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let mut source = SqliteConnectOptions::new()
+            .filename(backup_file)
+            .read_only(true)
+            .connect()
+            .await
+            .wrap_err(format!("Unable to open backup '{}'", backup_file.display()))?;
+
+        // Check before anything is replaced, a newer backup can't be migrated back
+        let versions: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations")
+            .fetch_all(&mut source)
+            .await
+            .wrap_err(format!(
+                "'{}' is not an App Lemonator backup",
+                backup_file.display()
+            ))?;
+        if let Some(version) = versions.iter().find(|version| {
+            !MIGRATOR
+                .iter()
+                .any(|migration| migration.version == **version)
+        }) {
+            return Err(eyre!(
+                "Backup '{}' is from a newer version of App Lemonator, it has migration {}",
+                backup_file.display(),
+                version
+            ));
+        }
+
+        let mut destination = SqliteConnectOptions::new()
+            .filename(database_file)
+            .connect()
+            .await
+            .wrap_err(format!(
+                "Unable to open database '{}'",
+                database_file.display()
+            ))?;
+        copy_database(&mut source, &mut destination).await?;
+
+        MIGRATOR
+            .run(&mut destination)
+            .await
+            .wrap_err("Unable to run database migrations on the restored backup")?;
+        Ok(())
+    })
+}
+
+async fn copy_database(
+    source: &mut SqliteConnection,
+    destination: &mut SqliteConnection,
+) -> Result<(), Report> {
+    const MAIN: &CStr = c"main";
+
+    let mut source_handle = source
+        .lock_handle()
+        .await
+        .wrap_err("Unable to lock the database to copy from")?;
+    let mut destination_handle = destination
+        .lock_handle()
+        .await
+        .wrap_err("Unable to lock the database to copy to")?;
+    let source = source_handle.as_raw_handle().as_ptr();
+    let destination = destination_handle.as_raw_handle().as_ptr();
+
+    // Safety: both connections stay locked, so open and unused elsewhere, until the backup
+    // is finished
+    unsafe {
+        let backup = ffi::sqlite3_backup_init(destination, MAIN.as_ptr(), source, MAIN.as_ptr());
+        if backup.is_null() {
+            return Err(eyre!(
+                "Unable to start copying the database - {}",
+                get_error_message(destination)
+            ));
+        }
+
+        let mut busy_retries = 0;
+        let step_result = loop {
+            match ffi::sqlite3_backup_step(backup, BACKUP_PAGES_PER_STEP) {
+                ffi::SQLITE_OK => busy_retries = 0,
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if busy_retries < BACKUP_BUSY_RETRIES => {
+                    busy_retries += 1;
+                    thread::sleep(BACKUP_BUSY_WAIT);
+                }
+                result => break result,
+            }
+        };
+
+        // Finishing frees the backup, even when a step failed
+        let finish_result = ffi::sqlite3_backup_finish(backup);
+        if step_result != ffi::SQLITE_DONE || finish_result != ffi::SQLITE_OK {
+            return Err(eyre!(
+                "Unable to copy the database - {}",
+                get_error_message(destination)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Safety: the connection must be open.
+unsafe fn get_error_message(db: *mut ffi::sqlite3) -> String {
+    CStr::from_ptr(ffi::sqlite3_errmsg(db))
+        .to_string_lossy()
+        .to_string()
+}
+
 pub fn create_db() -> Result<bool, eyre::Report> {
     let runtime = tokio::runtime::Runtime::new().unwrap();
     runtime.block_on(async {
@@ -53,12 +208,94 @@ pub fn create_db() -> Result<bool, eyre::Report> {
 }
 
 pub fn add_app(app: &data::App) -> Result<sqlx::sqlite::SqliteQueryResult, Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let db = get_db().await;
+        let mut tx = db
+            .begin()
+            .await
+            .wrap_err("Failed to start add app transaction")?;
+
+        let result = insert_app(&mut tx, app).await?;
+
+        tx.commit()
+            .await
+            .wrap_err(format!("Failed to add app '{:?}", app))?;
+        Ok(result)
+    })
+}
+
+/// Replaces the settings of the app with the same name. It keeps its id and what the last update
+/// found, like its path and version, until the next update.
+/// Replaces the app with the same name. With `clear_app_path`, the path found by the old search is
+/// forgotten, so the next update or open searches again.
+pub fn replace_app(
+    app: &data::App,
+    clear_app_path: bool,
+) -> Result<sqlx::sqlite::SqliteQueryResult, Report> {
     let runtime = tokio::runtime::Runtime::new().unwrap();
     runtime.block_on(async {
     let db = get_db().await;
+    let mut tx = db.begin().await.wrap_err("Failed to start replace app transaction")?;
 
-    sqlx::query(
-        "INSERT INTO apps (app_name, exe_name, params, search_term, search_method, operating_system) VALUES (?,?,?,?,?,?)",
+    let app_id: i64 = sqlx::query_scalar("SELECT id FROM apps WHERE app_name=$1 COLLATE NOCASE")
+        .bind(&app.app_name)
+        .fetch_one(&mut *tx)
+        .await
+        .wrap_err(format!("Failed to find app '{}' to replace", app.app_name))?;
+
+    let result = sqlx::query("UPDATE apps SET app_name=$1, exe_name=$2, search_term=$3, search_method=$4, params=$5,
+        operating_system=$6, search_max_depth=$7, search_excludes=$8, search_follow_symlinks=$9, search_max_matches=$10,
+        search_time_budget=$11, search_arch=$12, verify_policy=$13, verify_signer=$14 WHERE id=$15")
+    .bind(&app.app_name)
+    .bind(&app.exe_name)
+    .bind(&app.search_term)
+    .bind(&app.search_method)
+    .bind(&app.params)
+    .bind(&app.operating_system)
+    .bind(app.search_options.search_max_depth)
+    .bind(&app.search_options.search_excludes)
+    .bind(app.search_options.search_follow_symlinks)
+    .bind(app.search_options.search_max_matches)
+    .bind(app.search_options.search_time_budget)
+    .bind(&app.search_options.search_arch)
+    .bind(&app.verify_options.verify_policy)
+    .bind(&app.verify_options.verify_signer)
+    .bind(app_id)
+    .execute(&mut *tx)
+    .await
+    .wrap_err(format!(
+        "Failed to replace app '{:?}'", app
+    ))?;
+
+    if clear_app_path {
+        sqlx::query("UPDATE apps SET app_path=NULL, app_description=NULL, app_version=NULL, last_updated=NULL,
+            matched_search_method=NULL, matched_search_term=NULL, app_publisher=NULL, app_product_name=NULL,
+            app_bundle_id=NULL, app_copyright=NULL, app_architecture=NULL, app_build_number=NULL,
+            app_sha256=NULL WHERE id=$1")
+        .bind(app_id)
+        .execute(&mut *tx)
+        .await
+        .wrap_err(format!("Failed to clear the app path of app '{}'", app.app_name))?;
+    }
+
+    save_search_strategies(&mut tx, app_id, &app.fallback_strategies).await?;
+    save_variants(&mut tx, app_id, &app.variants).await?;
+
+    tx.commit().await.wrap_err(format!("Failed to replace app '{:?}'", app))?;
+    Ok(result)
+})
+}
+
+async fn insert_app(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    app: &data::App,
+) -> Result<sqlx::sqlite::SqliteQueryResult, Report> {
+    let result = sqlx::query(
+        "INSERT INTO apps (app_name, exe_name, params, search_term, search_method, operating_system,
+        search_max_depth, search_excludes, search_follow_symlinks, search_max_matches, search_time_budget, search_arch,
+        verify_policy, verify_signer, last_opened)
+        VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)",
     )
     .bind(&app.app_name)
     .bind(&app.exe_name)
@@ -66,12 +303,25 @@ pub fn add_app(app: &data::App) -> Result<sqlx::sqlite::SqliteQueryResult, Repor
     .bind(&app.search_term)
     .bind(&app.search_method)
     .bind(&app.operating_system)
-    .execute(&db)
+    .bind(app.search_options.search_max_depth)
+    .bind(&app.search_options.search_excludes)
+    .bind(app.search_options.search_follow_symlinks)
+    .bind(app.search_options.search_max_matches)
+    .bind(app.search_options.search_time_budget)
+    .bind(&app.search_options.search_arch)
+    .bind(&app.verify_options.verify_policy)
+    .bind(&app.verify_options.verify_signer)
+    .bind(app.last_opened)
+    // Not last_updated, the app has no path yet so the next refresh has to find it
+    .execute(&mut **tx)
     .await
     .wrap_err(format!(
         "Failed to add app '{:?}", app
-    ))
-})
+    ))?;
+
+    save_search_strategies(tx, result.last_insert_rowid(), &app.fallback_strategies).await?;
+    save_variants(tx, result.last_insert_rowid(), &app.variants).await?;
+    Ok(result)
 }
 
 pub fn edit_app(
@@ -81,32 +331,148 @@ pub fn edit_app(
     let runtime = tokio::runtime::Runtime::new().unwrap();
     runtime.block_on(async {
     let db = get_db().await;
+    let mut tx = db.begin().await.wrap_err("Failed to start edit app transaction")?;
 
-    sqlx::query("UPDATE apps SET app_name=$1, exe_name=$2, search_term=$3, search_method=$4, params=$5 WHERE app_name=$6 COLLATE NOCASE")
+    let result = sqlx::query("UPDATE apps SET app_name=$1, exe_name=$2, search_term=$3, search_method=$4, params=$5,
+        search_max_depth=$6, search_excludes=$7, search_follow_symlinks=$8, search_max_matches=$9, search_time_budget=$10,
+        search_arch=$11, verify_policy=$12, verify_signer=$13 WHERE app_name=$14 COLLATE NOCASE")
     .bind(&app.app_name)
     .bind(&app.exe_name)
     .bind(&app.search_term)
     .bind(&app.search_method)
     .bind(&app.params)
+    .bind(app.search_options.search_max_depth)
+    .bind(&app.search_options.search_excludes)
+    .bind(app.search_options.search_follow_symlinks)
+    .bind(app.search_options.search_max_matches)
+    .bind(app.search_options.search_time_budget)
+    .bind(&app.search_options.search_arch)
+    .bind(&app.verify_options.verify_policy)
+    .bind(&app.verify_options.verify_signer)
     .bind(lookup_app_name)
-    .execute(&db)
+    .execute(&mut *tx)
     .await
     .wrap_err(format!(
         "Failed to edit app '{:?}'", app
-    ))
+    ))?;
+
+    save_search_strategies(&mut tx, app.id.into(), &app.fallback_strategies).await?;
+    save_variants(&mut tx, app.id.into(), &app.variants).await?;
+
+    tx.commit().await.wrap_err(format!("Failed to edit app '{:?}'", app))?;
+    Ok(result)
 })
 }
 
+/// Replaces the fallback search strategies of an app, keeping them in the order given.
+async fn save_search_strategies(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    app_id: i64,
+    strategies: &[data::SearchStrategy],
+) -> Result<(), Report> {
+    sqlx::query("DELETE FROM app_search_strategies WHERE app_id = $1")
+        .bind(app_id)
+        .execute(&mut **tx)
+        .await
+        .wrap_err(format!(
+            "Failed to remove search strategies for app with id '{}'",
+            app_id
+        ))?;
+
+    for (position, strategy) in strategies.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO app_search_strategies (app_id, position, search_method, search_term) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(app_id)
+        .bind(position as i64)
+        .bind(&strategy.search_method)
+        .bind(&strategy.search_term)
+        .execute(&mut **tx)
+        .await
+        .wrap_err(format!(
+            "Failed to add search strategy '{}' for app with id '{}'",
+            strategy, app_id
+        ))?;
+    }
+    Ok(())
+}
+
+async fn get_search_strategies(
+    db: &SqlitePool,
+    app_id: i32,
+) -> Result<Vec<data::SearchStrategy>, Report> {
+    sqlx::query_as::<_, data::SearchStrategy>(
+        "SELECT search_method, search_term FROM app_search_strategies WHERE app_id = $1 ORDER BY position",
+    )
+    .bind(app_id)
+    .fetch_all(db)
+    .await
+    .wrap_err(format!(
+        "Failed to get search strategies for app with id '{}'",
+        app_id
+    ))
+}
+
+/// Replaces the per operating system variants of an app.
+async fn save_variants(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    app_id: i64,
+    variants: &[data::AppVariant],
+) -> Result<(), Report> {
+    sqlx::query("DELETE FROM app_variants WHERE app_id = $1")
+        .bind(app_id)
+        .execute(&mut **tx)
+        .await
+        .wrap_err(format!(
+            "Failed to remove variants for app with id '{}'",
+            app_id
+        ))?;
+
+    for variant in variants {
+        sqlx::query(
+            "INSERT INTO app_variants (app_id, operating_system, exe_name, search_method, search_term, params) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(app_id)
+        .bind(&variant.operating_system)
+        .bind(&variant.exe_name)
+        .bind(&variant.search_method)
+        .bind(&variant.search_term)
+        .bind(&variant.params)
+        .execute(&mut **tx)
+        .await
+        .wrap_err(format!(
+            "Failed to add variant '{}' for app with id '{}'",
+            variant, app_id
+        ))?;
+    }
+    Ok(())
+}
+
+async fn get_variants(db: &SqlitePool, app_id: i32) -> Result<Vec<data::AppVariant>, Report> {
+    sqlx::query_as::<_, data::AppVariant>(
+        "SELECT operating_system, exe_name, search_method, search_term, params FROM app_variants WHERE app_id = $1 ORDER BY id",
+    )
+    .bind(app_id)
+    .fetch_all(db)
+    .await
+    .wrap_err(format!("Failed to get variants for app with id '{}'", app_id))
+}
+
 pub fn get_app(app: &str) -> Result<data::App, Report> {
     let runtime = tokio::runtime::Runtime::new().unwrap();
     runtime.block_on(async {
         let db = get_db().await;
 
-        sqlx::query_as::<_, data::App>("SELECT * FROM apps WHERE app_name = ? COLLATE NOCASE")
-            .bind(app.to_lowercase())
-            .fetch_one(&db)
-            .await
-            .wrap_err(format!("Failed to find app named '{}'", app))
+        let mut found_app =
+            sqlx::query_as::<_, data::App>("SELECT * FROM apps WHERE app_name = ? COLLATE NOCASE")
+                .bind(app.to_lowercase())
+                .fetch_one(&db)
+                .await
+                .wrap_err(format!("Failed to find app named '{}'", app))?;
+
+        found_app.fallback_strategies = get_search_strategies(&db, found_app.id).await?;
+        found_app.variants = get_variants(&db, found_app.id).await?;
+        Ok(found_app)
     })
 }
 
@@ -115,10 +481,16 @@ pub fn get_apps() -> Result<Vec<data::App>, Report> {
     runtime.block_on(async {
         let db = get_db().await;
 
-        sqlx::query_as::<_, data::App>("SELECT * FROM apps ORDER BY app_name")
+        let mut apps = sqlx::query_as::<_, data::App>("SELECT * FROM apps ORDER BY app_name")
             .fetch_all(&db)
             .await
-            .wrap_err("Failed to get list of all apps")
+            .wrap_err("Failed to get list of all apps")?;
+
+        for app in apps.iter_mut() {
+            app.fallback_strategies = get_search_strategies(&db, app.id).await?;
+            app.variants = get_variants(&db, app.id).await?;
+        }
+        Ok(apps)
     })
 }
 
@@ -132,13 +504,35 @@ pub fn update_app_file_version(
 
         sqlx::query(
             "UPDATE apps SET app_path = $2, app_description = $3, app_version = $4,
-     last_updated = $5 WHERE id=$1 COLLATE NOCASE",
+     last_updated = $5, matched_search_method = COALESCE($6, matched_search_method),
+     matched_search_term = COALESCE($7, matched_search_term), app_publisher = $8, app_product_name = $9,
+     app_bundle_id = $10, app_copyright = $11, app_architecture = $12, app_build_number = $13,
+     app_sha256 = $14 WHERE id=$1 COLLATE NOCASE",
         )
         .bind(id)
         .bind(app_file_version.path.clone())
         .bind(app_file_version.app_description.clone())
         .bind(app_file_version.display_version())
         .bind(Utc::now())
+        .bind(
+            app_file_version
+                .matched_strategy
+                .as_ref()
+                .map(|strategy| &strategy.search_method),
+        )
+        .bind(
+            app_file_version
+                .matched_strategy
+                .as_ref()
+                .map(|strategy| &strategy.search_term),
+        )
+        .bind(&app_file_version.metadata.app_publisher)
+        .bind(&app_file_version.metadata.app_product_name)
+        .bind(&app_file_version.metadata.app_bundle_id)
+        .bind(&app_file_version.metadata.app_copyright)
+        .bind(&app_file_version.metadata.app_architecture)
+        .bind(&app_file_version.metadata.app_build_number)
+        .bind(&app_file_version.sha256)
         .execute(&db)
         .await
         .wrap_err(format!(
@@ -177,3 +571,262 @@ pub fn delete_app(app: &str) -> Result<sqlx::sqlite::SqliteQueryResult, Report>
             .wrap_err(format!("Failed to delete app '{}'", app))
     })
 }
+
+pub fn get_index_last_indexed(base_folder: &str) -> Result<Option<DateTime<Utc>>, Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let db = get_db().await;
+
+        sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT last_indexed FROM index_roots WHERE base_folder = $1",
+        )
+        .bind(base_folder)
+        .fetch_optional(&db)
+        .await
+        .wrap_err(format!(
+            "Failed to get index details for base folder '{}'",
+            base_folder
+        ))
+    })
+}
+
+pub fn get_index_folders(base_folder: &str) -> Result<Vec<data::IndexedFolder>, Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let db = get_db().await;
+
+        sqlx::query_as::<_, data::IndexedFolder>(
+            "SELECT folder_path, depth, modified FROM index_folders WHERE base_folder = $1",
+        )
+        .bind(base_folder)
+        .fetch_all(&db)
+        .await
+        .wrap_err(format!(
+            "Failed to get indexed folders for base folder '{}'",
+            base_folder
+        ))
+    })
+}
+
+pub fn get_index_files(base_folder: &str) -> Result<Vec<data::IndexedFile>, Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let db = get_db().await;
+
+        sqlx::query_as::<_, data::IndexedFile>(
+            "SELECT folder_path, file_path, file_name, depth FROM index_files WHERE base_folder = $1 ORDER BY file_path",
+        )
+        .bind(base_folder)
+        .fetch_all(&db)
+        .await
+        .wrap_err(format!(
+            "Failed to get indexed files for base folder '{}'",
+            base_folder
+        ))
+    })
+}
+
+pub fn save_index_changes(
+    base_folder: &str,
+    changed_folders: &[data::IndexedFolderChange],
+    removed_folders: &[String],
+) -> Result<(), Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let db = get_db().await;
+        let mut tx = db
+            .begin()
+            .await
+            .wrap_err("Failed to start index transaction")?;
+
+        for folder_path in removed_folders {
+            sqlx::query("DELETE FROM index_files WHERE base_folder = $1 AND folder_path = $2")
+                .bind(base_folder)
+                .bind(folder_path)
+                .execute(&mut *tx)
+                .await
+                .wrap_err(format!("Failed to remove indexed files for '{}'", folder_path))?;
+            sqlx::query("DELETE FROM index_folders WHERE base_folder = $1 AND folder_path = $2")
+                .bind(base_folder)
+                .bind(folder_path)
+                .execute(&mut *tx)
+                .await
+                .wrap_err(format!("Failed to remove indexed folder '{}'", folder_path))?;
+        }
+
+        for change in changed_folders {
+            sqlx::query("DELETE FROM index_files WHERE base_folder = $1 AND folder_path = $2")
+                .bind(base_folder)
+                .bind(&change.folder.folder_path)
+                .execute(&mut *tx)
+                .await
+                .wrap_err(format!(
+                    "Failed to remove indexed files for '{}'",
+                    change.folder.folder_path
+                ))?;
+
+            for file in &change.files {
+                sqlx::query(
+                    "INSERT INTO index_files (base_folder, folder_path, file_path, file_name, depth) VALUES ($1, $2, $3, $4, $5)",
+                )
+                .bind(base_folder)
+                .bind(&file.folder_path)
+                .bind(&file.file_path)
+                .bind(&file.file_name)
+                .bind(file.depth)
+                .execute(&mut *tx)
+                .await
+                .wrap_err(format!("Failed to index file '{}'", file.file_path))?;
+            }
+
+            sqlx::query(
+                "INSERT INTO index_folders (base_folder, folder_path, depth, modified) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (base_folder, folder_path) DO UPDATE SET depth = $3, modified = $4",
+            )
+            .bind(base_folder)
+            .bind(&change.folder.folder_path)
+            .bind(change.folder.depth)
+            .bind(change.folder.modified)
+            .execute(&mut *tx)
+            .await
+            .wrap_err(format!(
+                "Failed to index folder '{}'",
+                change.folder.folder_path
+            ))?;
+        }
+
+        sqlx::query(
+            "INSERT INTO index_roots (base_folder, last_indexed) VALUES ($1, $2)
+            ON CONFLICT (base_folder) DO UPDATE SET last_indexed = $2",
+        )
+        .bind(base_folder)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await
+        .wrap_err(format!("Failed to save index for '{}'", base_folder))?;
+
+        tx.commit()
+            .await
+            .wrap_err(format!("Failed to save index for '{}'", base_folder))
+    })
+}
+
+pub fn clear_index() -> Result<(), Report> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let db = get_db().await;
+
+        for table in ["index_files", "index_folders", "index_roots"] {
+            sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&db)
+                .await
+                .wrap_err(format!("Failed to clear index table '{}'", table))?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, Connection};
+    use uuid::Uuid;
+
+    use crate::db::{backup_db, restore_db, MIGRATOR};
+
+    /// Creates a database with the migrations run and the apps given, then runs the SQL.
+    fn create_test_db(database_file: &Path, app_names: &[&str], sql: &str) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mut db = SqliteConnectOptions::new()
+                .filename(database_file)
+                .create_if_missing(true)
+                .connect()
+                .await
+                .unwrap();
+            MIGRATOR.run(&mut db).await.unwrap();
+            for app_name in app_names {
+                sqlx::query(
+                    "INSERT INTO apps (app_name, exe_name, search_term, search_method, operating_system)
+                    VALUES (?, 'lemon.exe', 'C:\\Lemon', 'FolderSearch', 'windows')",
+                )
+                .bind(app_name)
+                .execute(&mut db)
+                .await
+                .unwrap();
+            }
+            if !sql.is_empty() {
+                sqlx::query(sql).execute(&mut db).await.unwrap();
+            }
+            db.close().await.unwrap();
+        });
+    }
+
+    fn get_app_names(database_file: &Path) -> Vec<String> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mut db = SqliteConnectOptions::new()
+                .filename(database_file)
+                .connect()
+                .await
+                .unwrap();
+            let app_names = sqlx::query_scalar("SELECT app_name FROM apps ORDER BY app_name")
+                .fetch_all(&mut db)
+                .await
+                .unwrap();
+            db.close().await.unwrap();
+            app_names
+        })
+    }
+
+    #[test]
+    fn backup_db_restore_db_round_trip() {
+        // Arrange
+        let folder = env::temp_dir().join(format!("app-lemonator-db-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let database_file = folder.join("app-lemonator.db");
+        let backup_file = folder.join("backup.db");
+        create_test_db(&database_file, &["Lemon", "Lime"], "");
+
+        // Act
+        backup_db(&database_file, &backup_file).unwrap();
+        create_test_db(
+            &database_file,
+            &["Orange"],
+            "DELETE FROM apps WHERE app_name = 'Lime'",
+        );
+        restore_db(&backup_file, &database_file).unwrap();
+
+        // Assert
+        assert_eq!(get_app_names(&database_file), ["Lemon", "Lime"]);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn restore_db_newer_migration() {
+        // Arrange
+        let folder = env::temp_dir().join(format!("app-lemonator-db-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let database_file = folder.join("app-lemonator.db");
+        let backup_file = folder.join("backup.db");
+        create_test_db(&database_file, &["Lemon"], "");
+        create_test_db(
+            &backup_file,
+            &["Lime"],
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+            VALUES (99990101000000, 'from the future', 1, x'00', 0)",
+        );
+
+        // Act
+        let actual = restore_db(&backup_file, &database_file);
+
+        // Assert
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .contains("newer version of App Lemonator"));
+        assert_eq!(get_app_names(&database_file), ["Lemon"]);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
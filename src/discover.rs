@@ -0,0 +1,645 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use indicatif::ProgressBar;
+use log::{debug, error};
+use regex::Regex;
+use walkdir::WalkDir;
+
+use crate::{bundle, constants, data, finder, lnk, paths};
+
+/// How deep to look for executables in each Program Files folder.
+const PROGRAM_FILES_MAX_DEPTH: usize = 3;
+
+/// Executables that are part of an app, but not the app itself.
+const IGNORED_EXE_NAMES: [&str; 9] = [
+    "unins",
+    "uninstall",
+    "setup",
+    "install",
+    "update",
+    "crash",
+    "helper",
+    "report",
+    "elevate",
+];
+
+/// An app found on this machine that isn't in the database yet.
+pub struct Candidate {
+    pub app: data::App,
+    pub app_path: String,
+    pub source: &'static str,
+}
+
+/// Scans the well known app locations for this OS, returning the apps not already in the database.
+pub fn find_candidates(
+    existing_apps: &[data::App],
+    operating_system: &data::OperatingSystem,
+    pb: &ProgressBar,
+) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    if env::consts::OS == constants::OS_WINDOWS {
+        pb.set_message("Reading Start Menu shortcuts");
+        candidates.extend(find_start_menu_shortcuts(operating_system));
+        pb.set_message("Reading Microsoft Store apps");
+        candidates.extend(find_appx_packages(operating_system));
+        pb.set_message("Searching Program Files");
+        candidates.extend(find_program_files(operating_system));
+    } else if env::consts::OS == constants::OS_MACOS {
+        pb.set_message("Reading Applications");
+        candidates.extend(find_mac_applications(operating_system));
+    } else {
+        pb.set_message("Reading desktop entries");
+        candidates.extend(find_desktop_entries(operating_system));
+    }
+
+    remove_known_candidates(candidates, existing_apps)
+}
+
+/// Drops candidates that are already in the database, or found more than once. The first source
+/// to find an app wins.
+fn remove_known_candidates(
+    candidates: Vec<Candidate>,
+    existing_apps: &[data::App],
+) -> Vec<Candidate> {
+    let mut seen_names: HashSet<String> = existing_apps
+        .iter()
+        .map(|app| app.app_name.to_lowercase())
+        .collect();
+    let mut seen_paths: HashSet<String> = existing_apps
+        .iter()
+        .filter_map(|app| app.app_path.as_ref().map(|path| path.to_lowercase()))
+        .collect();
+
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            let is_new_name = seen_names.insert(candidate.app.app_name.to_lowercase());
+            let is_new_path = seen_paths.insert(candidate.app_path.to_lowercase());
+            is_new_name && is_new_path
+        })
+        .collect()
+}
+
+fn new_candidate(
+    app_name: &str,
+    app_path: &Path,
+    source: &'static str,
+    operating_system: &data::OperatingSystem,
+) -> Option<Candidate> {
+    let exe_name = app_path.file_name()?.to_string_lossy().to_string();
+    let folder = app_path.parent()?;
+    let (search_method, search_term) = get_search_for_folder(folder);
+
+    Some(Candidate {
+        app: data::App::new(
+            app_name.trim().to_string(),
+            exe_name,
+            None,
+            search_term,
+            search_method,
+            operating_system.clone(),
+        ),
+        app_path: app_path.display().to_string(),
+        source,
+    })
+}
+
+/// Folders with a version in their name change with each update, so those apps are found by
+/// searching the folder above. Anything else can be run directly, as can folders with nothing
+/// above the version to search.
+pub fn get_search_for_folder(folder: &Path) -> (data::SearchMethod, String) {
+    let version_re = Regex::new(r"\d+\.\d+").unwrap();
+
+    let mut search_folder = PathBuf::new();
+    for component in folder.components() {
+        if version_re.is_match(&component.as_os_str().to_string_lossy()) {
+            if search_folder.as_os_str().is_empty() {
+                break;
+            }
+            return (
+                data::SearchMethod::FolderSearch,
+                paths::to_path_variable(&search_folder.display().to_string()),
+            );
+        }
+        search_folder.push(component);
+    }
+
+    (
+        data::SearchMethod::Shortcut,
+        paths::to_path_variable(&folder.display().to_string()),
+    )
+}
+
+pub fn is_ignored_exe(file_name: &str) -> bool {
+    let file_name = file_name.to_lowercase();
+    IGNORED_EXE_NAMES
+        .iter()
+        .any(|ignored| file_name.contains(ignored))
+}
+
+/// Splits PowerShell output lines of '|' separated values, skipping lines with empty values.
+fn parse_powershell_rows(lines: &[String], columns: usize) -> Vec<Vec<String>> {
+    lines
+        .iter()
+        .map(|line| {
+            line.split('|')
+                .map(|value| value.trim().to_string())
+                .collect::<Vec<String>>()
+        })
+        .filter(|values| values.len() == columns && values.iter().all(|value| !value.is_empty()))
+        .collect()
+}
+
+fn find_start_menu_shortcuts(operating_system: &data::OperatingSystem) -> Vec<Candidate> {
+    let start_menu_folders: Vec<PathBuf> = ["APPDATA", "PROGRAMDATA"]
+        .iter()
+        .filter_map(|variable| env::var(variable).ok())
+        .map(|folder| Path::new(&folder).join(r"Microsoft\Windows\Start Menu\Programs"))
+        .collect();
+
+    let mut candidates = Vec::new();
+    for start_menu_folder in start_menu_folders {
+        let lnk_files = WalkDir::new(start_menu_folder)
+            .sort_by_file_name()
+            .into_iter()
+            .flatten()
+            .map(|entry| entry.into_path())
+            .filter(|path| {
+                path.extension()
+                    .map(|extension| extension.eq_ignore_ascii_case("lnk"))
+                    .unwrap_or(false)
+            });
+
+        for lnk_file in lnk_files {
+            if let Some(candidate) = new_lnk_candidate(&lnk_file, operating_system) {
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Shortcuts are kept up to date by the app installer, so the app is found by reading the
+/// shortcut each time.
+fn new_lnk_candidate(
+    lnk_file: &Path,
+    operating_system: &data::OperatingSystem,
+) -> Option<Candidate> {
+    let app_name = lnk_file.file_stem()?.to_string_lossy().to_string();
+    if is_ignored_exe(&app_name) {
+        return None;
+    }
+
+    let shell_link = match lnk::read(&lnk_file.display().to_string()) {
+        Ok(shell_link) => shell_link,
+        Err(e) => {
+            debug!("Skipping shortcut '{}': {:?}", lnk_file.display(), e);
+            return None;
+        }
+    };
+    let target_path = paths::expand_env_vars(&shell_link.target_path?);
+    let is_exe = Path::new(&target_path)
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("exe"))
+        .unwrap_or(false);
+    if !is_exe || !paths::file_exists(&target_path) {
+        return None;
+    }
+
+    let app = data::App::new(
+        app_name,
+        lnk_file.file_name()?.to_string_lossy().to_string(),
+        shell_link
+            .arguments
+            .filter(|arguments| !arguments.is_empty()),
+        paths::to_path_variable(&lnk_file.parent()?.display().to_string()),
+        data::SearchMethod::LnkFile,
+        operating_system.clone(),
+    );
+
+    Some(Candidate {
+        app,
+        app_path: target_path,
+        source: "Start Menu",
+    })
+}
+
+fn find_appx_packages(operating_system: &data::OperatingSystem) -> Vec<Candidate> {
+    let powershell_cmd = r#"Get-AppXPackage | Where-Object { -not $_.IsFramework -and $_.SignatureKind -eq 'Store' } | ForEach-Object { $package = $_; $manifest = Get-AppxPackageManifest $package; foreach ($application in $manifest.Package.Applications.Application) { "$($package.Name)|$($package.InstallLocation)|$($application.Executable)" } }"#;
+    let lines = match finder::run_powershell_cmd(powershell_cmd) {
+        Ok(lines) => lines,
+        Err(e) => {
+            error!("Unable to read Microsoft Store apps: {:?}", e);
+            return Vec::new();
+        }
+    };
+
+    parse_powershell_rows(&lines, 3)
+        .into_iter()
+        .map(|values| {
+            // The name is like "Microsoft.WindowsTerminal", so use the last part as the app name
+            let app_name = values[0].rsplit('.').next().unwrap_or(&values[0]);
+            Candidate {
+                app: data::App::new(
+                    app_name.to_string(),
+                    values[2].clone(),
+                    None,
+                    values[0].clone(),
+                    data::SearchMethod::PSGetApp,
+                    operating_system.clone(),
+                ),
+                app_path: paths::get_full_path(&values[1], &values[2]),
+                source: "Microsoft Store",
+            }
+        })
+        .collect()
+}
+
+fn find_program_files(operating_system: &data::OperatingSystem) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for variable in ["PROGRAMFILES", "PROGRAMFILES(X86)"] {
+        let Ok(program_files) = env::var(variable) else {
+            continue;
+        };
+        let Ok(entries) = fs::read_dir(&program_files) else {
+            continue;
+        };
+
+        for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+            let folder_name = entry.file_name().to_string_lossy().to_string();
+            let exe_files: Vec<PathBuf> = WalkDir::new(entry.path())
+                .max_depth(PROGRAM_FILES_MAX_DEPTH)
+                .into_iter()
+                .flatten()
+                .filter(|exe_entry| exe_entry.file_type().is_file())
+                .map(|exe_entry| exe_entry.into_path())
+                .filter(|path| {
+                    path.extension()
+                        .map(|extension| extension.eq_ignore_ascii_case("exe"))
+                        .unwrap_or(false)
+                })
+                .collect();
+
+            let exe_names: Vec<String> = exe_files
+                .iter()
+                .filter_map(|path| path.file_name())
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .collect();
+            if let Some(index) = pick_main_exe(&folder_name, &exe_names) {
+                if let Some(candidate) = new_candidate(
+                    &folder_name,
+                    &exe_files[index],
+                    "Program Files",
+                    operating_system,
+                ) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Picks the executable that looks like the main one for an app folder. That's one named like
+/// the folder, or the only one left after ignoring uninstallers, updaters and the like.
+fn pick_main_exe(folder_name: &str, exe_names: &[String]) -> Option<usize> {
+    let simplify = |name: &str| -> String {
+        name.to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect()
+    };
+    let simple_folder_name = simplify(folder_name);
+
+    let candidates: Vec<(usize, String)> = exe_names
+        .iter()
+        .enumerate()
+        .filter(|(_, exe_name)| !is_ignored_exe(exe_name))
+        .map(|(index, exe_name)| {
+            let stem = Path::new(exe_name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (index, simplify(&stem))
+        })
+        .filter(|(_, stem)| !stem.is_empty())
+        .collect();
+
+    let named_like_folder = candidates.iter().find(|(_, stem)| {
+        simple_folder_name.contains(stem.as_str()) || stem.contains(simple_folder_name.as_str())
+    });
+    match (named_like_folder, candidates.len()) {
+        (Some((index, _)), _) => Some(*index),
+        (None, 1) => Some(candidates[0].0),
+        _ => None,
+    }
+}
+
+/// Bundles are found by their bundle identifier where they have one, so they are still found
+/// after being renamed or moved.
+fn find_mac_applications(operating_system: &data::OperatingSystem) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for bundle_path in bundle::find_bundles(&bundle::get_bundle_folders()) {
+        let (Some(app_name), Some(exe_name)) = (bundle_path.file_stem(), bundle_path.file_name())
+        else {
+            continue;
+        };
+        let app_name = app_name.to_string_lossy().to_string();
+
+        let candidate = match bundle::get_bundle_identifier(&bundle_path) {
+            Some(bundle_id) => Some(Candidate {
+                app: data::App::new(
+                    app_name,
+                    exe_name.to_string_lossy().to_string(),
+                    None,
+                    bundle_id,
+                    data::SearchMethod::BundleId,
+                    operating_system.clone(),
+                ),
+                app_path: bundle_path.display().to_string(),
+                source: "Applications",
+            }),
+            None => new_candidate(&app_name, &bundle_path, "Applications", operating_system),
+        };
+        candidates.extend(candidate);
+    }
+
+    candidates.sort_by_key(|candidate| candidate.app.app_name.to_lowercase());
+    candidates
+}
+
+/// The XDG data folders, which hold the desktop entries and icon themes. The user's own folder
+/// comes first.
+pub fn get_data_folders() -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+    match env::var("XDG_DATA_HOME") {
+        Ok(data_home) if !data_home.is_empty() => folders.push(PathBuf::from(data_home)),
+        _ => {
+            if let Some(home) = dirs::home_dir() {
+                folders.push(home.join(".local/share"));
+            }
+        }
+    }
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|data_dirs| !data_dirs.is_empty())
+        .unwrap_or("/usr/local/share:/usr/share".to_string());
+    folders.extend(data_dirs.split(':').map(PathBuf::from));
+    folders
+}
+
+/// The desktop entry files in the applications folder of each data folder.
+pub fn get_desktop_files(data_folders: &[PathBuf]) -> Vec<PathBuf> {
+    let mut desktop_files = Vec::new();
+    for folder in data_folders {
+        let Ok(entries) = fs::read_dir(folder.join("applications")) else {
+            continue;
+        };
+        let mut folder_files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .map(|extension| extension == "desktop")
+                    .unwrap_or(false)
+            })
+            .collect();
+        folder_files.sort();
+        desktop_files.extend(folder_files);
+    }
+
+    desktop_files
+}
+
+fn find_desktop_entries(operating_system: &data::OperatingSystem) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for desktop_file in get_desktop_files(&get_data_folders()) {
+        let Ok(contents) = fs::read_to_string(&desktop_file) else {
+            continue;
+        };
+        let Some((app_name, program)) = parse_desktop_entry(&contents) else {
+            continue;
+        };
+        match paths::find_in_path(&program) {
+            Some(app_path) => {
+                if let Some(candidate) =
+                    new_candidate(&app_name, &app_path, "Desktop Entry", operating_system)
+                {
+                    candidates.push(candidate);
+                }
+            }
+            None => debug!(
+                "Unable to find program '{}' from '{}'",
+                program,
+                desktop_file.display()
+            ),
+        }
+    }
+
+    candidates
+}
+
+/// Reads a key from the desktop entry section, like the Icon.
+pub fn get_desktop_entry_value(contents: &str, key: &str) -> Option<String> {
+    let mut in_desktop_entry = false;
+    for line in contents.lines().map(|line| line.trim()) {
+        if line.starts_with('[') {
+            in_desktop_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_desktop_entry {
+            continue;
+        }
+        if let Some((line_key, value)) = line.split_once('=') {
+            if line_key.trim() == key && !value.trim().is_empty() {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Reads the app name and program from a desktop entry, skipping entries that are hidden or
+/// aren't applications.
+pub fn parse_desktop_entry(contents: &str) -> Option<(String, String)> {
+    let mut in_desktop_entry = false;
+    let mut name: Option<String> = None;
+    let mut exec: Option<String> = None;
+    let mut is_application = false;
+
+    for line in contents.lines().map(|line| line.trim()) {
+        if line.starts_with('[') {
+            in_desktop_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_desktop_entry {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match (key.trim(), value.trim()) {
+            ("Name", value) => name = Some(value.to_string()),
+            ("Exec", value) => exec = Some(value.to_string()),
+            ("Type", value) => is_application = value == "Application",
+            ("NoDisplay", "true") | ("Hidden", "true") => return None,
+            _ => {}
+        }
+    }
+
+    if !is_application {
+        return None;
+    }
+
+    // Skip "env VAR=value" prefixes and field codes like %U, the program is the first thing left
+    let arguments = paths::try_parse_arguments(&exec?)?;
+    let program = arguments
+        .into_iter()
+        .filter(|argument| argument != "env" && !argument.contains('='))
+        .find(|argument| !argument.starts_with('%'))?;
+
+    Some((name?, program))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        data::SearchMethod,
+        discover::{
+            get_search_for_folder, parse_desktop_entry, parse_powershell_rows, pick_main_exe,
+        },
+    };
+
+    #[test]
+    fn parse_desktop_entry_application() {
+        // Arrange
+        let contents = "[Desktop Entry]
+Name=Lemon Editor
+Exec=env GDK_BACKEND=x11 /opt/lemon/lemon-editor %F
+Type=Application
+
+[Desktop Action new-window]
+Name=New Window
+Exec=/opt/lemon/lemon-editor --new-window
+";
+
+        // Act
+        let actual = parse_desktop_entry(contents);
+
+        // Assert
+        assert_eq!(
+            actual,
+            Some((
+                "Lemon Editor".to_string(),
+                "/opt/lemon/lemon-editor".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_desktop_entry_hidden() {
+        // Arrange
+        let contents = "[Desktop Entry]
+Name=Lemon Helper
+Exec=lemon-helper
+Type=Application
+NoDisplay=true
+";
+
+        // Act
+        let actual = parse_desktop_entry(contents);
+
+        // Assert
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn pick_main_exe_named_like_folder() {
+        // Arrange
+        let exe_names = vec![
+            "unins000.exe".to_string(),
+            "crashpad_handler.exe".to_string(),
+            "Notepad++.exe".to_string(),
+            "GUP.exe".to_string(),
+        ];
+
+        // Act
+        let actual = pick_main_exe("Notepad++", &exe_names);
+
+        // Assert
+        assert_eq!(actual, Some(2));
+    }
+
+    #[test]
+    fn pick_main_exe_ambiguous() {
+        // Arrange
+        let exe_names = vec!["first.exe".to_string(), "second.exe".to_string()];
+
+        // Act
+        let actual = pick_main_exe("Lemon Tools", &exe_names);
+
+        // Assert
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn parse_powershell_rows_skips_incomplete_lines() {
+        // Arrange
+        let lines = vec![
+            "Lemon|C:\\Lemon\\lemon.exe".to_string(),
+            "Empty|".to_string(),
+            String::new(),
+        ];
+
+        // Act
+        let actual = parse_powershell_rows(&lines, 2);
+
+        // Assert
+        assert_eq!(
+            actual,
+            vec![vec![
+                "Lemon".to_string(),
+                "C:\\Lemon\\lemon.exe".to_string()
+            ]]
+        );
+    }
+
+    #[test]
+    fn get_search_for_folder_versioned() {
+        // Arrange
+        let folder = Path::new("Lemon").join("1.2.3").join("bin");
+
+        // Act
+        let actual = get_search_for_folder(&folder);
+
+        // Assert
+        assert_eq!(actual, (SearchMethod::FolderSearch, "Lemon".to_string()));
+    }
+
+    #[test]
+    fn get_search_for_folder_version_first_uses_whole_folder() {
+        // Arrange
+        let folder = Path::new("1.2.3").join("bin");
+
+        // Act
+        let actual = get_search_for_folder(&folder);
+
+        // Assert
+        assert_eq!(
+            actual,
+            (SearchMethod::Shortcut, folder.display().to_string())
+        );
+    }
+}
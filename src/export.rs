@@ -0,0 +1,192 @@
+use std::env;
+
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Context, Report};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{constants, data};
+
+/// Bump when the export changes in a way older versions can't read, and add a step to `upgrade`
+/// that turns the previous version into this one.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Header written before the apps, so later versions know how to read the file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportFile {
+    pub format_version: u32,
+    /// Version of App Lemonator that wrote the file. Missing for files upgraded from the bare array.
+    #[serde(default)]
+    pub tool_version: Option<String>,
+    #[serde(default)]
+    pub host_os: Option<String>,
+    #[serde(default)]
+    pub exported_at: Option<DateTime<Utc>>,
+    /// Whether the last opened times were included.
+    #[serde(default)]
+    pub includes_history: bool,
+    pub apps: Vec<ExportedApp>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedApp {
+    #[serde(flatten)]
+    pub app: data::App,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_opened: Option<DateTime<Utc>>,
+}
+
+impl ExportFile {
+    pub fn new(apps: Vec<data::App>, include_history: bool) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            tool_version: Some(constants::CRATE_VERSION.to_string()),
+            host_os: Some(env::consts::OS.to_string()),
+            exported_at: Some(Utc::now()),
+            includes_history: include_history,
+            apps: apps
+                .into_iter()
+                .map(|app| ExportedApp {
+                    last_opened: app.last_opened.filter(|_| include_history),
+                    app,
+                })
+                .collect(),
+        }
+    }
+
+    /// The apps, with when they were last opened when the file included it. They are never
+    /// updated, the path was found on the machine that exported them.
+    pub fn into_apps(self) -> Vec<data::App> {
+        self.apps
+            .into_iter()
+            .map(|exported| {
+                let mut app = exported.app;
+                app.last_opened = exported.last_opened;
+                app
+            })
+            .collect()
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "format version {}, exported by version '{}' on '{}' at '{}'",
+            self.format_version,
+            data::display_option_string(&self.tool_version),
+            data::display_option_string(&self.host_os),
+            data::display_option_utc_datetime_to_local(&self.exported_at)
+        )
+    }
+}
+
+/// Reads an export, upgrading files written in older formats.
+pub fn parse(contents: &str) -> Result<ExportFile, Report> {
+    let mut value: Value = serde_json::from_str(contents).wrap_err("Export file is not JSON")?;
+
+    let mut format_version = get_format_version(&value)?;
+    if format_version > FORMAT_VERSION {
+        return Err(eyre!(
+            "Export file is format version {}, this version of {} only reads up to format version {}. Update {} to import it",
+            format_version,
+            constants::APP_NAME,
+            FORMAT_VERSION,
+            constants::APP_NAME
+        ));
+    }
+    while format_version < FORMAT_VERSION {
+        value = upgrade(value, format_version);
+        format_version += 1;
+    }
+
+    serde_json::from_value(value).wrap_err("Export file has an invalid app")
+}
+
+fn get_format_version(value: &Value) -> Result<u32, Report> {
+    match value {
+        // Exports were a bare array of apps before the header was added
+        Value::Array(_) => Ok(0),
+        Value::Object(header) => header
+            .get("format_version")
+            .and_then(|format_version| format_version.as_u64())
+            .map(|format_version| format_version as u32)
+            .ok_or_else(|| eyre!("Export file has no format version")),
+        _ => Err(eyre!(
+            "Export file is not a list of apps or an export header"
+        )),
+    }
+}
+
+/// Upgrades the file from the format version to the next one.
+fn upgrade(value: Value, from_version: u32) -> Value {
+    match from_version {
+        0 => json!({
+            "format_version": 1,
+            "includes_history": false,
+            "apps": value,
+        }),
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{App, OperatingSystem, SearchMethod},
+        export::{parse, ExportFile, FORMAT_VERSION},
+    };
+
+    #[test]
+    fn parse_upgrades_bare_array() {
+        // Arrange
+        let contents = r#"[{"app_name":"Lemon","exe_name":"lemon.exe","params":null,"search_term":"C:\\Lemon","search_method":"Shortcut","app_path":null,"app_description":null,"app_version":null,"operating_system":"Windows"}]"#;
+
+        // Act
+        let actual = parse(contents).unwrap();
+
+        // Assert
+        assert_eq!(actual.format_version, FORMAT_VERSION);
+        assert_eq!(actual.tool_version, None);
+        assert_eq!(actual.apps.len(), 1);
+        assert_eq!(actual.apps[0].app.app_name, "Lemon");
+    }
+
+    #[test]
+    fn parse_rejects_newer_format() {
+        // Arrange
+        let contents = format!(r#"{{"format_version":{},"apps":[]}}"#, FORMAT_VERSION + 1);
+
+        // Act
+        let actual = parse(&contents);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn round_trip_keeps_history_when_included() {
+        // Arrange
+        let mut app = App::new(
+            "Lemon".to_string(),
+            "lemon.exe".to_string(),
+            None,
+            "C:\\Lemon".to_string(),
+            SearchMethod::Shortcut,
+            OperatingSystem::Windows,
+        );
+        app.last_opened = Some(chrono::Utc::now());
+        app.last_updated = Some(chrono::Utc::now());
+        let with_history =
+            serde_json::to_string(&ExportFile::new(vec![app.clone()], true)).unwrap();
+        let without_history =
+            serde_json::to_string(&ExportFile::new(vec![app.clone()], false)).unwrap();
+
+        // Act
+        let actual_with_history = parse(&with_history).unwrap().into_apps();
+        let actual_without_history = parse(&without_history).unwrap().into_apps();
+
+        // Assert
+        assert_eq!(actual_with_history[0].last_opened, app.last_opened);
+        assert_eq!(actual_with_history[0].last_updated, None);
+        assert!(!with_history.contains("last_updated"));
+        assert_eq!(actual_without_history[0].last_opened, None);
+    }
+}
@@ -2,17 +2,20 @@ use std::{
     env,
     fs::File,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use eyre::{eyre, Context, Report, Result};
-use log::{debug, error};
+use indicatif::ProgressBar;
+use log::{debug, error, warn};
 use owo_colors::OwoColorize;
 use plist::Value;
 use powershell_script::PsScriptBuilder;
 
 use crate::{
-    constants, data,
+    arch, bundle, cancel, constants, data,
     data::SearchMethod,
+    index, lnk,
     paths::{self},
 };
 
@@ -20,32 +23,96 @@ pub fn get_app_file_version(
     app: data::App,
     app_file_version: Option<data::FileVersion>,
 ) -> Result<data::FileVersion, Report> {
-    match app_file_version {
-        Some(app_file_version) => Ok(app_file_version),
-        None => search_for_app_file_version(app.clone()).wrap_err(format!(
+    if let Some(app_file_version) = app_file_version {
+        return Ok(app_file_version);
+    }
+
+    // One spinner for the app, shared by its fallback searches and cleared once they are done
+    let pb = paths::new_search_progress_bar();
+    pb.set_prefix(format!("{} ", app.app_name.blue()));
+    pb.enable_steady_tick(Duration::from_millis(120));
+    let result = search_app_file_version(app, &pb);
+    pb.finish_and_clear();
+    result
+}
+
+pub fn search_app_file_version(
+    app: data::App,
+    pb: &ProgressBar,
+) -> Result<data::FileVersion, Report> {
+    let context = match app.fallback_strategies.is_empty() {
+        true => format!(
             "Failed to get app details for app '{}' using search method '{}' and search term '{}'",
             app.app_name, app.search_method, app.search_term
-        )),
+        ),
+        false => format!(
+            "Failed to get app details for app '{}' using {} search strategies",
+            app.app_name,
+            app.fallback_strategies.len() + 1
+        ),
+    };
+    search_for_app_file_version(app, pb).wrap_err(context)
+}
+
+/// Tries each of the app's search strategies in order, returning the first that finds the app.
+fn search_for_app_file_version(
+    app: data::App,
+    pb: &ProgressBar,
+) -> Result<data::FileVersion, Report> {
+    let strategies = app.get_search_strategies();
+    let mut failures: Vec<(&data::SearchStrategy, Report)> = Vec::new();
+
+    for strategy in &strategies {
+        if cancel::is_cancelled() {
+            break;
+        }
+
+        debug!(
+            "Searching for app '{}' using strategy {}",
+            app.app_name.blue(),
+            strategy
+        );
+        match search_with_strategy(app.with_search_strategy(strategy), pb) {
+            Ok(mut app_file_version) => {
+                app_file_version.matched_strategy = Some(strategy.clone());
+                return Ok(app_file_version);
+            }
+            Err(e) => {
+                debug!("Search strategy {} failed: {:?}", strategy, e);
+                failures.push((strategy, e));
+            }
+        }
+    }
+
+    match failures.len() {
+        0 => Err(eyre!("Search cancelled for app '{}'", app.app_name)),
+        1 => Err(failures.remove(0).1),
+        _ => Err(eyre!(format!(
+            "All {} search strategies failed:\n{}",
+            failures.len(),
+            failures
+                .iter()
+                .map(|(strategy, e)| format!("{} - {:#}", strategy, e))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ))),
     }
 }
 
-fn search_for_app_file_version(app: data::App) -> Result<data::FileVersion, Report> {
+fn search_with_strategy(app: data::App, pb: &ProgressBar) -> Result<data::FileVersion, Report> {
     let app_path = match app.search_method {
-        SearchMethod::PSGetApp => Ok(get_powershell_getxapppackage(app)?),
-        SearchMethod::FolderSearch => Ok(get_folder_search(app)?),
-        SearchMethod::Shortcut => Ok(get_shortcut(app)?),
+        SearchMethod::PSGetApp => get_powershell_getxapppackage(app)?,
+        SearchMethod::FolderSearch => get_folder_search(app, pb)?,
+        SearchMethod::Shortcut => get_shortcut(app)?,
+        SearchMethod::LnkFile => get_lnk_file(app)?,
+        SearchMethod::BundleId => get_bundle_id(app)?,
+        SearchMethod::PathLookup => get_path_lookup(app)?,
     };
 
-    match app_path {
-        Ok(app_path) => {
-            let app_file_version = get_file_version(&app_path)?;
-            Ok(app_file_version)
-        }
-        Err(e) => Err(e),
-    }
+    get_file_version(&app_path)
 }
 
-fn run_powershell_cmd(powershell_cmd: &str) -> Result<Vec<String>, Report> {
+pub fn run_powershell_cmd(powershell_cmd: &str) -> Result<Vec<String>, Report> {
     if env::consts::OS != constants::OS_WINDOWS {
         return Err(eyre!(format!(
             "PowerShell is only supported on Windows, not on '{}'",
@@ -120,29 +187,59 @@ fn get_windows_file_version_information(
     }
 }
 
-fn get_windows_file_description_information(full_path: &str) -> Result<String, eyre::Report> {
+fn get_windows_file_description_information(
+    full_path: &str,
+) -> Result<(String, data::FileMetadata), eyre::Report> {
     let stdout_result = run_powershell_cmd(&format!(
-        r#"(Get-Item "{}").VersionInfo | Format-List -Property FileDescription"#,
+        r#"(Get-Item "{}").VersionInfo | Format-List -Property FileDescription, CompanyName, ProductName, LegalCopyright, FileBuildPart"#,
         full_path
     ));
 
     match stdout_result {
-        Ok(stdout_strings) => {
-            let app_description =
-                get_property_from_stdout(stdout_strings.clone(), "FileDescription :");
-            Ok(app_description)
-        }
+        Ok(stdout_strings) => Ok(get_windows_file_metadata(&stdout_strings)),
         Err(e) => Err(e),
     }
 }
 
-fn get_macos_file_version_information(full_path: &str) -> Result<(String, String), eyre::Report> {
+/// Reads the Format-List output of VersionInfo. Format-List pads the property names to the same
+/// width, so the values are found by name rather than a fixed prefix.
+fn get_windows_file_metadata(stdout_strings: &[String]) -> (String, data::FileMetadata) {
+    let get_value = |property_name: &str| -> Option<String> {
+        stdout_strings
+            .iter()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim() == property_name)
+            .map(|(_, value)| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    (
+        get_value("FileDescription").unwrap_or_default(),
+        data::FileMetadata {
+            app_publisher: get_value("CompanyName"),
+            app_product_name: get_value("ProductName"),
+            app_bundle_id: None,
+            app_copyright: get_value("LegalCopyright"),
+            app_architecture: None,
+            // The build part of the file version, FileVersionRaw.Build. ProductVersion is free text.
+            app_build_number: get_value("FileBuildPart"),
+        },
+    )
+}
+
+fn get_macos_file_version_information(
+    full_path: &str,
+) -> Result<(String, String, data::FileMetadata), eyre::Report> {
     // Construct path to Info.plist
     let plist_path = Path::new(full_path).join("Contents").join("Info.plist");
 
     // Open the plist file
     if !paths::file_exists(&plist_path.display().to_string()) {
-        return Ok(("0.0.0.0".to_string(), "".to_string()));
+        return Ok((
+            "0.0.0.0".to_string(),
+            "".to_string(),
+            data::FileMetadata::default(),
+        ));
     }
     let file = File::open(plist_path)?;
 
@@ -164,7 +261,47 @@ fn get_macos_file_version_information(full_path: &str) -> Result<(String, String
 
     let app_description_str = app_description.unwrap_or("");
 
-    Ok((version_str.to_string(), app_description_str.to_string()))
+    let metadata = value
+        .as_dictionary()
+        .map(get_macos_file_metadata)
+        .unwrap_or_default();
+
+    Ok((
+        version_str.to_string(),
+        app_description_str.to_string(),
+        metadata,
+    ))
+}
+
+fn get_macos_file_metadata(dict: &plist::Dictionary) -> data::FileMetadata {
+    let get_value = |key: &str| -> Option<String> {
+        dict.get(key)
+            .and_then(|value| value.as_string())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let app_architecture = dict
+        .get("LSArchitecturePriority")
+        .and_then(|value| value.as_array())
+        .map(|architectures| {
+            architectures
+                .iter()
+                .filter_map(|architecture| architecture.as_string())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        })
+        .filter(|architectures| !architectures.is_empty());
+
+    // Info.plist has no publisher
+    data::FileMetadata {
+        app_publisher: None,
+        app_product_name: get_value("CFBundleDisplayName").or(get_value("CFBundleName")),
+        app_bundle_id: get_value("CFBundleIdentifier"),
+        app_copyright: get_value("NSHumanReadableCopyright"),
+        app_architecture,
+        app_build_number: get_value("CFBundleVersion"),
+    }
 }
 
 pub fn parse_file_version_to_i32(version: &str) -> Option<(u32, u32, u32, u32)> {
@@ -211,9 +348,19 @@ fn get_file_version(full_path: &str) -> Result<FileVersion, Report> {
 */
 
 fn get_file_version(full_path: &str) -> Result<data::FileVersion, eyre::Report> {
+    let mut file_version = get_file_version_information(full_path)?;
+
+    let architectures = arch::detect(full_path);
+    if !architectures.is_empty() {
+        file_version.metadata.app_architecture = Some(arch::display_architectures(&architectures));
+    }
+    Ok(file_version)
+}
+
+fn get_file_version_information(full_path: &str) -> Result<data::FileVersion, eyre::Report> {
     if env::consts::OS == constants::OS_WINDOWS {
         let (major, minor, build, revision) = get_windows_file_version_information(full_path)?;
-        let app_description = get_windows_file_description_information(full_path)?;
+        let (app_description, metadata) = get_windows_file_description_information(full_path)?;
 
         return Ok(data::FileVersion {
             app_description: app_description.to_string(),
@@ -222,11 +369,14 @@ fn get_file_version(full_path: &str) -> Result<data::FileVersion, eyre::Report>
             minor: minor.parse::<u32>().unwrap_or(0),
             build: build.parse::<u32>().unwrap_or(0),
             revision: revision.parse::<u32>().unwrap_or(0),
+            matched_strategy: None,
+            metadata,
+            sha256: None,
         });
     }
 
     if env::consts::OS == constants::OS_MACOS {
-        let (version, app_description) = get_macos_file_version_information(full_path)?;
+        let (version, app_description, metadata) = get_macos_file_version_information(full_path)?;
 
         match parse_file_version_to_i32(&version) {
             Some((major, minor, build, revision)) => {
@@ -237,6 +387,9 @@ fn get_file_version(full_path: &str) -> Result<data::FileVersion, eyre::Report>
                     minor,
                     build,
                     revision,
+                    matched_strategy: None,
+                    metadata,
+                    sha256: None,
                 });
             }
             None => {
@@ -248,15 +401,23 @@ fn get_file_version(full_path: &str) -> Result<data::FileVersion, eyre::Report>
         }
     }
 
+    // Linux executables have no version resource, so apps are only told apart by path
+    if env::consts::OS == constants::OS_LINUX {
+        return Ok(data::FileVersion::new(
+            String::new(),
+            full_path.to_string(),
+            String::new(),
+        ));
+    }
+
     Err(eyre::eyre!(format!(
-        "get_file_version is only supported on Windows and MacOS, not on '{}'",
+        "get_file_version is only supported on Windows, MacOS and Linux, not on '{}'",
         env::consts::OS
     )))
 }
 
-fn get_folder_search(app: data::App) -> Result<String, Report> {
+fn get_folder_search(app: data::App, pb: &ProgressBar) -> Result<String, Report> {
     debug!("get_folder_search for app '{}'", app.app_name.blue());
-    let mut files: Vec<String> = Vec::new();
 
     let base_folder = paths::get_base_folder(&app.search_term);
 
@@ -267,15 +428,69 @@ fn get_folder_search(app: data::App) -> Result<String, Report> {
         ),));
     }
 
-    paths::find_file_in_folders(&base_folder, &app.exe_name, &mut files);
+    let file_name_matcher = paths::FileNameMatcher::new(&app.exe_name).map_err(|e| eyre!(e))?;
+
+    cancel::install_handler();
+    let files = match index::find_files(&base_folder, &file_name_matcher, &app.search_options, pb) {
+        Some(files) => files,
+        None => {
+            let mut files: Vec<String> = Vec::new();
+            paths::find_file_in_folders(
+                &base_folder,
+                &file_name_matcher,
+                &app.search_options,
+                &mut files,
+                pb,
+            );
+            files
+        }
+    };
 
     if files.is_empty() {
+        if cancel::is_cancelled() {
+            return Err(eyre!(format!(
+                "Search cancelled for '{}' in folder '{}'",
+                &app.exe_name, &base_folder
+            )));
+        }
         return Err(eyre!(format!(
             "No matches found for '{}' using recursive search in folder '{}'",
             &app.exe_name, &base_folder
         )));
     }
 
+    if cancel::is_cancelled() {
+        warn!(
+            "Search cancelled for '{}', using the {} matches found so far",
+            &app.exe_name,
+            files.len()
+        );
+    }
+
+    // Keep the builds that suit the app's architecture preference
+    let files = arch::filter_by_preference(
+        files
+            .into_iter()
+            .map(|file| {
+                let architectures = arch::detect(&file);
+                debug!(
+                    "File '{}' architectures '{}'",
+                    file,
+                    arch::display_architectures(&architectures)
+                );
+                (file, architectures)
+            })
+            .collect(),
+        &app.search_options.search_arch,
+        arch::native(),
+    );
+    if files.is_empty() {
+        return Err(eyre!(format!(
+            "No {} builds of '{}' found in folder '{}'",
+            &app.search_options.search_arch, &app.exe_name, &base_folder
+        )));
+    }
+
     if env::consts::OS == constants::OS_WINDOWS || env::consts::OS == constants::OS_MACOS {
         debug!("Checking file versions for {} files", files.len());
 
@@ -329,3 +544,122 @@ fn get_shortcut(app: data::App) -> Result<String, Report> {
         path.to_string_lossy()
     )))
 }
+
+fn get_lnk_file(app: data::App) -> Result<String, Report> {
+    debug!("get_lnk_file");
+
+    let lnk_path = paths::get_full_path(&paths::get_base_folder(&app.search_term), &app.exe_name);
+    let shell_link = lnk::read(&lnk_path)?;
+    let target_path = match shell_link.target_path {
+        Some(target_path) => paths::expand_env_vars(&target_path),
+        None => {
+            return Err(eyre!(format!(
+                "Shortcut '{}' does not have a target path",
+                lnk_path
+            )))
+        }
+    };
+
+    if paths::check_app_exists(&target_path) {
+        return Ok(target_path);
+    }
+
+    Err(eyre!(format!(
+        "Shortcut '{}' points to '{}', which does not exist",
+        lnk_path, target_path
+    )))
+}
+
+fn get_bundle_id(app: data::App) -> Result<String, Report> {
+    debug!("get_bundle_id");
+
+    let folders = bundle::get_bundle_folders();
+    match bundle::find_bundle_by_id(&folders, &app.search_term) {
+        Some(bundle_path) => Ok(bundle_path),
+        None => Err(eyre!(format!(
+            "No app bundle found with bundle identifier '{}' in folders {}",
+            &app.search_term,
+            folders.join(", ")
+        ))),
+    }
+}
+
+fn get_path_lookup(app: data::App) -> Result<String, Report> {
+    debug!("get_path_lookup");
+
+    match paths::find_in_path(&app.search_term) {
+        Some(app_path) => Ok(app_path.to_string_lossy().to_string()),
+        None => Err(eyre!(format!(
+            "Program '{}' was not found in any folder on the PATH",
+            &app.search_term
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::FileMetadata,
+        finder::{get_macos_file_version_information, get_windows_file_metadata},
+    };
+
+    #[test]
+    fn get_windows_file_metadata_padded_properties() {
+        // Arrange
+        let stdout_strings: Vec<String> = vec![
+            "".to_string(),
+            "FileDescription : Lemon Editor".to_string(),
+            "CompanyName     : Example Ltd.".to_string(),
+            "ProductName     : Lemon".to_string(),
+            "LegalCopyright  : Copyright (C) 2024 Example Ltd.".to_string(),
+            "FileBuildPart   : 42".to_string(),
+        ];
+
+        // Act
+        let (app_description, metadata) = get_windows_file_metadata(&stdout_strings);
+
+        // Assert
+        assert_eq!(app_description, "Lemon Editor");
+        assert_eq!(
+            metadata,
+            FileMetadata {
+                app_publisher: Some("Example Ltd.".to_string()),
+                app_product_name: Some("Lemon".to_string()),
+                app_bundle_id: None,
+                app_copyright: Some("Copyright (C) 2024 Example Ltd.".to_string()),
+                app_architecture: None,
+                app_build_number: Some("42".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn get_macos_file_version_information_fixture_bundle() {
+        // Arrange
+        let bundle = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/bundles/UserApplications/Lemon Editor Beta.app"
+        );
+
+        // Act
+        let (version, app_description, metadata) =
+            get_macos_file_version_information(bundle).unwrap();
+
+        // Assert
+        assert_eq!(version, "2.10.0");
+        assert_eq!(app_description, "Lemon Editor");
+        assert_eq!(
+            metadata,
+            FileMetadata {
+                app_publisher: None,
+                app_product_name: Some("Lemon Editor Beta".to_string()),
+                app_bundle_id: Some("com.example.LemonEditor".to_string()),
+                app_copyright: Some(
+                    "Copyright © 2024 Example Ltd. All rights reserved.".to_string()
+                ),
+                app_architecture: Some("arm64, x86_64".to_string()),
+                app_build_number: Some("2100.42".to_string()),
+            }
+        );
+    }
+}
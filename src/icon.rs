@@ -0,0 +1,592 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Context, Report};
+use log::debug;
+use sha2::{Digest, Sha256};
+
+use crate::{arch, bundle, data, discover, paths};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const ICNS_MAGIC: &[u8] = b"icns";
+
+/// Resource types in a PE file. A group icon lists the sizes, each size is its own icon resource.
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+const RESOURCE_SUBDIRECTORY: u32 = 0x8000_0000;
+
+/// Icon theme sizes to look for, largest first. Only the hicolor theme is checked, every icon
+/// theme falls back to it.
+const THEME_ICON_SIZES: [&str; 9] = [
+    "512x512", "256x256", "128x128", "96x96", "64x64", "48x48", "32x32", "24x24", "16x16",
+];
+
+pub fn get_icons_folder() -> PathBuf {
+    paths::get_data_folder().join("icons")
+}
+
+/// Gets the app's icon as a PNG from the cache, extracting it again when the app has been
+/// updated since it was cached.
+pub fn get_cached_icon(app: &data::App) -> Result<PathBuf, Report> {
+    let app_path = app.app_path.as_ref().ok_or_else(|| {
+        eyre!(
+            "App '{}' has no app path yet, run update to find it",
+            app.app_name
+        )
+    })?;
+
+    let (icon_path, source_path) = get_cache_paths(app_path);
+    if is_cached(&icon_path, &source_path, app_path, app.last_updated) {
+        debug!("Using cached icon '{}'", icon_path.display());
+        return Ok(icon_path);
+    }
+
+    let png = extract(Path::new(app_path))
+        .wrap_err(format!("Unable to get an icon for app '{}'", app.app_name))?;
+    fs::create_dir_all(get_icons_folder()).wrap_err("Unable to create the icons folder")?;
+    fs::write(&icon_path, png)
+        .wrap_err(format!("Unable to save icon to '{}'", icon_path.display()))?;
+    fs::write(&source_path, app_path).wrap_err(format!(
+        "Unable to save icon to '{}'",
+        source_path.display()
+    ))?;
+    debug!("Cached icon '{}'", icon_path.display());
+
+    Ok(icon_path)
+}
+
+/// Icons are cached by a hash of the app path rather than the app's id, so apps in other
+/// databases, or a new app reusing an id, never get each other's icon. The source file next to
+/// the icon holds the app path it came from.
+fn get_cache_paths(app_path: &str) -> (PathBuf, PathBuf) {
+    let key = format!("{:x}", Sha256::digest(app_path.as_bytes()));
+    let folder = get_icons_folder();
+    (
+        folder.join(format!("{}.png", key)),
+        folder.join(format!("{}.source", key)),
+    )
+}
+
+pub fn remove_cached_icon(app: &data::App) {
+    let Some(app_path) = &app.app_path else {
+        return;
+    };
+    let (icon_path, source_path) = get_cache_paths(app_path);
+    for path in [icon_path, source_path] {
+        if path.exists() {
+            if let Err(error) = fs::remove_file(&path) {
+                debug!("Unable to remove '{}': {:?}", path.display(), error);
+            }
+        }
+    }
+}
+
+pub fn clear_cached_icons() {
+    let folder = get_icons_folder();
+    if folder.exists() {
+        if let Err(error) = fs::remove_dir_all(&folder) {
+            debug!("Unable to remove '{}': {:?}", folder.display(), error);
+        }
+    }
+}
+
+/// The icon is out of date when it came from another path, or the app was updated or its file
+/// changed after the icon was saved.
+fn is_cached(
+    icon_path: &Path,
+    source_path: &Path,
+    app_path: &str,
+    last_updated: Option<DateTime<Utc>>,
+) -> bool {
+    match fs::read_to_string(source_path) {
+        Ok(source) if source == app_path => {}
+        _ => return false,
+    }
+    let Ok(modified) = fs::metadata(icon_path).and_then(|metadata| metadata.modified()) else {
+        return false;
+    };
+    let modified = DateTime::<Utc>::from(modified);
+    let is_after_update = match last_updated {
+        Some(last_updated) => modified >= last_updated,
+        None => true,
+    };
+    let is_after_app_changed = match fs::metadata(app_path).and_then(|metadata| metadata.modified())
+    {
+        Ok(app_modified) => modified >= DateTime::<Utc>::from(app_modified),
+        Err(_) => true,
+    };
+    is_after_update && is_after_app_changed
+}
+
+/// Extracts the icon as a PNG. App bundles use their icns file, Windows exes their icon
+/// resources, anything else the icon of the desktop entry that runs it.
+pub fn extract(app_path: &Path) -> Result<Vec<u8>, Report> {
+    if let Some(bundle) = app_path.ancestors().find(|path| bundle::is_bundle(path)) {
+        let icon_path = bundle::get_bundle_icon_path(bundle)
+            .ok_or_else(|| eyre!("App bundle '{}' has no icon file", bundle.display()))?;
+        let bytes =
+            fs::read(&icon_path).wrap_err(format!("Unable to read '{}'", icon_path.display()))?;
+        return extract_from_icns(&bytes);
+    }
+
+    let bytes = fs::read(app_path).wrap_err(format!("Unable to read '{}'", app_path.display()))?;
+    if bytes.starts_with(b"MZ") {
+        return extract_from_pe(&bytes);
+    }
+
+    let data_folders = discover::get_data_folders();
+    let icon_name = find_desktop_icon_name(app_path, &data_folders).ok_or_else(|| {
+        eyre!(
+            "No desktop entry with an icon runs '{}'",
+            app_path.display()
+        )
+    })?;
+    let icon_path = find_theme_icon(&icon_name, &data_folders).ok_or_else(|| {
+        eyre!(
+            "Unable to find a PNG for icon '{}', only PNG icons are supported",
+            icon_name
+        )
+    })?;
+    fs::read(&icon_path).wrap_err(format!("Unable to read '{}'", icon_path.display()))
+}
+
+/// Picks the largest PNG in an icns file. Older icns files only hold raw bitmaps, which aren't
+/// supported.
+pub fn extract_from_icns(bytes: &[u8]) -> Result<Vec<u8>, Report> {
+    if !bytes.starts_with(ICNS_MAGIC) {
+        return Err(eyre!("Not an icns file"));
+    }
+    let length = (arch::read_u32(bytes, 4, true).unwrap_or_default() as usize).min(bytes.len());
+
+    let mut largest: Option<(u32, &[u8])> = None;
+    let mut offset = 8;
+    while offset + 8 <= length {
+        let Some(entry_length) = arch::read_u32(bytes, offset + 4, true) else {
+            break;
+        };
+        let entry_length = entry_length as usize;
+        if entry_length < 8 {
+            break;
+        }
+        if let Some(data) = bytes.get(offset + 8..offset + entry_length) {
+            if let Some(width) = get_png_width(data) {
+                if largest
+                    .map(|(largest_width, _)| width > largest_width)
+                    .unwrap_or(true)
+                {
+                    largest = Some((width, data));
+                }
+            }
+        }
+        offset += entry_length;
+    }
+
+    largest
+        .map(|(_, data)| data.to_vec())
+        .ok_or_else(|| eyre!("icns file has no PNG images, older icns files aren't supported"))
+}
+
+/// Picks the largest image of the exe's first group icon, which is the one Explorer shows.
+pub fn extract_from_pe(bytes: &[u8]) -> Result<Vec<u8>, Report> {
+    let resources = PeResources::new(bytes).ok_or_else(|| eyre!("Exe has no resources"))?;
+    let group = resources
+        .find(RT_GROUP_ICON, None)
+        .ok_or_else(|| eyre!("Exe has no icon"))?;
+
+    // Each entry is 14 bytes after the 6 byte header, the width is 0 for 256 pixels
+    let count = arch::read_u16(group, 4, false).unwrap_or_default() as usize;
+    let icon_id = (0..count)
+        .filter_map(|index| {
+            let entry = group.get(6 + index * 14..6 + (index + 1) * 14)?;
+            let width = match entry[0] {
+                0 => 256,
+                width => width as u32,
+            };
+            let bit_count = arch::read_u16(entry, 6, false)?;
+            let id = arch::read_u16(entry, 12, false)?;
+            Some(((width, bit_count), id))
+        })
+        .max_by_key(|(size, _)| *size)
+        .map(|(_, id)| id)
+        .ok_or_else(|| eyre!("Exe icon has no images"))?;
+
+    let image = resources
+        .find(RT_ICON, Some(icon_id as u32))
+        .ok_or_else(|| eyre!("Exe icon image {} is missing", icon_id))?;
+    match image.starts_with(PNG_SIGNATURE) {
+        true => Ok(image.to_vec()),
+        false => dib_to_png(image),
+    }
+}
+
+/// The resource section of a PE file, a tree of type, then name, then language.
+struct PeResources<'a> {
+    bytes: &'a [u8],
+    /// File offset of the root directory.
+    root: usize,
+    /// Virtual address of the section, data entries are relative to it.
+    virtual_address: usize,
+}
+
+impl<'a> PeResources<'a> {
+    fn new(bytes: &'a [u8]) -> Option<Self> {
+        let pe_offset = arch::read_u32(bytes, 0x3C, false)? as usize;
+        if bytes.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let coff = pe_offset + 4;
+        let section_count = arch::read_u16(bytes, coff + 2, false)? as usize;
+        let optional_header_size = arch::read_u16(bytes, coff + 16, false)? as usize;
+        let optional_header = coff + 20;
+
+        // The data directories come after the fields, which are wider in 64 bit exes
+        let (directory_count_offset, data_directories) =
+            match arch::read_u16(bytes, optional_header, false)? {
+                0x10B => (optional_header + 92, optional_header + 96),
+                0x20B => (optional_header + 108, optional_header + 112),
+                _ => return None,
+            };
+        if arch::read_u32(bytes, directory_count_offset, false)? <= 2 {
+            return None;
+        }
+        let resource_address = arch::read_u32(bytes, data_directories + 16, false)? as usize;
+        if resource_address == 0 {
+            return None;
+        }
+
+        let sections = optional_header + optional_header_size;
+        (0..section_count).find_map(|index| {
+            let section = sections + index * 40;
+            let virtual_size = arch::read_u32(bytes, section + 8, false)? as usize;
+            let virtual_address = arch::read_u32(bytes, section + 12, false)? as usize;
+            let raw_size = arch::read_u32(bytes, section + 16, false)? as usize;
+            let raw_offset = arch::read_u32(bytes, section + 20, false)? as usize;
+            let section_end = virtual_address + virtual_size.max(raw_size);
+            if resource_address < virtual_address || resource_address >= section_end {
+                return None;
+            }
+            Some(PeResources {
+                bytes,
+                root: raw_offset + resource_address - virtual_address,
+                virtual_address: virtual_address - raw_offset,
+            })
+        })
+    }
+
+    /// Finds a resource by type and id, or the first of the type when there is no id.
+    fn find(&self, resource_type: u32, id: Option<u32>) -> Option<&'a [u8]> {
+        let type_directory = self
+            .get_entries(0)
+            .into_iter()
+            .find(|(name, _)| *name == resource_type)
+            .and_then(|(_, target)| get_subdirectory(target))?;
+        let name_directory = self
+            .get_entries(type_directory)
+            .into_iter()
+            .find(|(name, _)| id.map(|id| *name == id).unwrap_or(true))
+            .and_then(|(_, target)| get_subdirectory(target))?;
+        let (_, data_entry) = self.get_entries(name_directory).into_iter().next()?;
+        if get_subdirectory(data_entry).is_some() {
+            return None;
+        }
+
+        let data_entry = self.root + data_entry as usize;
+        let data_address = arch::read_u32(self.bytes, data_entry, false)? as usize;
+        let size = arch::read_u32(self.bytes, data_entry + 4, false)? as usize;
+        let data_offset = data_address.checked_sub(self.virtual_address)?;
+        self.bytes.get(data_offset..data_offset + size)
+    }
+
+    /// The name or id and target of each entry in a directory. Named entries have the top bit
+    /// of the name set, so never match an id.
+    fn get_entries(&self, directory: usize) -> Vec<(u32, u32)> {
+        let directory = self.root + directory;
+        let named_count = arch::read_u16(self.bytes, directory + 12, false).unwrap_or_default();
+        let id_count = arch::read_u16(self.bytes, directory + 14, false).unwrap_or_default();
+        (0..(named_count as usize + id_count as usize))
+            .map_while(|index| {
+                let entry = directory + 16 + index * 8;
+                Some((
+                    arch::read_u32(self.bytes, entry, false)?,
+                    arch::read_u32(self.bytes, entry + 4, false)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+fn get_subdirectory(target: u32) -> Option<usize> {
+    match target & RESOURCE_SUBDIRECTORY {
+        0 => None,
+        _ => Some((target & !RESOURCE_SUBDIRECTORY) as usize),
+    }
+}
+
+/// Converts an icon bitmap to a PNG. The bitmap is twice the icon's height, the colours bottom
+/// up then the transparency mask, one bit a pixel.
+fn dib_to_png(image: &[u8]) -> Result<Vec<u8>, Report> {
+    let read_u32 = |offset| arch::read_u32(image, offset, false);
+    let header_size = read_u32(0).ok_or_else(|| eyre!("Icon image is empty"))? as usize;
+    let width = read_u32(4).unwrap_or_default() as usize;
+    let height = read_u32(8).unwrap_or_default() as usize / 2;
+    let bit_count = arch::read_u16(image, 14, false).unwrap_or_default() as usize;
+    if width == 0 || width > 256 || height == 0 || height > 256 {
+        return Err(eyre!(
+            "Icon image is {}x{}, which isn't valid",
+            width,
+            height
+        ));
+    }
+    if read_u32(16) != Some(0) || ![1, 4, 8, 24, 32].contains(&bit_count) {
+        return Err(eyre!("Icon image format isn't supported"));
+    }
+
+    let palette_size = match (bit_count, read_u32(32).unwrap_or_default() as usize) {
+        (bit_count, 0) if bit_count <= 8 => 1 << bit_count,
+        (bit_count, colours_used) if bit_count <= 8 => colours_used,
+        _ => 0,
+    };
+    let palette = header_size;
+    let colours = palette + palette_size * 4;
+    let colours_stride = (width * bit_count).div_ceil(32) * 4;
+    let mask = colours + colours_stride * height;
+    let mask_stride = width.div_ceil(32) * 4;
+    if image.len() < mask {
+        return Err(eyre!("Icon image is cut short"));
+    }
+
+    let mut rgba = vec![0u8; width * height * 4];
+    for y in 0..height {
+        let row = colours + (height - 1 - y) * colours_stride;
+        for x in 0..width {
+            let bgra = match bit_count {
+                32 => image[row + x * 4..row + x * 4 + 4].try_into().unwrap(),
+                24 => [
+                    image[row + x * 3],
+                    image[row + x * 3 + 1],
+                    image[row + x * 3 + 2],
+                    255,
+                ],
+                _ => {
+                    let bit = x * bit_count;
+                    let shift = 8 - bit_count - bit % 8;
+                    let index = (image[row + bit / 8] >> shift) as usize & ((1 << bit_count) - 1);
+                    let colour = palette + index * 4;
+                    match image.get(colour..colour + 3) {
+                        Some(colour) => [colour[0], colour[1], colour[2], 255],
+                        None => [0, 0, 0, 255],
+                    }
+                }
+            };
+            let pixel = (y * width + x) * 4;
+            rgba[pixel..pixel + 4].copy_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+        }
+    }
+
+    // Bitmaps with an alpha channel can still leave it empty and rely on the mask
+    let has_alpha = bit_count == 32 && rgba.chunks(4).any(|pixel| pixel[3] != 0);
+    if !has_alpha {
+        for y in 0..height {
+            let row = mask + (height - 1 - y) * mask_stride;
+            for x in 0..width {
+                let is_transparent = image
+                    .get(row + x / 8)
+                    .map(|byte| byte & (0x80 >> (x % 8)) != 0)
+                    .unwrap_or(false);
+                rgba[(y * width + x) * 4 + 3] = if is_transparent { 0 } else { 255 };
+            }
+        }
+    }
+
+    encode_png(width as u32, height as u32, &rgba)
+}
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, Report> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .wrap_err("Unable to write icon as PNG")?;
+    Ok(png)
+}
+
+fn get_png_width(bytes: &[u8]) -> Option<u32> {
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+    // The header chunk is always first, its width straight after the chunk length and type
+    arch::read_u32(bytes, 16, true)
+}
+
+/// The Icon of the first desktop entry whose program is the app.
+fn find_desktop_icon_name(app_path: &Path, data_folders: &[PathBuf]) -> Option<String> {
+    discover::get_desktop_files(data_folders)
+        .into_iter()
+        .find_map(|desktop_file| {
+            let contents = fs::read_to_string(&desktop_file).ok()?;
+            let (_, program) = discover::parse_desktop_entry(&contents)?;
+            if paths::find_in_path(&program)? != app_path {
+                return None;
+            }
+            debug!(
+                "App '{}' runs from '{}'",
+                app_path.display(),
+                desktop_file.display()
+            );
+            discover::get_desktop_entry_value(&contents, "Icon")
+        })
+}
+
+/// Looks an icon up in the hicolor theme of each data folder, largest size first, then in
+/// pixmaps. An Icon can also be the path of the image.
+fn find_theme_icon(icon_name: &str, data_folders: &[PathBuf]) -> Option<PathBuf> {
+    let icon_path = Path::new(icon_name);
+    if icon_path.is_absolute() {
+        let is_png = icon_path
+            .extension()
+            .map(|extension| extension.eq_ignore_ascii_case("png"))
+            .unwrap_or(false);
+        return (is_png && icon_path.is_file()).then(|| icon_path.to_path_buf());
+    }
+
+    let file_name = &format!("{}.png", icon_name);
+    THEME_ICON_SIZES
+        .iter()
+        .flat_map(|size| {
+            data_folders.iter().map(move |folder| {
+                folder
+                    .join("icons")
+                    .join("hicolor")
+                    .join(size)
+                    .join("apps")
+                    .join(file_name)
+            })
+        })
+        .chain(
+            data_folders
+                .iter()
+                .map(|folder| folder.join("pixmaps").join(file_name)),
+        )
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+    };
+
+    use uuid::Uuid;
+
+    use crate::icon::{dib_to_png, extract, extract_from_pe, find_theme_icon, is_cached};
+
+    fn get_fixture(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(path)
+    }
+
+    fn decode_png(bytes: &[u8]) -> (u32, u32, Vec<u8>) {
+        let decoder = png::Decoder::new(bytes);
+        let mut reader = decoder.read_info().unwrap();
+        let mut rgba = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut rgba).unwrap();
+        (info.width, info.height, rgba)
+    }
+
+    #[test]
+    fn extract_from_pe_largest_bitmap() {
+        // Arrange
+        let bytes = fs::read(get_fixture("icons/pe_icon.exe")).unwrap();
+
+        // Act
+        let actual = extract_from_pe(&bytes).unwrap();
+
+        // Assert
+        let (width, height, rgba) = decode_png(&actual);
+        assert_eq!((width, height), (4, 4));
+        assert_eq!(rgba[0..4], [255, 0, 0, 255]);
+        assert_eq!(rgba[4..8], [0, 255, 0, 128]);
+    }
+
+    #[test]
+    fn dib_to_png_palette_with_mask() {
+        // Arrange - a 2x2 1 bit bitmap, black and white, with the bottom right pixel masked
+        let mut image = vec![0u8; 40];
+        image[0] = 40;
+        image[4] = 2;
+        image[8] = 4;
+        image[12] = 1;
+        image[14] = 1;
+        image.extend([0, 0, 0, 0, 255, 255, 255, 0]);
+        // Colours, bottom row first, each row padded to 4 bytes
+        image.extend([0b0100_0000, 0, 0, 0, 0b1000_0000, 0, 0, 0]);
+        image.extend([0b0100_0000, 0, 0, 0, 0, 0, 0, 0]);
+
+        // Act
+        let actual = dib_to_png(&image).unwrap();
+
+        // Assert
+        let (_, _, rgba) = decode_png(&actual);
+        assert_eq!(
+            rgba,
+            [255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 0]
+        );
+    }
+
+    #[test]
+    fn extract_app_bundle_largest_icns_image() {
+        // Arrange
+        let app_path = get_fixture("bundles/Applications/Utilities/Lime.app/Contents/MacOS/Lime");
+
+        // Act
+        let actual = extract(&app_path).unwrap();
+
+        // Assert
+        let (width, height, _) = decode_png(&actual);
+        assert_eq!((width, height), (32, 32));
+    }
+
+    #[test]
+    fn find_theme_icon_largest_size_then_pixmaps() {
+        // Arrange
+        let data_folders = vec![get_fixture("icons/share")];
+
+        // Act
+        let lime = find_theme_icon("lime", &data_folders).unwrap();
+        let lemon = find_theme_icon("lemon", &data_folders).unwrap();
+
+        // Assert
+        assert!(lime.ends_with("hicolor/256x256/apps/lime.png"));
+        assert!(lemon.ends_with("pixmaps/lemon.png"));
+        assert_eq!(find_theme_icon("orange", &data_folders), None);
+    }
+
+    #[test]
+    fn is_cached_checks_source_path() {
+        // Arrange
+        let folder = env::temp_dir().join(format!("app-lemonator-icons-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let icon_path = folder.join("icon.png");
+        let source_path = folder.join("icon.source");
+        fs::write(&icon_path, "").unwrap();
+        fs::write(&source_path, "/opt/lemon/lemon").unwrap();
+
+        // Act
+        let actual_same = is_cached(&icon_path, &source_path, "/opt/lemon/lemon", None);
+        let actual_other = is_cached(&icon_path, &source_path, "/opt/lime/lime", None);
+
+        // Assert
+        assert!(actual_same);
+        assert!(!actual_other);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
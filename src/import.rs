@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use tabled::{builder::Builder, settings::Style};
+
+use crate::data;
+
+/// An app read from the file being imported, or why it couldn't be read.
+#[derive(Debug)]
+pub enum ImportItem {
+    App {
+        /// Where the app is in the file, like the line, for finding it when there is a problem.
+        location: String,
+        app: Box<data::App>,
+    },
+    /// Failed to read, like a CSV row with a search method that doesn't exist.
+    Invalid { location: String, error: String },
+    /// An entry in another launcher's config that can't be turned into an app, like a link.
+    Unmapped { source: String, reason: String },
+}
+
+/// What importing an app will do to the database.
+#[derive(Debug)]
+pub enum ImportChange {
+    Add(data::App),
+    Skip(data::App),
+    Overwrite {
+        current: data::App,
+        imported: data::App,
+    },
+    Merge {
+        current: data::App,
+        merged: data::App,
+    },
+    Rename {
+        original_name: String,
+        renamed: data::App,
+    },
+}
+
+impl ImportChange {
+    pub fn get_app(&self) -> &data::App {
+        match self {
+            ImportChange::Add(app) | ImportChange::Skip(app) => app,
+            ImportChange::Overwrite { imported, .. } => imported,
+            ImportChange::Merge { merged, .. } => merged,
+            ImportChange::Rename { renamed, .. } => renamed,
+        }
+    }
+}
+
+/// Apps in the database by lower case name, kept up to date as apps are imported so later apps in
+/// the same file see the earlier ones.
+pub struct ImportState {
+    apps: HashMap<String, data::App>,
+}
+
+impl ImportState {
+    pub fn new(apps: Vec<data::App>) -> Self {
+        Self {
+            apps: apps
+                .into_iter()
+                .map(|app| (app.app_name.to_lowercase(), app))
+                .collect(),
+        }
+    }
+
+    fn get(&self, app_name: &str) -> Option<&data::App> {
+        self.apps.get(&app_name.to_lowercase())
+    }
+
+    /// Works out what importing the app will do, without changing anything.
+    pub fn plan(&self, app: data::App, on_conflict: &data::ImportConflict) -> ImportChange {
+        let Some(current) = self.get(&app.app_name) else {
+            return ImportChange::Add(app);
+        };
+
+        match on_conflict {
+            data::ImportConflict::Skip => ImportChange::Skip(app),
+            data::ImportConflict::Overwrite => ImportChange::Overwrite {
+                current: current.clone(),
+                imported: app,
+            },
+            data::ImportConflict::Merge => {
+                let mut merged = current.clone();
+                merged.fill_empty_from(&app);
+                match merged == *current {
+                    true => ImportChange::Skip(app),
+                    false => ImportChange::Merge {
+                        current: current.clone(),
+                        merged,
+                    },
+                }
+            }
+            data::ImportConflict::Rename => {
+                let original_name = app.app_name.clone();
+                let mut renamed = app;
+                renamed.app_name = (2..)
+                    .map(|number| format!("{} ({})", original_name, number))
+                    .find(|app_name| self.get(app_name).is_none())
+                    .unwrap_or_default();
+                ImportChange::Rename {
+                    original_name,
+                    renamed,
+                }
+            }
+        }
+    }
+
+    /// Records the change, once it has been made or would have been for a dry run.
+    pub fn apply(&mut self, change: &ImportChange) {
+        if let ImportChange::Skip(_) = change {
+            return;
+        }
+        let app = change.get_app();
+        self.apps.insert(app.app_name.to_lowercase(), app.clone());
+    }
+}
+
+fn get_fields(app: &data::App) -> Vec<(&'static str, String)> {
+    vec![
+        ("Exe Name", app.exe_name.clone()),
+        ("Params", data::display_option_string(&app.params)),
+        ("Search Term", app.search_term.clone()),
+        ("Search Method", app.search_method.to_string()),
+        ("Operating System", app.operating_system.to_string()),
+        (
+            "Search Options",
+            data::display_search_options(&app.search_options),
+        ),
+        (
+            "Fallbacks",
+            data::display_search_strategies(&app.fallback_strategies),
+        ),
+        ("Verify", data::display_verify_options(&app.verify_options)),
+        ("Variants", data::display_variants(&app.variants)),
+    ]
+}
+
+/// The fields that differ between the apps, as field name, current value and new value.
+pub fn diff_fields(current: Option<&data::App>, new: &data::App) -> Vec<[String; 3]> {
+    let current_fields = current.map(get_fields).unwrap_or_default();
+    get_fields(new)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (field, new_value))| {
+            let current_value = current_fields
+                .get(index)
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            // New apps only list the fields that are set
+            match current_value == new_value || (current.is_none() && new_value == "N/A") {
+                true => None,
+                false => Some([field.to_string(), current_value, new_value]),
+            }
+        })
+        .collect()
+}
+
+/// A table of the fields each change sets, for showing what an import would do.
+pub fn display_changes(changes: &[ImportChange]) -> String {
+    let mut builder = Builder::default();
+    builder.push_record(["App Name", "Change", "Field", "Current", "Imported"]);
+
+    for change in changes {
+        let (change_name, current, rows) = match change {
+            ImportChange::Add(app) => ("Add", None, diff_fields(None, app)),
+            ImportChange::Skip(_) => ("Skip", None, vec![]),
+            ImportChange::Overwrite { current, imported } => (
+                "Overwrite",
+                Some(current),
+                diff_fields(Some(current), imported),
+            ),
+            ImportChange::Merge { current, merged } => {
+                ("Merge", Some(current), diff_fields(Some(current), merged))
+            }
+            ImportChange::Rename {
+                original_name,
+                renamed,
+            } => {
+                let mut rows = vec![[
+                    "App Name".to_string(),
+                    original_name.clone(),
+                    renamed.app_name.clone(),
+                ]];
+                rows.extend(diff_fields(None, renamed));
+                ("Rename", None, rows)
+            }
+        };
+        let app_name = current
+            .map(|app| app.app_name.clone())
+            .unwrap_or(change.get_app().app_name.clone());
+
+        match rows.is_empty() {
+            true => builder.push_record([app_name, change_name.to_string()]),
+            false => {
+                for [field, current_value, new_value] in rows {
+                    builder.push_record([
+                        app_name.clone(),
+                        change_name.to_string(),
+                        field,
+                        current_value,
+                        new_value,
+                    ]);
+                }
+            }
+        }
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern());
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{App, ImportConflict, OperatingSystem, SearchMethod},
+        import::{ImportChange, ImportState},
+    };
+
+    fn new_app(app_name: &str, params: Option<&str>) -> App {
+        App::new(
+            app_name.to_string(),
+            "lemon.exe".to_string(),
+            params.map(|params| params.to_string()),
+            "%localappdata%\\Lemon".to_string(),
+            SearchMethod::FolderSearch,
+            OperatingSystem::Windows,
+        )
+    }
+
+    #[test]
+    fn plan_merge_fills_empty_fields() {
+        // Arrange
+        let state = ImportState::new(vec![new_app("Lemon", None)]);
+        let imported = new_app("lemon", Some("--fresh"));
+
+        // Act
+        let actual = state.plan(imported, &ImportConflict::Merge);
+
+        // Assert
+        match actual {
+            ImportChange::Merge { current, merged } => {
+                assert_eq!(current.params, None);
+                assert_eq!(merged.app_name, "Lemon");
+                assert_eq!(merged.params, Some("--fresh".to_string()));
+            }
+            _ => panic!("Expected a merge, got {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn plan_merge_nothing_to_fill_skips() {
+        // Arrange
+        let state = ImportState::new(vec![new_app("Lemon", Some("--stale"))]);
+        let imported = new_app("Lemon", Some("--fresh"));
+
+        // Act
+        let actual = state.plan(imported, &ImportConflict::Merge);
+
+        // Assert
+        assert!(matches!(actual, ImportChange::Skip(_)));
+    }
+
+    #[test]
+    fn plan_rename_finds_unused_name() {
+        // Arrange
+        let mut state = ImportState::new(vec![new_app("Lemon", None), new_app("Lemon (2)", None)]);
+
+        // Act
+        let first = state.plan(new_app("Lemon", None), &ImportConflict::Rename);
+        state.apply(&first);
+        let second = state.plan(new_app("Lemon", None), &ImportConflict::Rename);
+
+        // Assert
+        assert_eq!(first.get_app().app_name, "Lemon (3)");
+        assert_eq!(second.get_app().app_name, "Lemon (4)");
+    }
+
+    #[test]
+    fn plan_no_conflict_adds() {
+        // Arrange
+        let state = ImportState::new(vec![new_app("Lemon", None)]);
+
+        // Act
+        let actual = state.plan(new_app("Lime", None), &ImportConflict::Overwrite);
+
+        // Assert
+        assert!(matches!(actual, ImportChange::Add(_)));
+    }
+}
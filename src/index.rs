@@ -0,0 +1,580 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    time::{Instant, UNIX_EPOCH},
+};
+
+use chrono::{Duration, Utc};
+use eyre::{eyre, Report, Result};
+use glob::Pattern;
+use indicatif::ProgressBar;
+use log::{debug, error};
+
+use crate::{cancel, constants, data, db, paths};
+
+/// How long an index is trusted before a search refreshes it, even when it has a match.
+const INDEX_MAX_AGE_MINUTES: i64 = 60;
+
+/// File extensions treated as executables on Windows.
+const WINDOWS_EXECUTABLE_EXTENSIONS: [&str; 5] = ["exe", "com", "bat", "cmd", "ps1"];
+
+/// Finds files in the index of the base folder. The index is refreshed first when it is missing
+/// or stale, and again when it has no match or a match has gone. Returns None when the index
+/// can't answer, so the caller can search the folder instead.
+pub fn find_files(
+    base_folder: &str,
+    file_name_matcher: &paths::FileNameMatcher,
+    search_options: &data::SearchOptions,
+    pb: &ProgressBar,
+) -> Option<Vec<String>> {
+    // The index never follows symbolic links, so it can't answer these searches
+    if search_options.search_follow_symlinks {
+        return None;
+    }
+
+    let is_stale = match db::get_index_last_indexed(base_folder) {
+        Ok(Some(last_indexed)) => {
+            Utc::now() - last_indexed > Duration::try_minutes(INDEX_MAX_AGE_MINUTES).unwrap()
+        }
+        Ok(None) => true,
+        Err(e) => {
+            error!("Unable to read index for '{}': {:?}", base_folder, e);
+            return None;
+        }
+    };
+
+    let mut files = match is_stale {
+        true => Vec::new(),
+        false => lookup(base_folder, file_name_matcher, search_options)?,
+    };
+
+    // No match, or a match that no longer exists, may mean the folder changed since it was indexed
+    if is_stale || files.is_empty() || files.iter().any(|file| !paths::file_exists(file)) {
+        if let Err(e) = refresh(base_folder, search_options, pb) {
+            error!("Unable to refresh index for '{}': {:?}", base_folder, e);
+            return None;
+        }
+        files = lookup(base_folder, file_name_matcher, search_options)?;
+    }
+
+    // Nothing found may just mean the file isn't an executable we index, so search the folder
+    if files.is_empty() {
+        return None;
+    }
+
+    if let Some(max_matches) = search_options.search_max_matches {
+        files.truncate(max_matches as usize);
+    }
+    debug!("Index matches for '{}' - {:?}", base_folder, files);
+    Some(files)
+}
+
+fn lookup(
+    base_folder: &str,
+    file_name_matcher: &paths::FileNameMatcher,
+    search_options: &data::SearchOptions,
+) -> Option<Vec<String>> {
+    match db::get_index_files(base_folder) {
+        Ok(indexed_files) => Some(filter_files(
+            base_folder,
+            indexed_files,
+            file_name_matcher,
+            search_options,
+        )),
+        Err(e) => {
+            error!("Unable to read index for '{}': {:?}", base_folder, e);
+            None
+        }
+    }
+}
+
+/// Keeps the indexed files that match the file name and are within the search options.
+fn filter_files(
+    base_folder: &str,
+    indexed_files: Vec<data::IndexedFile>,
+    file_name_matcher: &paths::FileNameMatcher,
+    search_options: &data::SearchOptions,
+) -> Vec<String> {
+    let excludes = paths::get_exclude_patterns(search_options);
+
+    indexed_files
+        .into_iter()
+        .filter(|file| file_name_matcher.matches(&file.file_name))
+        .filter(|file| match search_options.search_max_depth {
+            // Files are one deeper than their folder, so the base folder's files are depth 1
+            Some(max_depth) => file.depth <= max_depth + 1,
+            None => true,
+        })
+        .filter(|file| !is_indexed_path_excluded(base_folder, &file.file_path, &excludes))
+        .map(|file| file.file_path)
+        .collect()
+}
+
+fn is_indexed_path_excluded(base_folder: &str, file_path: &str, excludes: &[Pattern]) -> bool {
+    match Path::new(file_path).strip_prefix(base_folder) {
+        Ok(relative_path) => relative_path
+            .iter()
+            .any(|component| paths::is_excluded(&component.to_string_lossy(), excludes)),
+        Err(_) => false,
+    }
+}
+
+/// Brings the index of the base folder up to date. Only folders whose modified time has changed
+/// are read again, a folder's modified time changes whenever something is added to or removed
+/// from it.
+///
+/// The walk honours the search options. Folders it skips, for being too deep, excluded or past
+/// the time budget, are kept in the index as they are, as other apps can share the base folder.
+pub fn refresh(
+    base_folder: &str,
+    search_options: &data::SearchOptions,
+    pb: &ProgressBar,
+) -> Result<(), Report> {
+    debug!(
+        "Refreshing index for '{}', options {:?}",
+        base_folder, search_options
+    );
+    let known_folders = db::get_index_folders(base_folder)?;
+    let (changed_folders, removed_folders) = walk(base_folder, known_folders, search_options, pb)?;
+    db::save_index_changes(base_folder, &changed_folders, &removed_folders)
+}
+
+/// Walks the base folder against the folders already indexed, returning the folders that have
+/// changed and the paths of those that have gone.
+fn walk(
+    base_folder: &str,
+    known_folders: Vec<data::IndexedFolder>,
+    search_options: &data::SearchOptions,
+    pb: &ProgressBar,
+) -> Result<(Vec<data::IndexedFolderChange>, Vec<String>), Report> {
+    if !paths::folder_exists(base_folder) {
+        return Err(eyre!("Base Folder '{}' does not exist", base_folder));
+    }
+
+    let known_folders: HashMap<String, data::IndexedFolder> = known_folders
+        .into_iter()
+        .map(|folder| (folder.folder_path.clone(), folder))
+        .collect();
+
+    let mut known_subfolders: HashMap<PathBuf, Vec<&data::IndexedFolder>> = HashMap::new();
+    for folder in known_folders.values() {
+        if let Some(parent) = Path::new(&folder.folder_path).parent() {
+            known_subfolders
+                .entry(parent.to_path_buf())
+                .or_default()
+                .push(folder);
+        }
+    }
+
+    let excludes = paths::get_exclude_patterns(search_options);
+    let start = Instant::now();
+    let mut folders_to_check = vec![(base_folder.to_string(), 0)];
+    let mut seen_folders: HashSet<String> = HashSet::new();
+    let mut new_subfolders: Vec<(String, u32)> = Vec::new();
+    let mut changed_folders: Vec<data::IndexedFolderChange> = Vec::new();
+
+    while let Some((folder_path, depth)) = folders_to_check.pop() {
+        if cancel::is_cancelled() {
+            return Err(eyre!("Indexing cancelled for '{}'", base_folder));
+        }
+
+        if let Some(time_budget) = search_options.search_time_budget {
+            if start.elapsed().as_secs() >= time_budget as u64 {
+                debug!(
+                    "Search time budget of {}s used up indexing '{}'",
+                    time_budget, base_folder
+                );
+                break;
+            }
+        }
+
+        let Ok(metadata) = fs::metadata(&folder_path) else {
+            continue;
+        };
+        if !metadata.is_dir() {
+            continue;
+        }
+        let modified = get_modified(&metadata);
+        seen_folders.insert(folder_path.clone());
+
+        pb.set_message(format!(
+            "Indexing: '{}'",
+            paths::truncate_middle(&folder_path, 80)
+        ));
+        pb.inc(1);
+
+        // Sub folders past the maximum depth would only hold files too deep to match
+        let is_walking_down = match search_options.search_max_depth {
+            Some(max_depth) => depth < max_depth,
+            None => true,
+        };
+
+        match known_folders.get(&folder_path) {
+            Some(known_folder) if known_folder.modified == modified => {
+                // Unchanged, so it has the same sub folders as last time. They still need checking.
+                if let Some(subfolders) = known_subfolders.get(Path::new(&folder_path)) {
+                    for subfolder in subfolders {
+                        if is_walking_down && !is_folder_excluded(&subfolder.folder_path, &excludes)
+                        {
+                            folders_to_check.push((subfolder.folder_path.clone(), depth + 1));
+                        }
+                    }
+                }
+            }
+            _ => {
+                let (files, subfolders) = read_folder(&folder_path, depth);
+                for subfolder in subfolders {
+                    if is_walking_down && !is_folder_excluded(&subfolder, &excludes) {
+                        folders_to_check.push((subfolder.clone(), depth + 1));
+                    }
+                    if !known_folders.contains_key(&subfolder) {
+                        new_subfolders.push((subfolder, depth + 1));
+                    }
+                }
+                changed_folders.push(data::IndexedFolderChange {
+                    folder: data::IndexedFolder {
+                        folder_path,
+                        depth,
+                        modified,
+                    },
+                    files,
+                });
+            }
+        }
+    }
+
+    // New sub folders the walk skipped are added without a modified time, so the next walk that
+    // reaches them reads them
+    for (folder_path, depth) in new_subfolders {
+        if !seen_folders.contains(&folder_path) {
+            changed_folders.push(data::IndexedFolderChange {
+                folder: data::IndexedFolder {
+                    folder_path,
+                    depth,
+                    modified: 0,
+                },
+                files: Vec::new(),
+            });
+        }
+    }
+
+    // Folders the walk skipped are still in the index, so only those gone from disk are removed
+    let removed_folders: Vec<String> = known_folders
+        .keys()
+        .filter(|folder_path| {
+            !seen_folders.contains(*folder_path) && !paths::folder_exists(folder_path)
+        })
+        .cloned()
+        .collect();
+
+    debug!(
+        "Index for '{}' - {} folders checked, {} changed, {} removed",
+        base_folder,
+        seen_folders.len(),
+        changed_folders.len(),
+        removed_folders.len()
+    );
+    Ok((changed_folders, removed_folders))
+}
+
+fn is_folder_excluded(folder_path: &str, excludes: &[Pattern]) -> bool {
+    Path::new(folder_path)
+        .file_name()
+        .map(|folder_name| paths::is_excluded(&folder_name.to_string_lossy(), excludes))
+        .unwrap_or(false)
+}
+
+/// Reads the executables in the folder and lists its sub folders. Excluded files are still
+/// indexed, lookups leave them out.
+fn read_folder(folder_path: &str, depth: u32) -> (Vec<data::IndexedFile>, Vec<String>) {
+    let mut files = Vec::new();
+    let mut subfolders = Vec::new();
+
+    let entries = match fs::read_dir(folder_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Unable to read folder '{}': {}", folder_path, e);
+            return (files, subfolders);
+        }
+    };
+
+    for entry in entries.flatten() {
+        // Symbolic links are not followed, their file type is neither a folder or a file
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+
+        if file_type.is_dir() {
+            subfolders.push(path.display().to_string());
+        } else if file_type.is_file() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if is_executable(&path, &metadata) {
+                files.push(data::IndexedFile {
+                    folder_path: folder_path.to_string(),
+                    file_path: path.display().to_string(),
+                    file_name: entry.file_name().to_string_lossy().to_string(),
+                    depth: depth + 1,
+                });
+            }
+        }
+    }
+
+    (files, subfolders)
+}
+
+fn get_modified(metadata: &Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as i64)
+        .unwrap_or(0)
+}
+
+fn is_executable(path: &Path, metadata: &Metadata) -> bool {
+    if env::consts::OS == constants::OS_WINDOWS {
+        return path
+            .extension()
+            .map(|extension| {
+                WINDOWS_EXECUTABLE_EXTENSIONS
+                    .contains(&extension.to_string_lossy().to_lowercase().as_str())
+            })
+            .unwrap_or(false);
+    }
+
+    has_execute_permission(metadata)
+}
+
+#[cfg(unix)]
+fn has_execute_permission(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn has_execute_permission(_metadata: &Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use glob::Pattern;
+    use indicatif::ProgressBar;
+    use uuid::Uuid;
+
+    use crate::data::{IndexedFile, IndexedFolder, IndexedFolderChange, SearchOptions};
+    use crate::index::{filter_files, is_folder_excluded, is_indexed_path_excluded, walk};
+    use crate::paths::FileNameMatcher;
+
+    /// Creates the files, as executables, in a new temp folder.
+    fn create_test_folder(files: &[&str]) -> String {
+        let folder = env::temp_dir().join(format!("app-lemonator-index-{}", Uuid::new_v4()));
+        for file in files {
+            let file_path = folder.join(file);
+            fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            fs::write(&file_path, "").unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&file_path, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+        }
+        folder.display().to_string()
+    }
+
+    /// The folders relative to the base folder, sorted.
+    fn get_folder_names<'a>(
+        base_folder: &str,
+        folder_paths: impl Iterator<Item = &'a String>,
+    ) -> Vec<String> {
+        let mut folder_names: Vec<String> = folder_paths
+            .map(|folder_path| {
+                Path::new(folder_path)
+                    .strip_prefix(base_folder)
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        folder_names.sort();
+        folder_names
+    }
+
+    fn get_changed_folder_names(base_folder: &str, changes: &[IndexedFolderChange]) -> Vec<String> {
+        get_folder_names(
+            base_folder,
+            changes.iter().map(|change| &change.folder.folder_path),
+        )
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn walk_reads_only_changed_folders() {
+        // Arrange
+        let base_folder = create_test_folder(&["lemon", "lime/lemon", "lime/bin/lime"]);
+        let search_options = SearchOptions::default();
+        let pb = ProgressBar::hidden();
+        let (first_changes, _) = walk(&base_folder, Vec::new(), &search_options, &pb).unwrap();
+        let mut known_folders: Vec<IndexedFolder> = first_changes
+            .iter()
+            .map(|change| change.folder.clone())
+            .collect();
+        let lime_folder = format!("{}/lime", base_folder);
+        for folder in known_folders.iter_mut() {
+            if folder.folder_path == lime_folder {
+                folder.modified = 0;
+            }
+        }
+        fs::remove_dir_all(format!("{}/lime/bin", base_folder)).unwrap();
+
+        // Act
+        let (changed, removed) = walk(&base_folder, known_folders, &search_options, &pb).unwrap();
+
+        // Assert
+        assert_eq!(
+            get_changed_folder_names(&base_folder, &first_changes),
+            ["", "lime", "lime/bin"]
+        );
+        assert_eq!(
+            first_changes
+                .iter()
+                .map(|change| change.files.len())
+                .sum::<usize>(),
+            3
+        );
+        assert_eq!(get_changed_folder_names(&base_folder, &changed), ["lime"]);
+        assert_eq!(get_folder_names(&base_folder, removed.iter()), ["lime/bin"]);
+        fs::remove_dir_all(&base_folder).unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn walk_keeps_skipped_folders() {
+        // Arrange
+        let base_folder = create_test_folder(&["lemon", "lime/bin/lime", "node_modules/lemon"]);
+        let search_options = SearchOptions {
+            search_max_depth: Some(0),
+            search_excludes: Some("node_modules".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let (changed, removed) = walk(
+            &base_folder,
+            Vec::new(),
+            &search_options,
+            &ProgressBar::hidden(),
+        )
+        .unwrap();
+
+        // Assert
+        let placeholders = changed
+            .iter()
+            .filter(|change| change.folder.modified == 0)
+            .map(|change| &change.folder.folder_path);
+        assert_eq!(changed.len(), 3);
+        assert_eq!(
+            get_folder_names(&base_folder, placeholders),
+            ["lime", "node_modules"]
+        );
+        assert!(removed.is_empty());
+        fs::remove_dir_all(&base_folder).unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn filter_files_depth_and_excludes() {
+        // Arrange
+        let indexed_files: Vec<IndexedFile> = [
+            ("/opt/apps", "lemon", 1),
+            ("/opt/apps/lime", "lemon", 2),
+            ("/opt/apps/node_modules", "lemon", 2),
+            ("/opt/apps/lime", "lime", 2),
+        ]
+        .iter()
+        .map(|(folder_path, file_name, depth)| IndexedFile {
+            folder_path: folder_path.to_string(),
+            file_path: format!("{}/{}", folder_path, file_name),
+            file_name: file_name.to_string(),
+            depth: *depth,
+        })
+        .collect();
+        let matcher = FileNameMatcher::new("lemon").unwrap();
+        let base_folder_only = SearchOptions {
+            search_max_depth: Some(0),
+            ..Default::default()
+        };
+        let excluded = SearchOptions {
+            search_excludes: Some("node_modules".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let actual_base_folder_only = filter_files(
+            "/opt/apps",
+            indexed_files.clone(),
+            &matcher,
+            &base_folder_only,
+        );
+        let actual_excluded = filter_files("/opt/apps", indexed_files, &matcher, &excluded);
+
+        // Assert
+        assert_eq!(actual_base_folder_only, ["/opt/apps/lemon"]);
+        assert_eq!(actual_excluded, ["/opt/apps/lemon", "/opt/apps/lime/lemon"]);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn is_indexed_path_excluded_checks_each_folder() {
+        // Arrange
+        let excludes = vec![Pattern::new("node_modules").unwrap()];
+
+        // Act
+        let actual_excluded = is_indexed_path_excluded(
+            "/opt/apps",
+            "/opt/apps/tool/node_modules/.bin/tool",
+            &excludes,
+        );
+        let actual_included =
+            is_indexed_path_excluded("/opt/apps", "/opt/apps/tool/bin/tool", &excludes);
+
+        // Assert
+        assert!(actual_excluded);
+        assert!(!actual_included);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn is_indexed_path_excluded_ignores_base_folder() {
+        // Arrange
+        let excludes = vec![Pattern::new("apps").unwrap()];
+
+        // Act
+        let actual = is_indexed_path_excluded("/opt/apps", "/opt/apps/tool/bin/tool", &excludes);
+
+        // Assert
+        assert!(!actual);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn is_folder_excluded_checks_folder_name() {
+        // Arrange
+        let excludes = vec![Pattern::new("node_modules").unwrap()];
+
+        // Act
+        let actual_excluded = is_folder_excluded("/opt/apps/tool/node_modules", &excludes);
+        let actual_included = is_folder_excluded("/opt/apps/node_modules/tool", &excludes);
+
+        // Assert
+        assert!(actual_excluded);
+        assert!(!actual_included);
+    }
+}
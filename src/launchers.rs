@@ -0,0 +1,484 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Context, Report};
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use crate::{constants, data, discover, lnk, paths, streamdeck};
+
+/// An entry in the launcher's config, turned into an app or the reason it couldn't be.
+#[derive(Debug)]
+pub struct LauncherEntry {
+    /// Where the entry came from, like the file it is in and its title.
+    pub source: String,
+    pub app: Result<data::App, String>,
+}
+
+/// Reads the apps from another launcher's config. Folders are searched for the files the
+/// launcher keeps its apps in.
+pub fn read(
+    launcher: &data::Launcher,
+    path: &str,
+    operating_system: &data::OperatingSystem,
+) -> Result<Vec<LauncherEntry>, Report> {
+    let path = Path::new(path);
+    if !path.exists() {
+        return Err(eyre!("'{}' does not exist", path.display()));
+    }
+
+    let mut entries = Vec::new();
+    for file in get_files(path, launcher.get_extension()) {
+        match launcher {
+            data::Launcher::StreamDeck => {
+                let is_profile = file
+                    .extension()
+                    .map(|extension| extension.eq_ignore_ascii_case("streamDeckProfile"))
+                    .unwrap_or(false);
+                let manifest: Value = match is_profile {
+                    // Profiles exported from Stream Deck are a zip of the profile folder
+                    true => fs::read(&file)
+                        .wrap_err(format!("Unable to read '{}'", file.display()))
+                        .and_then(|profile| streamdeck::read_profile_manifest(&profile))?,
+                    false => serde_json::from_str(&read_file(&file)?).wrap_err(format!(
+                        "Stream Deck manifest '{}' is not JSON",
+                        file.display()
+                    ))?,
+                };
+                entries.extend(read_stream_deck_manifest(
+                    &file.display().to_string(),
+                    &manifest,
+                    operating_system,
+                ));
+            }
+            data::Launcher::LnkFolder => entries.push(read_lnk_file(&file, operating_system)),
+            data::Launcher::Desktop => {
+                let contents = read_file(&file)?;
+                entries.push(read_desktop_file(
+                    &file.display().to_string(),
+                    &contents,
+                    operating_system,
+                ));
+            }
+            data::Launcher::Json => {
+                let contents = read_file(&file)?;
+                let value: Value = serde_json::from_str(&contents)
+                    .wrap_err(format!("'{}' is not JSON", file.display()))?;
+                entries.extend(read_launcher_json(
+                    &file.display().to_string(),
+                    &value,
+                    operating_system,
+                )?);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+fn get_files(path: &Path, extension: &str) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+
+    WalkDir::new(path)
+        .sort_by_file_name()
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.into_path())
+        .filter(|file| {
+            file.is_file()
+                && file
+                    .file_name()
+                    .map(|file_name| {
+                        file_name
+                            .to_string_lossy()
+                            .to_lowercase()
+                            .ends_with(extension)
+                    })
+                    .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn read_file(file: &Path) -> Result<String, Report> {
+    fs::read_to_string(file).wrap_err(format!("Unable to read '{}'", file.display()))
+}
+
+/// Finds the Open actions in a Stream Deck profile manifest, including those in multi actions
+/// and folders.
+fn read_stream_deck_manifest(
+    file_name: &str,
+    manifest: &Value,
+    operating_system: &data::OperatingSystem,
+) -> Vec<LauncherEntry> {
+    let mut actions = Vec::new();
+    find_stream_deck_actions(manifest, &mut actions);
+
+    actions
+        .into_iter()
+        .map(|action| {
+            let title = action
+                .pointer("/States/0/Title")
+                .and_then(|title| title.as_str())
+                .map(|title| title.split_whitespace().collect::<Vec<&str>>().join(" "))
+                .filter(|title| !title.is_empty());
+            let source = format!(
+                "{} - {}",
+                file_name,
+                title.clone().unwrap_or("Untitled".to_string())
+            );
+
+            if action.get("UUID").and_then(|uuid| uuid.as_str())
+                != Some(streamdeck::OPEN_ACTION_UUID)
+            {
+                let action_name = action
+                    .get("Name")
+                    .and_then(|name| name.as_str())
+                    .unwrap_or_default();
+                return LauncherEntry {
+                    source,
+                    app: Err(format!("'{}' actions don't open an app", action_name)),
+                };
+            }
+
+            let command = action
+                .pointer("/Settings/path")
+                .and_then(|path| path.as_str())
+                .unwrap_or_default();
+            LauncherEntry {
+                source,
+                app: from_command(title, command, operating_system),
+            }
+        })
+        .collect()
+}
+
+/// Collects every object that looks like an action, one with a UUID and settings.
+fn find_stream_deck_actions<'a>(value: &'a Value, actions: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(object) => {
+            let is_action = object.contains_key("UUID") && object.contains_key("Settings");
+            let is_container = object
+                .get("Settings")
+                .and_then(|settings| settings.get("Routine").or(settings.get("Actions")))
+                .is_some();
+            if is_action && !is_container {
+                actions.push(value);
+                return;
+            }
+            for child in object.values() {
+                find_stream_deck_actions(child, actions);
+            }
+        }
+        Value::Array(array) => {
+            for child in array {
+                find_stream_deck_actions(child, actions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Turns a command line, the program and its arguments, into an app.
+fn from_command(
+    title: Option<String>,
+    command: &str,
+    operating_system: &data::OperatingSystem,
+) -> Result<data::App, String> {
+    let arguments = paths::try_parse_arguments(command.trim())
+        .ok_or(format!("Unable to read the command '{}'", command))?;
+    let Some((program, arguments)) = arguments.split_first() else {
+        return Err("No app to open".to_string());
+    };
+    if program.contains("://") {
+        return Err(format!("'{}' is a link, not an app", program));
+    }
+
+    let (_, exe_name) = split_program(program);
+    let program_name = Path::new(exe_name)
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if program_name.eq_ignore_ascii_case(constants::APP_NAME) {
+        return Err(format!(
+            "Already opens '{}' through {}",
+            arguments.join(" "),
+            constants::APP_NAME
+        ));
+    }
+
+    let params = match arguments.is_empty() {
+        true => None,
+        false => Some(
+            arguments
+                .iter()
+                .map(|argument| match argument.contains(' ') {
+                    true => format!("\"{}\"", argument),
+                    false => argument.clone(),
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+        ),
+    };
+    from_program(
+        title.unwrap_or(program_name),
+        program,
+        params,
+        operating_system,
+    )
+}
+
+/// Picks how to find the program. Shortcut files are read each time, programs in versioned
+/// folders are searched for and programs without a folder are looked up on the PATH.
+fn from_program(
+    app_name: String,
+    program: &str,
+    params: Option<String>,
+    operating_system: &data::OperatingSystem,
+) -> Result<data::App, String> {
+    let (folder, exe_name) = split_program(program);
+    if exe_name.is_empty() {
+        return Err(format!("'{}' is not a program", program));
+    }
+
+    let (search_method, search_term) = match folder {
+        None => (data::SearchMethod::PathLookup, exe_name.to_string()),
+        Some(folder) if exe_name.to_lowercase().ends_with(".lnk") => {
+            (data::SearchMethod::LnkFile, paths::to_path_variable(folder))
+        }
+        Some(folder) => discover::get_search_for_folder(Path::new(folder)),
+    };
+
+    Ok(data::App::new(
+        app_name,
+        exe_name.to_string(),
+        params,
+        search_term,
+        search_method,
+        operating_system.clone(),
+    ))
+}
+
+/// Splits the program into its folder and file name. Configs can come from either operating
+/// system, so both kinds of separator are split on.
+fn split_program(program: &str) -> (Option<&str>, &str) {
+    match program.trim_end_matches(['/', '\\']).rfind(['/', '\\']) {
+        // Keep the separator for programs in the root folder
+        Some(index) => (Some(&program[..index.max(1)]), &program[index + 1..]),
+        None => (None, program),
+    }
+}
+
+fn read_lnk_file(lnk_file: &Path, operating_system: &data::OperatingSystem) -> LauncherEntry {
+    let source = lnk_file.display().to_string();
+    let app_name = lnk_file
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if discover::is_ignored_exe(&app_name) {
+        return LauncherEntry {
+            source,
+            app: Err("Uninstallers and helpers are not imported".to_string()),
+        };
+    }
+
+    let app = lnk::read(&source)
+        .map_err(|error| format!("Unable to read shortcut - {}", error))
+        .and_then(|shell_link| {
+            let target_path = shell_link
+                .target_path
+                .ok_or("Shortcut has no target".to_string())?;
+            if target_path.contains("://") {
+                return Err(format!("'{}' is a link, not an app", target_path));
+            }
+            from_program(
+                app_name,
+                &lnk_file.display().to_string(),
+                shell_link
+                    .arguments
+                    .filter(|arguments| !arguments.is_empty()),
+                operating_system,
+            )
+        });
+    LauncherEntry { source, app }
+}
+
+fn read_desktop_file(
+    file_name: &str,
+    contents: &str,
+    operating_system: &data::OperatingSystem,
+) -> LauncherEntry {
+    let app = match discover::parse_desktop_entry(contents) {
+        Some((app_name, program)) => from_program(app_name, &program, None, operating_system),
+        None => Err("Not an application, or hidden".to_string()),
+    };
+    LauncherEntry {
+        source: file_name.to_string(),
+        app,
+    }
+}
+
+/// Reads Alfred style JSON, `{"items": [{"title", "arg"}]}`, or Raycast style JSON,
+/// `[{"name", "link"}]`. Items can also name a macOS bundle identifier.
+fn read_launcher_json(
+    file_name: &str,
+    value: &Value,
+    operating_system: &data::OperatingSystem,
+) -> Result<Vec<LauncherEntry>, Report> {
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(object) => object
+            .get("items")
+            .and_then(|items| items.as_array())
+            .ok_or_else(|| eyre!("'{}' has no items", file_name))?,
+        _ => return Err(eyre!("'{}' is not a list of items", file_name)),
+    };
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            let get_string = |keys: &[&str]| {
+                keys.iter()
+                    .find_map(|key| item.get(*key).and_then(|value| value.as_str()))
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+            let title = get_string(&["title", "name"]);
+            let source = format!(
+                "{} - {}",
+                file_name,
+                title.clone().unwrap_or("Untitled".to_string())
+            );
+
+            let app = match (
+                get_string(&["bundleId", "bundle_id"]),
+                get_string(&["arg", "link", "path"]),
+            ) {
+                (Some(bundle_id), target) => {
+                    let exe_name = target
+                        .as_deref()
+                        .and_then(|target| Path::new(target).file_name())
+                        .map(|file_name| file_name.to_string_lossy().to_string())
+                        .or(title.clone().map(|title| format!("{}.app", title)))
+                        .unwrap_or_default();
+                    Ok(data::App::new(
+                        title.clone().unwrap_or(bundle_id.clone()),
+                        exe_name,
+                        None,
+                        bundle_id,
+                        data::SearchMethod::BundleId,
+                        operating_system.clone(),
+                    ))
+                }
+                (None, Some(target)) => {
+                    let target = paths::resolve_path(&target);
+                    match target.contains("://") {
+                        true => Err(format!("'{}' is a link, not an app", target)),
+                        false => from_program(
+                            title.unwrap_or(
+                                Path::new(&target)
+                                    .file_stem()
+                                    .map(|file_stem| file_stem.to_string_lossy().to_string())
+                                    .unwrap_or_default(),
+                            ),
+                            &target,
+                            get_string(&["arguments", "args"]),
+                            operating_system,
+                        ),
+                    }
+                }
+                (None, None) => Err("No app to open".to_string()),
+            };
+            LauncherEntry { source, app }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        data::{OperatingSystem, SearchMethod},
+        launchers::{read_launcher_json, read_stream_deck_manifest},
+    };
+
+    #[test]
+    fn read_stream_deck_manifest_open_actions() {
+        // Arrange
+        let manifest = json!({
+            "Name": "Default Profile",
+            "Actions": {
+                "0,0": {
+                    "Name": "Open",
+                    "UUID": "com.elgato.streamdeck.system.open",
+                    "Settings": { "path": "\"C:\\Program Files\\JetBrains\\Rider 2024.1\\bin\\rider64.exe\" --wait" },
+                    "States": [{ "Title": "Rider" }]
+                },
+                "1,0": {
+                    "Name": "Multi Action",
+                    "UUID": "com.elgato.streamdeck.multiactions.routine",
+                    "Settings": { "Routine": [{
+                        "Name": "Open",
+                        "UUID": "com.elgato.streamdeck.system.open",
+                        "Settings": { "path": "C:\\Tools\\app-lemonator.exe open Lemon" },
+                        "States": [{ "Title": "" }]
+                    }] }
+                },
+                "2,0": {
+                    "Name": "Website",
+                    "UUID": "com.elgato.streamdeck.system.website",
+                    "Settings": { "path": "https://example.com" },
+                    "States": [{ "Title": "Example" }]
+                }
+            }
+        });
+
+        // Act
+        let actual =
+            read_stream_deck_manifest("manifest.json", &manifest, &OperatingSystem::Windows);
+
+        // Assert
+        assert_eq!(actual.len(), 3);
+        let rider = actual[0].app.as_ref().unwrap();
+        assert_eq!(rider.app_name, "Rider");
+        assert_eq!(rider.exe_name, "rider64.exe");
+        assert_eq!(rider.params, Some("--wait".to_string()));
+        assert!(actual[1].app.is_err());
+        assert!(actual[2].app.is_err());
+    }
+
+    #[test]
+    fn read_launcher_json_alfred_and_raycast() {
+        // Arrange
+        let alfred = json!({ "items": [
+            { "title": "Lime", "arg": "/Applications/Lime.app" },
+            { "title": "Docs", "arg": "https://example.com/docs" }
+        ] });
+        let raycast = json!([
+            { "name": "Alacritty", "link": "alacritty" },
+            { "name": "Chrome", "bundleId": "com.google.Chrome" }
+        ]);
+
+        // Act
+        let actual_alfred =
+            read_launcher_json("alfred.json", &alfred, &OperatingSystem::MacOS).unwrap();
+        let actual_raycast =
+            read_launcher_json("raycast.json", &raycast, &OperatingSystem::MacOS).unwrap();
+
+        // Assert
+        let lime = actual_alfred[0].app.as_ref().unwrap();
+        assert_eq!(lime.exe_name, "Lime.app");
+        assert_eq!(lime.search_method, SearchMethod::Shortcut);
+        assert_eq!(lime.search_term, "/Applications");
+        assert!(actual_alfred[1].app.is_err());
+        let alacritty = actual_raycast[0].app.as_ref().unwrap();
+        assert_eq!(alacritty.search_method, SearchMethod::PathLookup);
+        let chrome = actual_raycast[1].app.as_ref().unwrap();
+        assert_eq!(chrome.search_method, SearchMethod::BundleId);
+        assert_eq!(chrome.exe_name, "Chrome.app");
+    }
+}
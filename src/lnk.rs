@@ -0,0 +1,353 @@
+use std::{fs, path::Path};
+
+use eyre::{eyre, Context, Report, Result};
+
+// Shell Link (.lnk) file format, see [MS-SHLLINK]
+// https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink
+
+const HEADER_SIZE: u32 = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0000_0001;
+const HAS_LINK_INFO: u32 = 0x0000_0002;
+const HAS_NAME: u32 = 0x0000_0004;
+const HAS_RELATIVE_PATH: u32 = 0x0000_0008;
+const HAS_WORKING_DIR: u32 = 0x0000_0010;
+const HAS_ARGUMENTS: u32 = 0x0000_0020;
+const HAS_ICON_LOCATION: u32 = 0x0000_0040;
+const IS_UNICODE: u32 = 0x0000_0080;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x0000_0001;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 0x0000_0002;
+
+const ENVIRONMENT_VARIABLE_DATA_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_DATA_BLOCK: u32 = 0xA000_0007;
+const ENVIRONMENT_DATA_BLOCK_SIZE: usize = 0x314;
+
+/// The parts of a Windows shortcut needed to run the app it points to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShellLink {
+    /// Full path of the target, environment variables like %ProgramFiles% are left as is.
+    pub target_path: Option<String>,
+    pub arguments: Option<String>,
+    pub working_dir: Option<String>,
+    pub icon_location: Option<String>,
+    pub icon_index: i32,
+    pub name: Option<String>,
+    pub relative_path: Option<String>,
+}
+
+pub fn read(lnk_path: &str) -> Result<ShellLink, Report> {
+    let bytes = fs::read(lnk_path).wrap_err(format!("Unable to read shortcut '{}'", lnk_path))?;
+    let mut shell_link =
+        parse(&bytes).wrap_err(format!("Unable to parse shortcut '{}'", lnk_path))?;
+
+    // Last resort, the relative path is relative to the shortcut itself
+    if shell_link.target_path.is_none() {
+        if let (Some(relative_path), Some(lnk_folder)) =
+            (&shell_link.relative_path, Path::new(lnk_path).parent())
+        {
+            shell_link.target_path = Some(lnk_folder.join(relative_path).display().to_string());
+        }
+    }
+
+    Ok(shell_link)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<ShellLink, Report> {
+    let mut reader = ByteReader::new(bytes);
+
+    let header_size = reader.read_u32()?;
+    let clsid = reader.read_bytes(16)?;
+    if header_size != HEADER_SIZE || clsid != LINK_CLSID {
+        return Err(eyre!("Not a shortcut file, the header is invalid"));
+    }
+    let link_flags = reader.read_u32()?;
+    reader.skip(4 + 8 + 8 + 8 + 4)?; // File attributes, times and file size
+    let icon_index = reader.read_u32()? as i32;
+    reader.skip(4 + 2 + 2 + 4 + 4)?; // Show command, hot key and reserved
+
+    if link_flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        let id_list_size = reader.read_u16()? as usize;
+        reader.skip(id_list_size)?;
+    }
+
+    let mut target_path = None;
+    if link_flags & HAS_LINK_INFO != 0 {
+        let link_info_size = reader.peek_u32()? as usize;
+        target_path = parse_link_info(reader.read_bytes(link_info_size)?)?;
+    }
+
+    let is_unicode = link_flags & IS_UNICODE != 0;
+    let mut read_string_data = |flag: u32| -> Result<Option<String>, Report> {
+        match link_flags & flag != 0 {
+            true => Ok(Some(reader.read_string_data(is_unicode)?)),
+            false => Ok(None),
+        }
+    };
+    let name = read_string_data(HAS_NAME)?;
+    let relative_path = read_string_data(HAS_RELATIVE_PATH)?;
+    let working_dir = read_string_data(HAS_WORKING_DIR)?;
+    let arguments = read_string_data(HAS_ARGUMENTS)?;
+    let mut icon_location = read_string_data(HAS_ICON_LOCATION)?;
+
+    // Extra data blocks hold the unexpanded paths, like %ProgramFiles%\App\app.exe
+    while reader.remaining() >= 4 {
+        let block_size = reader.read_u32()? as usize;
+        if block_size < 8 {
+            break;
+        }
+        let block = reader.read_bytes(block_size - 4)?;
+        let signature = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        match signature {
+            ENVIRONMENT_VARIABLE_DATA_BLOCK if target_path.is_none() => {
+                target_path = parse_environment_data_block(block, block_size);
+            }
+            ICON_ENVIRONMENT_DATA_BLOCK if icon_location.is_none() => {
+                icon_location = parse_environment_data_block(block, block_size);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(ShellLink {
+        target_path,
+        arguments,
+        working_dir,
+        icon_location,
+        icon_index,
+        name,
+        relative_path,
+    })
+}
+
+fn parse_link_info(link_info: &[u8]) -> Result<Option<String>, Report> {
+    let mut reader = ByteReader::new(link_info);
+    reader.skip(4)?; // Link info size
+    let header_size = reader.read_u32()?;
+    let flags = reader.read_u32()?;
+    reader.skip(4)?; // Volume ID offset
+    let local_base_path_offset = reader.read_u32()? as usize;
+    let network_link_offset = reader.read_u32()? as usize;
+    let common_path_suffix_offset = reader.read_u32()? as usize;
+
+    // Newer shortcuts also have Unicode copies of the paths
+    let (local_base_path_unicode_offset, common_path_suffix_unicode_offset) = if header_size >= 0x24
+    {
+        (
+            Some(reader.read_u32()? as usize),
+            Some(reader.read_u32()? as usize),
+        )
+    } else {
+        (None, None)
+    };
+
+    let common_path_suffix = match common_path_suffix_unicode_offset {
+        Some(offset) => read_utf16_at(link_info, offset)?,
+        None => read_ansi_at(link_info, common_path_suffix_offset)?,
+    };
+
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        let local_base_path = match local_base_path_unicode_offset {
+            Some(offset) => read_utf16_at(link_info, offset)?,
+            None => read_ansi_at(link_info, local_base_path_offset)?,
+        };
+        return Ok(Some(format!("{}{}", local_base_path, common_path_suffix)));
+    }
+
+    if flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 {
+        let network_link = link_info
+            .get(network_link_offset..)
+            .ok_or(eyre!("Shortcut network link is out of range"))?;
+        let mut network_reader = ByteReader::new(network_link);
+        network_reader.skip(8)?; // Size and flags
+        let net_name_offset = network_reader.read_u32()? as usize;
+        let net_name = read_ansi_at(network_link, net_name_offset)?;
+        return Ok(Some(match common_path_suffix.is_empty() {
+            true => net_name,
+            false => format!("{}\\{}", net_name, common_path_suffix),
+        }));
+    }
+
+    Ok(None)
+}
+
+fn parse_environment_data_block(block: &[u8], block_size: usize) -> Option<String> {
+    if block_size != ENVIRONMENT_DATA_BLOCK_SIZE {
+        return None;
+    }
+    // Signature, then 260 bytes of ANSI and 520 bytes of Unicode
+    let unicode = read_utf16_at(block, 4 + 260).ok()?;
+    let target = match unicode.is_empty() {
+        true => read_ansi_at(block, 4).ok()?,
+        false => unicode,
+    };
+    (!target.is_empty()).then_some(target)
+}
+
+fn read_ansi_at(bytes: &[u8], offset: usize) -> Result<String, Report> {
+    let text = bytes
+        .get(offset..)
+        .ok_or(eyre!("Shortcut string offset {} is out of range", offset))?;
+    let end = text.iter().position(|b| *b == 0).unwrap_or(text.len());
+    // Code pages aren't supported, so non ASCII characters are read as Latin-1
+    Ok(text[..end].iter().map(|b| *b as char).collect())
+}
+
+fn read_utf16_at(bytes: &[u8], offset: usize) -> Result<String, Report> {
+    let text = bytes
+        .get(offset..)
+        .ok_or(eyre!("Shortcut string offset {} is out of range", offset))?;
+    let units: Vec<u16> = text
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.position)
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], Report> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or(eyre!(
+                "Shortcut is truncated, expected {} bytes at offset {}",
+                count,
+                self.position
+            ))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), Report> {
+        self.read_bytes(count).map(|_| ())
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Report> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Report> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn peek_u32(&mut self) -> Result<u32, Report> {
+        let value = self.read_u32()?;
+        self.position -= 4;
+        Ok(value)
+    }
+
+    fn read_string_data(&mut self, is_unicode: bool) -> Result<String, Report> {
+        let count = self.read_u16()? as usize;
+        match is_unicode {
+            true => {
+                let bytes = self.read_bytes(count * 2)?;
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                Ok(String::from_utf16_lossy(&units))
+            }
+            false => Ok(self.read_bytes(count)?.iter().map(|b| *b as char).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lnk::{parse, read, ShellLink};
+
+    const LEMON_LNK: &[u8] = include_bytes!("../tests/fixtures/lnk/lemon.lnk");
+    const LIME_LNK: &[u8] = include_bytes!("../tests/fixtures/lnk/lime.lnk");
+
+    #[test]
+    fn parse_unicode_shortcut_with_link_info() {
+        // Arrange
+        let expected = ShellLink {
+            target_path: Some(r"C:\Program Files\Lemon\lemon.exe".to_string()),
+            arguments: Some(r#"--profile "Work Profile""#.to_string()),
+            working_dir: Some(r"C:\Program Files\Lemon".to_string()),
+            icon_location: Some(r"C:\Program Files\Lemon\lemon.ico".to_string()),
+            icon_index: 2,
+            name: Some("Lemon Editor".to_string()),
+            relative_path: Some(r"..\..\..\..\Program Files\Lemon\lemon.exe".to_string()),
+        };
+
+        // Act
+        let actual = parse(LEMON_LNK).unwrap();
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parse_ansi_shortcut_with_environment_target() {
+        // Arrange
+        let expected_target = r"%LOCALAPPDATA%\Programs\Lime\lime.exe";
+
+        // Act
+        let actual = parse(LIME_LNK).unwrap();
+
+        // Assert
+        assert_eq!(actual.target_path.as_deref(), Some(expected_target));
+        assert_eq!(
+            actual.working_dir.as_deref(),
+            Some(r"%LOCALAPPDATA%\Programs\Lime")
+        );
+        assert_eq!(actual.arguments, None);
+    }
+
+    #[test]
+    fn parse_truncated_shortcut_fails() {
+        // Arrange
+        let truncated = &LEMON_LNK[..100];
+
+        // Act
+        let actual = parse(truncated);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn parse_not_a_shortcut_fails() {
+        // Arrange
+        let bytes = b"[InternetShortcut]\r\nURL=https://example.com\r\n";
+
+        // Act
+        let actual = parse(bytes);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn read_fixture_file() {
+        // Arrange
+        let lnk_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/lnk/lemon.lnk");
+
+        // Act
+        let actual = read(lnk_path).unwrap();
+
+        // Assert
+        assert_eq!(actual.name.as_deref(), Some("Lemon Editor"));
+    }
+}
@@ -3,14 +3,30 @@ use log::debug;
 use std::{env, process};
 
 mod actions;
+mod arch;
+mod backup;
+mod bundle;
+mod cancel;
 mod cli;
 mod constants;
 mod data;
 mod db;
+mod discover;
+mod export;
 mod finder;
+mod icon;
+mod import;
+mod index;
+mod launchers;
+mod lnk;
 mod log_config;
+mod manifest;
 mod paths;
 mod runner;
+mod schedule;
+mod spreadsheet;
+mod streamdeck;
+mod verify;
 
 fn run() -> eyre::Result<String> {
     color_eyre::install()?;
@@ -24,10 +40,17 @@ fn run() -> eyre::Result<String> {
         constants::CRATE_VERSION,
     );
 
-    // For now, only works on Windows. Needs more work on macOS. Linux is a probably not.
-    if env::consts::OS != constants::OS_WINDOWS {
+    // Windows is the most complete. macOS and Linux have their own search methods, schedules and
+    // icons, but Linux apps have no file version details.
+    if ![
+        constants::OS_WINDOWS,
+        constants::OS_MACOS,
+        constants::OS_LINUX,
+    ]
+    .contains(&env::consts::OS)
+    {
         return Err(eyre::eyre!(
-            "This app only works on Windows. You are running on '{}'",
+            "This app only works on Windows, macOS and Linux. You are running on '{}'",
             env::consts::OS
         ));
     }
@@ -0,0 +1,436 @@
+use std::{collections::HashSet, path::Path};
+
+use clap::ValueEnum;
+use eyre::{eyre, Context, Report};
+use serde::{Deserialize, Deserializer};
+use tabled::{builder::Builder, settings::Style};
+
+use crate::{data, import};
+
+/// Apps described in a TOML or YAML file, kept in version control and applied with `apply`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub apps: Vec<ManifestApp>,
+}
+
+/// An app in a manifest. Enum values can be written like the command line, such as
+/// folder-search, or like the export, such as FolderSearch.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestApp {
+    pub app_name: String,
+    pub exe_name: String,
+    pub search_term: String,
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    pub search_method: data::SearchMethod,
+    #[serde(default)]
+    pub params: Option<String>,
+    /// Only apply the app on this operating system, or the ones it has variants for. Apps without
+    /// one apply everywhere.
+    #[serde(default, deserialize_with = "deserialize_optional_value_enum")]
+    pub operating_system: Option<data::OperatingSystem>,
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub max_matches: Option<u32>,
+    #[serde(default)]
+    pub time_budget: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    pub arch: data::ArchPreference,
+    /// Searches to try in order, as method:term.
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    pub verify: data::VerifyPolicy,
+    #[serde(default)]
+    pub signer: Option<String>,
+    /// How to find the app on each operating system, replacing the search above on that one.
+    #[serde(default)]
+    pub variants: Vec<ManifestVariant>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestVariant {
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    pub operating_system: data::OperatingSystem,
+    #[serde(default)]
+    pub exe_name: Option<String>,
+    pub search_term: String,
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    pub search_method: data::SearchMethod,
+    #[serde(default)]
+    pub params: Option<String>,
+}
+
+fn deserialize_value_enum<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    let value = String::deserialize(deserializer)?;
+    T::from_str(&value, true).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_optional_value_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => T::from_str(&value, true)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+impl ManifestApp {
+    /// The app for the operating system. Without an operating system of its own, the app's search
+    /// is used wherever there isn't a variant.
+    pub fn to_app(&self, operating_system: &data::OperatingSystem) -> Result<data::App, Report> {
+        let mut app = data::App::new(
+            self.app_name.clone(),
+            self.exe_name.clone(),
+            self.params.clone(),
+            self.search_term.clone(),
+            self.search_method.clone(),
+            self.operating_system
+                .clone()
+                .unwrap_or(operating_system.clone()),
+        );
+        app.search_options = data::SearchOptions {
+            search_max_depth: self.max_depth,
+            search_excludes: match self.excludes.is_empty() {
+                true => None,
+                false => Some(self.excludes.join(";")),
+            },
+            search_follow_symlinks: self.follow_symlinks,
+            search_max_matches: self.max_matches,
+            search_time_budget: self.time_budget,
+            search_arch: self.arch.clone(),
+        };
+        app.fallback_strategies = self
+            .fallbacks
+            .iter()
+            .map(|fallback| data::parse_search_strategy(fallback))
+            .collect::<Result<Vec<data::SearchStrategy>, String>>()
+            .map_err(|error| eyre!("App '{}' - {}", self.app_name, error))?;
+        app.verify_options = data::VerifyOptions {
+            verify_policy: self.verify.clone(),
+            verify_signer: self.signer.clone(),
+        };
+        app.variants = self
+            .variants
+            .iter()
+            .map(|variant| data::AppVariant {
+                operating_system: variant.operating_system.clone(),
+                exe_name: variant.exe_name.clone(),
+                search_method: variant.search_method.clone(),
+                search_term: variant.search_term.clone(),
+                params: variant.params.clone(),
+            })
+            .collect();
+
+        app.validate()
+            .map_err(|error| eyre!("App '{}' - {}", self.app_name, error))?;
+        Ok(app)
+    }
+}
+
+/// What applying the manifest will do to an app in the database.
+#[derive(Debug)]
+pub enum ManifestChange {
+    Add(data::App),
+    Update {
+        current: Box<data::App>,
+        desired: Box<data::App>,
+    },
+    Remove(data::App),
+}
+
+/// Reads the manifest, picking TOML or YAML from the file extension.
+pub fn parse(file_name: &str, contents: &str) -> Result<Manifest, Report> {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "toml" => toml::from_str(contents).wrap_err("Manifest is not valid TOML"),
+        "yaml" | "yml" => serde_yaml::from_str(contents).wrap_err("Manifest is not valid YAML"),
+        _ => Err(eyre!(
+            "Manifest '{}' must be a .toml, .yaml or .yml file",
+            file_name
+        )),
+    }
+}
+
+/// The apps in the manifest for this operating system, checked before anything is changed.
+pub fn get_desired_apps(
+    manifest: &Manifest,
+    operating_system: &data::OperatingSystem,
+) -> Result<Vec<data::App>, Report> {
+    let mut app_names = HashSet::new();
+    let mut apps = Vec::new();
+
+    for manifest_app in &manifest.apps {
+        if let Some(app_os) = &manifest_app.operating_system {
+            if app_os != operating_system
+                && !manifest_app
+                    .variants
+                    .iter()
+                    .any(|variant| variant.operating_system == *operating_system)
+            {
+                continue;
+            }
+        }
+        if !app_names.insert(manifest_app.app_name.to_lowercase()) {
+            return Err(eyre!(
+                "App '{}' is in the manifest more than once",
+                manifest_app.app_name
+            ));
+        }
+        apps.push(manifest_app.to_app(operating_system)?);
+    }
+
+    Ok(apps)
+}
+
+/// Works out the changes that make the database match the desired apps. Apps only in the
+/// database are removed when pruning, and otherwise left alone.
+pub fn plan(
+    current_apps: &[data::App],
+    desired_apps: Vec<data::App>,
+    prune: bool,
+) -> Vec<ManifestChange> {
+    let mut changes = Vec::new();
+
+    for desired_app in &desired_apps {
+        let current = current_apps
+            .iter()
+            .find(|app| app.app_name.eq_ignore_ascii_case(&desired_app.app_name));
+        match current {
+            None => changes.push(ManifestChange::Add(desired_app.clone())),
+            Some(current) => {
+                let desired = with_settings_from(current, desired_app);
+                if desired != *current {
+                    changes.push(ManifestChange::Update {
+                        current: Box::new(current.clone()),
+                        desired: Box::new(desired),
+                    });
+                }
+            }
+        }
+    }
+
+    if prune {
+        for current in current_apps {
+            if !desired_apps
+                .iter()
+                .any(|app| app.app_name.eq_ignore_ascii_case(&current.app_name))
+            {
+                changes.push(ManifestChange::Remove(current.clone()));
+            }
+        }
+    }
+
+    changes
+}
+
+/// The current app with the settings a manifest controls replaced, keeping what has been found
+/// for it, like the app path.
+fn with_settings_from(current: &data::App, desired: &data::App) -> data::App {
+    let mut app = current.clone();
+    app.app_name = desired.app_name.clone();
+    app.exe_name = desired.exe_name.clone();
+    app.params = desired.params.clone();
+    app.search_term = desired.search_term.clone();
+    app.search_method = desired.search_method.clone();
+    app.search_options = desired.search_options.clone();
+    app.fallback_strategies = desired.fallback_strategies.clone();
+    app.verify_options = desired.verify_options.clone();
+    app.variants = desired.variants.clone();
+    app
+}
+
+pub fn display_plan(changes: &[ManifestChange]) -> String {
+    let mut builder = Builder::default();
+    builder.push_record(["App Name", "Change", "Field", "Current", "Manifest"]);
+
+    for change in changes {
+        let (app_name, change_name, rows) = match change {
+            ManifestChange::Add(app) => (&app.app_name, "Add", import::diff_fields(None, app)),
+            ManifestChange::Update { current, desired } => (
+                &current.app_name,
+                "Update",
+                import::diff_fields(Some(current), desired),
+            ),
+            ManifestChange::Remove(app) => (&app.app_name, "Remove", vec![]),
+        };
+
+        match rows.is_empty() {
+            true => builder.push_record([app_name.clone(), change_name.to_string()]),
+            false => {
+                for [field, current_value, new_value] in rows {
+                    builder.push_record([
+                        app_name.clone(),
+                        change_name.to_string(),
+                        field,
+                        current_value,
+                        new_value,
+                    ]);
+                }
+            }
+        }
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern());
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{ArchPreference, OperatingSystem, SearchMethod},
+        manifest::{get_desired_apps, parse, plan, ManifestChange},
+    };
+
+    const TOML_MANIFEST: &str = r#"
+[[apps]]
+app_name = "Rider"
+exe_name = "rider64.exe"
+search_term = "%localappdata%\\Programs"
+search_method = "folder-search"
+max_depth = 4
+excludes = ["Temp", "*.old"]
+arch = "x64"
+fallbacks = ["shortcut:C:\\Rider"]
+
+[[apps]]
+app_name = "TextEdit"
+exe_name = "TextEdit.app"
+search_term = "com.apple.TextEdit"
+search_method = "BundleId"
+operating_system = "MacOS"
+"#;
+
+    const YAML_MANIFEST: &str = r#"
+apps:
+  - app_name: Rider
+    exe_name: rider64.exe
+    search_term: '%localappdata%\Programs'
+    search_method: folder-search
+    max_depth: 4
+    excludes: [Temp, "*.old"]
+    arch: x64
+    fallbacks: ['shortcut:C:\Rider']
+"#;
+
+    #[test]
+    fn parse_toml_and_yaml_match() {
+        // Arrange
+        let os = OperatingSystem::Windows;
+
+        // Act
+        let toml_apps = get_desired_apps(&parse("apps.toml", TOML_MANIFEST).unwrap(), &os).unwrap();
+        let yaml_apps = get_desired_apps(&parse("apps.yml", YAML_MANIFEST).unwrap(), &os).unwrap();
+
+        // Assert
+        assert_eq!(toml_apps.len(), 1);
+        assert_eq!(toml_apps, yaml_apps);
+        assert_eq!(toml_apps[0].search_method, SearchMethod::FolderSearch);
+        assert_eq!(toml_apps[0].search_options.search_arch, ArchPreference::X64);
+        assert_eq!(
+            toml_apps[0].search_options.search_excludes,
+            Some("Temp;*.old".to_string())
+        );
+        assert_eq!(toml_apps[0].fallback_strategies.len(), 1);
+    }
+
+    #[test]
+    fn get_desired_apps_keeps_variants_for_operating_system() {
+        // Arrange
+        let contents = r#"
+apps:
+  - app_name: Terminal
+    exe_name: wt.exe
+    search_term: Microsoft.WindowsTerminal
+    search_method: ps-get-app
+    operating_system: windows
+    fallbacks:
+      - folder-search:C:\Tools
+    variants:
+      - operating_system: macos
+        exe_name: iTerm.app
+        search_term: ~/Applications
+        search_method: shortcut
+"#;
+        let manifest = parse("apps.yaml", contents).unwrap();
+
+        // Act
+        let windows = get_desired_apps(&manifest, &OperatingSystem::Windows).unwrap();
+        let macos = get_desired_apps(&manifest, &OperatingSystem::MacOS).unwrap();
+        let linux = get_desired_apps(&manifest, &OperatingSystem::Linux).unwrap();
+
+        // Assert
+        assert_eq!(windows[0].search_method, SearchMethod::PSGetApp);
+        assert_eq!(macos[0].search_method, SearchMethod::PSGetApp);
+        assert_eq!(macos[0].fallback_strategies.len(), 1);
+        let macos_search = macos[0].for_operating_system(&OperatingSystem::MacOS);
+        assert_eq!(macos_search.search_method, SearchMethod::Shortcut);
+        assert_eq!(macos_search.exe_name, "iTerm.app");
+        assert!(linux.is_empty());
+    }
+
+    #[test]
+    fn parse_unknown_field_fails() {
+        // Arrange
+        let contents = "[[apps]]\napp_name = \"Rider\"\nexe = \"rider64.exe\"\n";
+
+        // Act
+        let actual = parse("apps.toml", contents);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn plan_adds_updates_and_prunes() {
+        // Arrange
+        let os = OperatingSystem::Windows;
+        let desired = get_desired_apps(&parse("apps.toml", TOML_MANIFEST).unwrap(), &os).unwrap();
+        let mut current_rider = desired[0].clone();
+        current_rider.app_path = Some("C:\\Rider\\rider64.exe".to_string());
+        current_rider.search_options.search_max_depth = Some(2);
+        let mut current_lemon = desired[0].clone();
+        current_lemon.app_name = "Lemon".to_string();
+
+        // Act
+        let without_prune = plan(
+            &[current_rider.clone(), current_lemon.clone()],
+            desired.clone(),
+            false,
+        );
+        let with_prune = plan(&[current_rider, current_lemon], desired, true);
+
+        // Assert
+        assert_eq!(without_prune.len(), 1);
+        match &without_prune[0] {
+            ManifestChange::Update { desired, .. } => {
+                assert_eq!(desired.search_options.search_max_depth, Some(4));
+                assert_eq!(desired.app_path, Some("C:\\Rider\\rider64.exe".to_string()));
+            }
+            change => panic!("Expected an update, got {:?}", change),
+        }
+        assert_eq!(with_prune.len(), 2);
+        assert!(matches!(&with_prune[1], ManifestChange::Remove(app) if app.app_name == "Lemon"));
+    }
+}
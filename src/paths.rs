@@ -2,20 +2,21 @@ use std::{
     env,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Instant,
 };
 
 use chrono::Local;
+use glob::{MatchOptions, Pattern};
 use indicatif::{ProgressBar, ProgressStyle};
-use log::{debug, error};
+use log::{debug, error, warn};
 use owo_colors::OwoColorize;
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::from_str;
 use strum_macros::{Display, EnumString};
 use uuid::Uuid;
-use walkdir::WalkDir;
 
-use crate::constants;
+use crate::{cancel, constants, data};
 
 #[derive(Display, EnumString)]
 pub enum BaseFolderType {
@@ -39,6 +40,75 @@ pub enum BaseFolderType {
     Temp,
 }
 
+const REGEX_PREFIX: &str = "re:";
+/// Syntax that only means something in a regex, like the quantifier in "python3.[0-9]+". A glob
+/// would take it literally, and no exe name has it.
+const REGEX_SYNTAX: [&str; 7] = ["]+", "]{", ")+", ")?", ".+", "\\d", "\\."];
+const SEARCH_MESSAGE_INTERVAL: usize = 64;
+
+/// How an exe name is matched during a folder search. Exe names starting with "re:" are a regex,
+/// as are names with regex only syntax, which must match the whole file name. Names containing
+/// any of "*?[" are a glob pattern, anything else is an exact match.
+#[derive(Debug)]
+pub enum FileNameMatcher {
+    Exact(String),
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+impl FileNameMatcher {
+    pub fn new(exe_name: &str) -> Result<Self, String> {
+        if let Some(regex) = exe_name.strip_prefix(REGEX_PREFIX) {
+            return RegexBuilder::new(regex)
+                .case_insensitive(true)
+                .build()
+                .map(FileNameMatcher::Regex)
+                .map_err(|e| format!("Exe Name regex '{}' is not valid - {}", regex, e));
+        }
+
+        if is_implied_regex(exe_name) {
+            return RegexBuilder::new(&format!("^(?:{})$", exe_name))
+                .case_insensitive(true)
+                .build()
+                .map(FileNameMatcher::Regex)
+                .map_err(|e| format!("Exe Name regex '{}' is not valid - {}", exe_name, e));
+        }
+
+        if exe_name.contains(['*', '?', '[']) {
+            return Pattern::new(exe_name)
+                .map(FileNameMatcher::Glob)
+                .map_err(|e| format!("Exe Name pattern '{}' is not valid - {}", exe_name, e));
+        }
+
+        Ok(FileNameMatcher::Exact(exe_name.to_lowercase()))
+    }
+
+    pub fn is_pattern(&self) -> bool {
+        !matches!(self, FileNameMatcher::Exact(_))
+    }
+
+    pub fn matches(&self, file_name: &str) -> bool {
+        match self {
+            FileNameMatcher::Exact(exe_name) => file_name.to_lowercase() == *exe_name,
+            FileNameMatcher::Glob(pattern) => pattern.matches_with(
+                file_name,
+                MatchOptions {
+                    case_sensitive: false,
+                    ..Default::default()
+                },
+            ),
+            FileNameMatcher::Regex(regex) => regex.is_match(file_name),
+        }
+    }
+}
+
+/// Whether the exe name is matched as a regex without starting with "re:", because it has regex
+/// only syntax. Worth a warning, as it may have been meant literally.
+pub fn is_implied_regex(exe_name: &str) -> bool {
+    !exe_name.starts_with(REGEX_PREFIX)
+        && REGEX_SYNTAX.iter().any(|syntax| exe_name.contains(syntax))
+}
+
 pub fn get_current_exe() -> String {
     let exe_result = env::current_exe();
     match exe_result {
@@ -52,38 +122,111 @@ pub fn get_current_exe() -> String {
     String::new()
 }
 
-pub fn find_file_in_folders(root_folder: &str, find_file: &str, results: &mut Vec<String>) {
-    debug!("find_file_in_folders: '{}'", root_folder);
-
-    // Create a new progress bar
+pub fn new_search_progress_bar() -> ProgressBar {
     let pb = ProgressBar::new_spinner();
     pb.set_style(
-        ProgressStyle::with_template("{spinner:.blue} [{elapsed_precise}] {msg}").unwrap(),
+        ProgressStyle::with_template("{spinner:.blue} [{elapsed_precise}] {prefix}{msg}").unwrap(),
     );
+    pb
+}
 
+pub fn find_file_in_folders(
+    root_folder: &str,
+    find_file: &FileNameMatcher,
+    search_options: &data::SearchOptions,
+    results: &mut Vec<String>,
+    pb: &ProgressBar,
+) {
+    debug!(
+        "find_file_in_folders: '{}', options {:?}",
+        root_folder, search_options
+    );
+
+    let excludes = get_exclude_patterns(search_options);
+    let start = Instant::now();
     let mut found_count = 0;
+    let mut error_count = 0;
+    let mut first_error: Option<String> = None;
+
+    // Folders are read in parallel, sorting keeps the order entries come back in the same each run.
+    // Each walk gets its own thread pool, as walks on a busy shared pool give up without results
+    // when apps are updated in parallel.
+    let mut walker = jwalk::WalkDir::new(root_folder)
+        .parallelism(jwalk::Parallelism::RayonNewPool(0))
+        .follow_links(search_options.search_follow_symlinks)
+        .skip_hidden(false)
+        .sort(true)
+        .process_read_dir(move |_, _, _, children| {
+            children.retain(|child| match child {
+                Ok(entry) => !is_excluded(&entry.file_name().to_string_lossy(), &excludes),
+                Err(_) => true,
+            });
+        });
+    // A max depth of 0 searches the files in the base folder, to jwalk those are depth 1
+    if let Some(max_depth) = search_options.search_max_depth {
+        walker = walker.max_depth(max_depth as usize + 1);
+    }
+
+    for (entry_count, entry) in walker.into_iter().enumerate() {
+        // Stop walking if Ctrl-C was pressed, keeping the matches found so far
+        if cancel::is_cancelled() {
+            debug!("Search cancelled in folder '{}'", root_folder);
+            break;
+        }
 
-    for entry in WalkDir::new(root_folder) {
+        if let Some(time_budget) = search_options.search_time_budget {
+            if start.elapsed().as_secs() >= time_budget as u64 {
+                debug!(
+                    "Search time budget of {}s used up in folder '{}'",
+                    time_budget, root_folder
+                );
+                break;
+            }
+        }
+
+        if let Err(error) = &entry {
+            debug!("Error walking folder '{}': {}", root_folder, error);
+            error_count += 1;
+            first_error.get_or_insert(error.to_string());
+        }
         if let Ok(entry) = entry {
             // Check if the file name matches
-            if entry.file_name().to_string_lossy().to_lowercase() == find_file.to_lowercase()
+            if find_file.matches(&entry.file_name().to_string_lossy())
                 && entry.file_type().is_file()
             {
                 found_count += 1;
                 results.push(entry.path().display().to_string());
             }
 
-            // Set the message to the currently-searched directory
-            pb.set_message(format!(
-                "({}) Searching: '{}'",
-                get_matches_count(found_count),
-                truncate_middle(&entry.path().display().to_string(), 80)
-            ));
+            // Set the message to the currently-searched directory. Not every entry, as the
+            // walk is quicker than the message formatting.
+            if entry_count % SEARCH_MESSAGE_INTERVAL == 0 {
+                pb.set_message(format!(
+                    "({}) Searching: '{}'",
+                    get_matches_count(found_count),
+                    truncate_middle(&entry.path().display().to_string(), 80)
+                ));
+            }
         }
 
         pb.inc(1); // Increase the spinner's step
+
+        if let Some(max_matches) = search_options.search_max_matches {
+            if found_count >= max_matches as i32 {
+                debug!("Search stopped after {} matches", found_count);
+                break;
+            }
+        }
     }
 
+    if let Some(first_error) = first_error {
+        warn!(
+            "Unable to read {} entries in folder '{}', first error: {}",
+            error_count, root_folder, first_error
+        );
+    }
+
+    results.sort();
     debug!("Match files found - {:?}", results);
 
     /*
@@ -103,6 +246,30 @@ pub fn find_file_in_folders(root_folder: &str, find_file: &str, results: &mut Ve
     */
 }
 
+pub fn get_exclude_patterns(search_options: &data::SearchOptions) -> Vec<Pattern> {
+    search_options
+        .get_excludes()
+        .iter()
+        .filter_map(|exclude| match Pattern::new(exclude) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                error!("Invalid search exclude '{}': {}", exclude, e);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn is_excluded(file_name: &str, excludes: &[Pattern]) -> bool {
+    let match_options = MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+    excludes
+        .iter()
+        .any(|pattern| pattern.matches_with(file_name, match_options))
+}
+
 fn get_matches_count(found_count: i32) -> String {
     if found_count == 0 {
         return "0 matches".to_string();
@@ -111,7 +278,7 @@ fn get_matches_count(found_count: i32) -> String {
     result.green().to_string()
 }
 
-fn truncate_middle(input: &str, size_limit: usize) -> String {
+pub fn truncate_middle(input: &str, size_limit: usize) -> String {
     // Yes this method is horrible. It is coping with folder paths that may
     // contain unicode characters and if truncated incorrectly will cause a
     // "assertion failed: self.is_char_boundary(n)" error.
@@ -148,15 +315,54 @@ pub fn folder_exists(folder_path: &str) -> bool {
     path.is_dir()
 }
 
+/// Compares paths the way the file system does, Windows paths ignore case.
+pub fn is_same_path(path: &str, other_path: &str) -> bool {
+    match env::consts::OS == constants::OS_WINDOWS {
+        true => path.eq_ignore_ascii_case(other_path),
+        false => path == other_path,
+    }
+}
+
 pub fn file_exists(file_path: &str) -> bool {
     let path = Path::new(file_path);
     path.is_file()
 }
 
+/// Finds a program the way a shell would, checking each folder on the PATH. On Windows, the
+/// extensions in PATHEXT are tried when the program has none.
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let program_path = Path::new(program);
+    if program_path.is_absolute() {
+        return program_path.is_file().then(|| program_path.to_path_buf());
+    }
+
+    let mut file_names = vec![program.to_string()];
+    if env::consts::OS == constants::OS_WINDOWS && program_path.extension().is_none() {
+        let path_ext = env::var("PATHEXT").unwrap_or(".COM;.EXE;.BAT;.CMD".to_string());
+        file_names.extend(
+            path_ext
+                .split(';')
+                .filter(|extension| !extension.is_empty())
+                .map(|extension| format!("{}{}", program, extension.to_lowercase())),
+        );
+    }
+
+    env::var_os("PATH").and_then(|path| {
+        env::split_paths(&path)
+            .flat_map(|folder| {
+                file_names
+                    .iter()
+                    .map(|file_name| folder.join(file_name))
+                    .collect::<Vec<PathBuf>>()
+            })
+            .find(|candidate| candidate.is_file())
+    })
+}
+
 pub fn check_app_exists(app_path: &str) -> bool {
     #[cfg(target_os = "macos")]
     return folder_exists(app_path);
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "macos"))]
     return file_exists(app_path);
 }
 
@@ -167,14 +373,14 @@ pub fn get_full_path(base_path: &str, file_name: &str) -> String {
     file_path.display().to_string()
 }
 
-pub fn get_unique_export_file_name() -> String {
+pub fn get_unique_export_file_name(extension: &str) -> String {
     let today = Local::now();
     format!(
         "{}-{}-{}{}",
         constants::APP_NAME,
         today.format("%Y-%m-%d"),
         Uuid::new_v4(),
-        ".json"
+        extension
     )
 }
 
@@ -209,12 +415,20 @@ pub fn get_temp_dir() -> String {
     temp_dir.display().to_string()
 }
 
+/// Where App Lemonator keeps files it can recreate, like extracted icons. Falls back to the temp
+/// folder when there is no local data folder.
+pub fn get_data_folder() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(constants::APP_NAME)
+}
+
 fn get_environment_folder(base_folder_type: BaseFolderType) -> String {
     if let Ok(appdata) = env::var(base_folder_type.to_string()) {
         let appdata_path = std::path::Path::new(&appdata);
         debug!(
             "Environment '{}' returns folder: '{}'",
-            base_folder_type.to_string(),
+            base_folder_type,
             appdata_path.display()
         );
         return appdata_path.display().to_string();
@@ -334,6 +548,21 @@ fn get_dropbox_folder_from_json(
     String::new()
 }
 
+fn get_path_variable_folder(base_folder_type: BaseFolderType) -> String {
+    match base_folder_type {
+        BaseFolderType::LocalAppData => get_local_app_data_folder(),
+        BaseFolderType::RoamingAppData => get_roaming_app_data_folder(),
+        BaseFolderType::PersonalDropbox | BaseFolderType::BusinessDropbox => {
+            get_dropbox_folder(base_folder_type)
+        }
+        BaseFolderType::ProgramFiles
+        | BaseFolderType::ProgramFilesX86
+        | BaseFolderType::WinDir
+        | BaseFolderType::HomePath
+        | BaseFolderType::Temp => get_environment_folder(base_folder_type),
+    }
+}
+
 pub fn get_base_folder(source_folder: &str) -> String {
     debug!("Source base folder: '{}'", source_folder);
     let mut output = source_folder.to_string();
@@ -350,24 +579,9 @@ pub fn get_base_folder(source_folder: &str) -> String {
             BaseFolderType::from_str(captured_value.to_lowercase().as_str());
 
         match base_folder_type_result {
-            Ok(base_folder_type) => match base_folder_type {
-                BaseFolderType::LocalAppData => {
-                    env_var_value = get_local_app_data_folder();
-                }
-                BaseFolderType::RoamingAppData => {
-                    env_var_value = get_roaming_app_data_folder();
-                }
-                BaseFolderType::PersonalDropbox | BaseFolderType::BusinessDropbox => {
-                    env_var_value = get_dropbox_folder(base_folder_type);
-                }
-                BaseFolderType::ProgramFiles
-                | BaseFolderType::ProgramFilesX86
-                | BaseFolderType::WinDir
-                | BaseFolderType::HomePath
-                | BaseFolderType::Temp => {
-                    env_var_value = get_environment_folder(base_folder_type);
-                }
-            },
+            Ok(base_folder_type) => {
+                env_var_value = get_path_variable_folder(base_folder_type);
+            }
             Err(e) => {
                 error!("Unknown path variable: '{}', error: {}", captured_value, e);
             }
@@ -384,9 +598,94 @@ pub fn get_base_folder(source_folder: &str) -> String {
     output
 }
 
+/// Expands every %NAME% environment variable, like the %SystemRoot% or %ProgramFiles(x86)% in a
+/// shortcut's target. Unlike get_base_folder, any variable works and unset ones are left as is.
+pub fn expand_env_vars(path: &str) -> String {
+    let re = Regex::new(r#"%([^%]+)%"#).unwrap();
+    re.replace_all(path, |capture: &regex::Captures| {
+        match env::var(&capture[1]) {
+            Ok(value) => value,
+            Err(_) => {
+                debug!("Environment variable '{}' is not set", &capture[1]);
+                capture[0].to_string()
+            }
+        }
+    })
+    .to_string()
+}
+
+/// Replaces the start of a folder with a path variable, the reverse of get_base_folder.
+pub fn to_path_variable(folder_path: &str) -> String {
+    let variable_folders: Vec<(String, String)> = [
+        BaseFolderType::LocalAppData,
+        BaseFolderType::RoamingAppData,
+        BaseFolderType::ProgramFiles,
+        BaseFolderType::WinDir,
+    ]
+    .into_iter()
+    .filter_map(|base_folder_type| {
+        env::var(base_folder_type.to_string())
+            .ok()
+            .filter(|folder| !folder.is_empty())
+            .map(|folder| (format!("%{}%", base_folder_type), folder))
+    })
+    .collect();
+
+    replace_folder_prefix(folder_path, &variable_folders)
+}
+
+fn replace_folder_prefix(folder_path: &str, variable_folders: &[(String, String)]) -> String {
+    let best_match = variable_folders
+        .iter()
+        .filter(|(_, folder)| {
+            let folder = folder.trim_end_matches(['\\', '/']);
+            folder_path.len() >= folder.len()
+                && folder_path.is_char_boundary(folder.len())
+                && folder_path[..folder.len()].eq_ignore_ascii_case(folder)
+                && folder_path[folder.len()..]
+                    .chars()
+                    .next()
+                    .map(|c| c == '\\' || c == '/')
+                    .unwrap_or(true)
+        })
+        .max_by_key(|(_, folder)| folder.len());
+
+    match best_match {
+        Some((variable, folder)) => format!(
+            "{}{}",
+            variable,
+            &folder_path[folder.trim_end_matches(['\\', '/']).len()..]
+        ),
+        None => folder_path.to_string(),
+    }
+}
+
 pub fn parse_arguments(input: &str) -> Vec<String> {
+    try_parse_arguments(input).unwrap_or_default()
+}
+
+pub fn try_parse_arguments(input: &str) -> Option<Vec<String>> {
     let escaped_arguments = input.replace('\\', r"\\");
-    shlex::split(&escaped_arguments).unwrap_or_default()
+    shlex::split(&escaped_arguments)
+}
+
+pub fn get_unresolved_path_variables(source_folder: &str) -> Vec<String> {
+    let re = Regex::new(r#"%([^%]+)%"#).unwrap();
+
+    re.captures_iter(source_folder)
+        .filter_map(|capture| {
+            let captured_value = &capture[1];
+            let resolved = match BaseFolderType::from_str(captured_value.to_lowercase().as_str()) {
+                Ok(base_folder_type) => get_path_variable_folder(base_folder_type),
+                Err(_) => String::new(),
+            };
+
+            match resolved.is_empty() {
+                true => Some(format!("%{}%", captured_value)),
+                false => None,
+            }
+        })
+        .collect()
 }
 
 pub fn resolve_path(folder_path: &str) -> String {
@@ -404,6 +703,7 @@ pub fn resolve_path(folder_path: &str) -> String {
 mod tests {
     #[cfg(target_os = "windows")]
     use std::path::PathBuf;
+    use std::{env, fs};
 
     #[cfg(target_os = "windows")]
     use crate::paths::{
@@ -411,7 +711,17 @@ mod tests {
         get_roaming_app_data_folder,
     };
 
-    use crate::paths::{get_dropbox_folder_from_json, parse_arguments, BaseFolderType};
+    use glob::Pattern;
+    use indicatif::ProgressBar;
+    use uuid::Uuid;
+
+    use crate::data::SearchOptions;
+    use crate::paths::{
+        expand_env_vars, find_file_in_folders, get_dropbox_folder_from_json,
+        get_unresolved_path_variables, is_excluded, is_implied_regex, is_same_path,
+        parse_arguments, replace_folder_prefix, try_parse_arguments, BaseFolderType,
+        FileNameMatcher,
+    };
 
     #[cfg(target_os = "windows")]
     #[test]
@@ -595,6 +905,214 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn file_name_matcher_exact_ignores_case() {
+        // Arrange
+        let matcher = FileNameMatcher::new("Rider64.exe").unwrap();
+
+        // Act
+        let actual = matcher.matches("rider64.EXE");
+
+        // Assert
+        assert!(!matcher.is_pattern());
+        assert!(actual);
+    }
+
+    #[test]
+    fn file_name_matcher_glob() {
+        // Arrange
+        let matcher = FileNameMatcher::new("idea*.exe").unwrap();
+
+        // Act
+        let actual_match = matcher.matches("idea64.exe");
+        let actual_no_match = matcher.matches("rider64.exe");
+
+        // Assert
+        assert!(matcher.is_pattern());
+        assert!(actual_match);
+        assert!(!actual_no_match);
+    }
+
+    #[test]
+    fn file_name_matcher_regex() {
+        // Arrange
+        let matcher = FileNameMatcher::new(r"re:^Code( - Insiders)?\.exe$").unwrap();
+
+        // Act
+        let actual_stable = matcher.matches("Code.exe");
+        let actual_insiders = matcher.matches("Code - Insiders.exe");
+        let actual_no_match = matcher.matches("Code - Tunnel.exe");
+
+        // Assert
+        assert!(actual_stable);
+        assert!(actual_insiders);
+        assert!(!actual_no_match);
+    }
+
+    #[test]
+    fn file_name_matcher_regex_without_prefix() {
+        // Arrange
+        let matcher = FileNameMatcher::new("python3.[0-9]+").unwrap();
+
+        // Act
+        let actual_match = matcher.matches("python3.12");
+        let actual_no_match = matcher.matches("python3.12-config");
+
+        // Assert
+        assert!(matches!(matcher, FileNameMatcher::Regex(_)));
+        assert!(actual_match);
+        assert!(!actual_no_match);
+    }
+
+    #[test]
+    fn is_implied_regex_only_without_prefix() {
+        // Act
+        let actual_implied = is_implied_regex("python3.[0-9]+");
+        let actual_prefixed = is_implied_regex("re:python3.[0-9]+");
+        let actual_glob = is_implied_regex("idea*.exe");
+
+        // Assert
+        assert!(actual_implied);
+        assert!(!actual_prefixed);
+        assert!(!actual_glob);
+    }
+
+    #[test]
+    fn file_name_matcher_invalid_regex() {
+        // Arrange
+        let exe_name = "re:python3.[0-9+";
+
+        // Act
+        let actual = FileNameMatcher::new(exe_name);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn is_same_path_ignores_case() {
+        // Act
+        let actual = is_same_path(
+            r"C:\Program Files\Lemon\lemon.exe",
+            r"c:\program files\lemon\LEMON.EXE",
+        );
+
+        // Assert
+        assert!(actual);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn is_same_path_checks_case() {
+        // Act
+        let actual = is_same_path("/opt/lemon/lemon", "/opt/Lemon/lemon");
+
+        // Assert
+        assert!(!actual);
+    }
+
+    #[test]
+    fn expand_env_vars_any_variable() {
+        // Arrange
+        std::env::set_var("APP_LEMONATOR_TEST_ROOT(x86)", "C:\\Lemon");
+        let path = r"%APP_LEMONATOR_TEST_ROOT(x86)%\Lime\%APP_LEMONATOR_TEST_UNSET%\lime.exe";
+
+        // Act
+        let actual = expand_env_vars(path);
+
+        // Assert
+        assert_eq!(actual, r"C:\Lemon\Lime\%APP_LEMONATOR_TEST_UNSET%\lime.exe");
+    }
+
+    #[test]
+    fn find_file_in_folders_max_depth() {
+        // Arrange
+        let folder = env::temp_dir().join(format!("app-lemonator-search-{}", Uuid::new_v4()));
+        fs::create_dir_all(folder.join("lime").join("bin")).unwrap();
+        for file in ["lemon", "lime/lemon", "lime/bin/lemon"] {
+            fs::write(folder.join(file), "").unwrap();
+        }
+        let matcher = FileNameMatcher::new("lemon").unwrap();
+        let find = |max_depth: u32| {
+            let search_options = SearchOptions {
+                search_max_depth: Some(max_depth),
+                ..Default::default()
+            };
+            let mut files: Vec<String> = Vec::new();
+            find_file_in_folders(
+                &folder.display().to_string(),
+                &matcher,
+                &search_options,
+                &mut files,
+                &ProgressBar::hidden(),
+            );
+            files.len()
+        };
+
+        // Act
+        let actual_base_folder = find(0);
+        let actual_one_down = find(1);
+
+        // Assert
+        assert_eq!(actual_base_folder, 1);
+        assert_eq!(actual_one_down, 2);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn is_excluded_ignores_case() {
+        // Arrange
+        let excludes = vec![
+            Pattern::new("node_modules").unwrap(),
+            Pattern::new("temp*").unwrap(),
+        ];
+
+        // Act
+        let actual_excluded = is_excluded("Temp", &excludes);
+        let actual_included = is_excluded("JetBrains", &excludes);
+
+        // Assert
+        assert!(actual_excluded);
+        assert!(!actual_included);
+    }
+
+    #[test]
+    fn try_parse_arguments_unclosed_quote() {
+        // Arrange
+        let input = r#" --args --profile-directory="Profile 1"#;
+
+        // Act
+        let actual = try_parse_arguments(input);
+
+        // Assert
+        assert!(actual.is_none());
+    }
+
+    #[test]
+    fn get_unresolved_path_variables_unknown_variable() {
+        // Arrange
+        let source = r"%notafolder%\JetBrains";
+
+        // Act
+        let actual = get_unresolved_path_variables(source);
+
+        // Assert
+        assert_eq!(actual, vec!["%notafolder%".to_string()]);
+    }
+
+    #[test]
+    fn get_unresolved_path_variables_no_variables() {
+        // Arrange
+        let source = "/Applications";
+
+        // Act
+        let actual = get_unresolved_path_variables(source);
+
+        // Assert
+        assert!(actual.is_empty());
+    }
+
     #[cfg(target_os = "windows")]
     #[test]
     fn get_export_file_name_full_path() {
@@ -602,7 +1120,7 @@ mod tests {
         use crate::paths::get_unique_export_file_name;
         let source = r"C:\Windows\test.json";
         let default_path = dirs::document_dir().unwrap();
-        let default_file_name = get_unique_export_file_name();
+        let default_file_name = get_unique_export_file_name(".json");
 
         // Act
         let actual = get_export_file_name(source, default_path, &default_file_name);
@@ -618,7 +1136,7 @@ mod tests {
         use crate::paths::{get_full_path, get_unique_export_file_name};
         let source = r"C:\Windows\";
         let default_path = dirs::document_dir().unwrap();
-        let default_file_name = get_unique_export_file_name();
+        let default_file_name = get_unique_export_file_name(".json");
         let expected = get_full_path(source, &default_file_name);
 
         // Act
@@ -635,7 +1153,7 @@ mod tests {
         use crate::paths::{get_full_path, get_unique_export_file_name};
         let source = r#"test.json"#;
         let default_path = dirs::document_dir().unwrap();
-        let default_file_name = get_unique_export_file_name();
+        let default_file_name = get_unique_export_file_name(".json");
         let expected = get_full_path(default_path.to_str().unwrap(), source);
 
         // Act
@@ -652,7 +1170,7 @@ mod tests {
         use crate::paths::{get_full_path, get_unique_export_file_name};
         let source = String::new();
         let default_path = dirs::document_dir().unwrap();
-        let default_file_name = get_unique_export_file_name();
+        let default_file_name = get_unique_export_file_name(".json");
         let expected = get_full_path(default_path.to_str().unwrap(), &default_file_name);
 
         // Act
@@ -661,4 +1179,37 @@ mod tests {
         // Assert
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn replace_folder_prefix_uses_longest_folder() {
+        // Arrange
+        let variable_folders = vec![
+            ("%homepath%".to_string(), r"C:\Users\Lemon".to_string()),
+            (
+                "%localappdata%".to_string(),
+                r"C:\Users\Lemon\AppData\Local\".to_string(),
+            ),
+        ];
+
+        // Act
+        let actual = replace_folder_prefix(
+            r"c:\users\lemon\AppData\Local\Programs\Rider",
+            &variable_folders,
+        );
+
+        // Assert
+        assert_eq!(actual, r"%localappdata%\Programs\Rider");
+    }
+
+    #[test]
+    fn replace_folder_prefix_needs_whole_folder() {
+        // Arrange
+        let variable_folders = vec![("%programfiles%".to_string(), r"C:\Program".to_string())];
+
+        // Act
+        let actual = replace_folder_prefix(r"C:\Program Files\Lemon", &variable_folders);
+
+        // Assert
+        assert_eq!(actual, r"C:\Program Files\Lemon");
+    }
 }
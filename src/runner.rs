@@ -1,9 +1,26 @@
-use std::{ffi::OsStr, process::Command};
+use std::{
+    env,
+    ffi::OsStr,
+    path::Path,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
 
 use eyre::Context;
+use log::{debug, error};
 use owo_colors::OwoColorize;
+use sysinfo::{Pid, Signal, System};
 
-use crate::{data, paths};
+use crate::{constants, data, paths};
+
+#[derive(Clone, Debug)]
+pub struct RunningProcess {
+    pub pid: u32,
+    pub name: String,
+    pub run_time: u64,
+    pub memory: u64,
+}
 
 pub fn open_process(app: data::App, app_path: &str) -> Result<String, eyre::Report> {
     #[cfg(target_os = "macos")]
@@ -11,7 +28,7 @@ pub fn open_process(app: data::App, app_path: &str) -> Result<String, eyre::Repo
     #[cfg(target_os = "macos")]
     cmd.arg(app_path);
 
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "macos"))]
     let mut cmd = Command::new(app_path);
 
     // Double check we can see the app before running
@@ -59,3 +76,339 @@ fn add_arguments_to_command(cmd: &mut Command, additional_arguments: Option<Stri
         }
     }
 }
+
+pub fn get_app_statuses(apps: Vec<data::App>) -> Vec<data::AppStatus> {
+    let mut system = System::new();
+    system.refresh_processes();
+
+    apps.into_iter()
+        .map(|app| {
+            let (processes, path_exists) = match &app.app_path {
+                Some(app_path) => (
+                    find_app_processes_in_system(&system, app_path),
+                    paths::check_app_exists(app_path),
+                ),
+                None => (Vec::new(), false),
+            };
+
+            data::AppStatus {
+                running: !processes.is_empty(),
+                pids: processes.iter().map(|p| p.pid).collect(),
+                // Report the longest running process, that is when the app was started
+                uptime: processes.iter().map(|p| p.run_time).max(),
+                memory: match processes.is_empty() {
+                    true => None,
+                    false => Some(processes.iter().map(|p| p.memory).sum()),
+                },
+                path_exists,
+                app_name: app.app_name,
+                app_path: app.app_path,
+            }
+        })
+        .collect()
+}
+
+fn find_app_processes_in_system(system: &System, app_path: &str) -> Vec<RunningProcess> {
+    let mut processes: Vec<RunningProcess> = system
+        .processes()
+        .values()
+        .filter_map(|process| {
+            let exe = process.exe()?;
+            if !process_matches_app_path(exe, app_path) {
+                return None;
+            }
+            Some(RunningProcess {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                run_time: process.run_time(),
+                memory: process.memory(),
+            })
+        })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    debug!("Processes found for '{}' - {:?}", app_path, processes);
+    processes
+}
+
+fn process_matches_app_path(exe_path: &Path, app_path: &str) -> bool {
+    // On Windows paths are case insensitive, so compare them as such
+    let (exe_path, app_path) = if env::consts::OS == constants::OS_WINDOWS {
+        (
+            exe_path.display().to_string().to_lowercase(),
+            app_path.to_lowercase(),
+        )
+    } else {
+        (exe_path.display().to_string(), app_path.to_string())
+    };
+
+    // macOS app paths are bundle folders, with the real executable living inside them
+    Path::new(&exe_path).starts_with(Path::new(&app_path))
+}
+
+pub fn close_process(
+    app: data::App,
+    app_path: &str,
+    force: bool,
+    timeout: Duration,
+) -> Result<String, eyre::Report> {
+    let mut system = System::new();
+    system.refresh_processes();
+
+    let processes = find_app_processes_in_system(&system, app_path);
+    if processes.is_empty() {
+        return Ok(format!(
+            "App '{}' is not running from '{}'",
+            app.app_name.blue(),
+            app_path.magenta()
+        ));
+    }
+
+    for process in &processes {
+        request_close(&system, process);
+    }
+
+    let mut remaining = wait_for_exit(&mut system, &processes, timeout);
+
+    if !remaining.is_empty() && force {
+        for process in &remaining {
+            debug!("Force killing process {} '{}'", process.pid, process.name);
+            if let Some(running) = system.process(Pid::from_u32(process.pid)) {
+                if !running.kill() {
+                    error!(
+                        "Failed to force kill process {} for app '{}'",
+                        process.pid, app.app_name
+                    );
+                }
+            }
+        }
+        remaining = wait_for_exit(&mut system, &remaining, timeout);
+    }
+
+    if !remaining.is_empty() {
+        let pids: Vec<String> = remaining.iter().map(|p| p.pid.to_string()).collect();
+        return Err(eyre::eyre!(
+            "App '{}' is still running after {} seconds (PIDs {}).{}",
+            app.app_name,
+            timeout.as_secs(),
+            pids.join(", "),
+            if force {
+                ""
+            } else {
+                " Use --force to kill it."
+            }
+        ));
+    }
+
+    Ok(format!(
+        "Successfully closed '{}' ({} {})",
+        &app.app_name.blue(),
+        processes.len(),
+        if processes.len() == 1 {
+            "process"
+        } else {
+            "processes"
+        }
+    ))
+}
+
+fn request_close(system: &System, process: &RunningProcess) {
+    debug!(
+        "Requesting close of process {} '{}'",
+        process.pid, process.name
+    );
+
+    // Windows has no SIGTERM, taskkill without /F sends WM_CLOSE to the app windows instead
+    if env::consts::OS == constants::OS_WINDOWS {
+        if let Err(error) = Command::new("taskkill")
+            .args(["/PID", &process.pid.to_string()])
+            .output()
+        {
+            error!(
+                "Failed to run taskkill for process {}: {:?}",
+                process.pid, error
+            );
+        }
+        return;
+    }
+
+    if let Some(running) = system.process(Pid::from_u32(process.pid)) {
+        if running.kill_with(Signal::Term) != Some(true) {
+            error!("Failed to send SIGTERM to process {}", process.pid);
+        }
+    }
+}
+
+fn wait_for_exit(
+    system: &mut System,
+    processes: &[RunningProcess],
+    timeout: Duration,
+) -> Vec<RunningProcess> {
+    let start = Instant::now();
+    loop {
+        let remaining: Vec<RunningProcess> = processes
+            .iter()
+            .filter(|p| system.refresh_process(Pid::from_u32(p.pid)))
+            .cloned()
+            .collect();
+
+        if remaining.is_empty() || start.elapsed() >= timeout {
+            return remaining;
+        }
+
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    #[cfg(not(target_os = "windows"))]
+    use std::{
+        env, fs,
+        process::{Child, Command},
+        thread,
+        time::Duration,
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    use uuid::Uuid;
+
+    use crate::runner::process_matches_app_path;
+    #[cfg(not(target_os = "windows"))]
+    use crate::{
+        data::{App, OperatingSystem, SearchMethod},
+        runner::close_process,
+    };
+
+    /// Runs a copy of a system binary from a folder of its own, so no other process has its path.
+    #[cfg(not(target_os = "windows"))]
+    fn start_test_process(binary: &str, args: &[&str]) -> (App, String, Child) {
+        let folder = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&folder).unwrap();
+        let file_name = Path::new(binary).file_name().unwrap();
+        fs::copy(binary, folder.join(file_name)).unwrap();
+        let app_path = fs::canonicalize(folder.join(file_name))
+            .unwrap()
+            .display()
+            .to_string();
+        let child = Command::new(&app_path).args(args).spawn().unwrap();
+        let app = App::new(
+            "Lemon".to_string(),
+            file_name.to_string_lossy().to_string(),
+            None,
+            folder.display().to_string(),
+            SearchMethod::FolderSearch,
+            OperatingSystem::MacOS,
+        );
+        (app, app_path, child)
+    }
+
+    /// Waits for the process in the background, so it doesn't stay behind as a zombie once closed.
+    #[cfg(not(target_os = "windows"))]
+    fn reap(mut child: Child) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            child.wait().unwrap();
+        })
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn close_process_closes_gracefully() {
+        // Arrange
+        let (app, app_path, child) = start_test_process("/bin/sleep", &["30"]);
+        let waiter = reap(child);
+
+        // Act
+        let actual = close_process(app, &app_path, false, Duration::from_secs(5));
+
+        // Assert
+        assert!(actual.unwrap().contains("1 process"));
+        waiter.join().unwrap();
+        fs::remove_dir_all(Path::new(&app_path).parent().unwrap()).unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn close_process_times_out_then_force_kills() {
+        // Arrange
+        let (app, app_path, child) = start_test_process(
+            "/bin/sh",
+            &["-c", "trap '' TERM; while :; do sleep 1; done"],
+        );
+        let waiter = reap(child);
+        thread::sleep(Duration::from_millis(250));
+
+        // Act
+        let actual_timeout = close_process(app.clone(), &app_path, false, Duration::from_secs(1));
+        let actual_force = close_process(app, &app_path, true, Duration::from_secs(5));
+
+        // Assert
+        assert!(actual_timeout.unwrap_err().to_string().contains("--force"));
+        assert!(actual_force.is_ok());
+        waiter.join().unwrap();
+        fs::remove_dir_all(Path::new(&app_path).parent().unwrap()).unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn close_process_not_running() {
+        // Arrange
+        let app_path = "/Applications/Lemonator Missing.app";
+        let app = App::new(
+            "Lemon".to_string(),
+            "Lemon.app".to_string(),
+            None,
+            "/Applications".to_string(),
+            SearchMethod::Shortcut,
+            OperatingSystem::MacOS,
+        );
+
+        // Act
+        let actual = close_process(app, app_path, true, Duration::from_secs(1));
+
+        // Assert
+        assert!(actual.unwrap().contains("is not running"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn process_matches_app_path_bundle() {
+        // Arrange
+        let exe_path = Path::new("/Applications/iTerm.app/Contents/MacOS/iTerm2");
+
+        // Act
+        let actual = process_matches_app_path(exe_path, "/Applications/iTerm.app");
+
+        // Assert
+        assert!(actual);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn process_matches_app_path_similar_name() {
+        // Arrange
+        let exe_path = Path::new("/Applications/iTerm.app.old/Contents/MacOS/iTerm2");
+
+        // Act
+        let actual = process_matches_app_path(exe_path, "/Applications/iTerm.app");
+
+        // Assert
+        assert!(!actual);
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn process_matches_app_path_ignores_case() {
+        // Arrange
+        let exe_path = Path::new(r"C:\Program Files\Google\Chrome\Application\chrome.exe");
+
+        // Act
+        let actual = process_matches_app_path(
+            exe_path,
+            r"c:\program files\google\chrome\application\CHROME.EXE",
+        );
+
+        // Assert
+        assert!(actual);
+    }
+}
@@ -0,0 +1,289 @@
+use chrono::Duration;
+use eyre::{Context, Report};
+use plist::{Dictionary, Value};
+
+use crate::{constants, data};
+
+const TASK_NAME: &str = "app-lemonator-refresh";
+const LAUNCHD_LABEL: &str = "com.app-lemonator.refresh";
+
+pub struct ScheduleFile {
+    pub file_name: String,
+    pub contents: String,
+}
+
+pub fn generate(
+    scheduler: &data::ScheduleType,
+    exe_path: &str,
+    working_dir: &str,
+    every: &Duration,
+    stale_after: &Duration,
+) -> Result<Vec<ScheduleFile>, Report> {
+    match scheduler {
+        data::ScheduleType::Systemd => {
+            Ok(generate_systemd(exe_path, working_dir, every, stale_after))
+        }
+        data::ScheduleType::TaskScheduler => Ok(vec![generate_task_scheduler(
+            exe_path,
+            working_dir,
+            every,
+            stale_after,
+        )]),
+        data::ScheduleType::Launchd => Ok(vec![generate_launchd(
+            exe_path,
+            working_dir,
+            every,
+            stale_after,
+        )?]),
+    }
+}
+
+pub fn get_install_hint(scheduler: &data::ScheduleType, folder: &str) -> String {
+    match scheduler {
+        data::ScheduleType::Systemd => format!(
+            "Copy the files in '{}' to ~/.config/systemd/user/ and run 'systemctl --user enable --now {}.timer'",
+            folder, TASK_NAME
+        ),
+        data::ScheduleType::TaskScheduler => format!(
+            "Run 'schtasks /Create /TN \"{}\" /XML \"{}\"'",
+            TASK_NAME,
+            std::path::Path::new(folder)
+                .join(format!("{}.xml", TASK_NAME))
+                .display()
+        ),
+        data::ScheduleType::Launchd => format!(
+            "Copy '{}.plist' in '{}' to ~/Library/LaunchAgents/ and run 'launchctl load ~/Library/LaunchAgents/{}.plist'",
+            LAUNCHD_LABEL, folder, LAUNCHD_LABEL
+        ),
+    }
+}
+
+fn get_refresh_args(stale_after: &Duration) -> Vec<String> {
+    vec![
+        "refresh".to_string(),
+        "--stale-after".to_string(),
+        data::display_duration(stale_after),
+    ]
+}
+
+fn generate_systemd(
+    exe_path: &str,
+    working_dir: &str,
+    every: &Duration,
+    stale_after: &Duration,
+) -> Vec<ScheduleFile> {
+    let service = format!(
+        "[Unit]
+Description={} - refresh stale app paths
+
+[Service]
+Type=oneshot
+WorkingDirectory={}
+ExecStart=\"{}\" {}
+",
+        constants::APP_NAME,
+        working_dir,
+        exe_path,
+        get_refresh_args(stale_after).join(" ")
+    );
+
+    let timer = format!(
+        "[Unit]
+Description={} - refresh stale app paths every {}
+
+[Timer]
+OnBootSec=15min
+OnUnitActiveSec={}s
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+",
+        constants::APP_NAME,
+        data::display_duration(every),
+        every.num_seconds()
+    );
+
+    vec![
+        ScheduleFile {
+            file_name: format!("{}.service", TASK_NAME),
+            contents: service,
+        },
+        ScheduleFile {
+            file_name: format!("{}.timer", TASK_NAME),
+            contents: timer,
+        },
+    ]
+}
+
+fn generate_task_scheduler(
+    exe_path: &str,
+    working_dir: &str,
+    every: &Duration,
+    stale_after: &Duration,
+) -> ScheduleFile {
+    let start_boundary = chrono::Local::now().format("%Y-%m-%dT09:00:00");
+
+    let contents = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>{} - refresh stale app paths</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <TimeTrigger>
+      <Repetition>
+        <Interval>{}</Interval>
+        <StopAtDurationEnd>false</StopAtDurationEnd>
+      </Repetition>
+      <StartBoundary>{}</StartBoundary>
+      <Enabled>true</Enabled>
+    </TimeTrigger>
+  </Triggers>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <Hidden>true</Hidden>
+    <ExecutionTimeLimit>PT1H</ExecutionTimeLimit>
+    <Enabled>true</Enabled>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{}</Command>
+      <Arguments>{}</Arguments>
+      <WorkingDirectory>{}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        constants::APP_NAME,
+        format_iso8601_duration(every),
+        start_boundary,
+        escape_xml(exe_path),
+        escape_xml(&get_refresh_args(stale_after).join(" ")),
+        escape_xml(working_dir)
+    );
+
+    ScheduleFile {
+        file_name: format!("{}.xml", TASK_NAME),
+        contents,
+    }
+}
+
+fn generate_launchd(
+    exe_path: &str,
+    working_dir: &str,
+    every: &Duration,
+    stale_after: &Duration,
+) -> Result<ScheduleFile, Report> {
+    let mut program_arguments = vec![Value::String(exe_path.to_string())];
+    program_arguments.extend(get_refresh_args(stale_after).into_iter().map(Value::String));
+
+    let mut dict = Dictionary::new();
+    dict.insert(
+        "Label".to_string(),
+        Value::String(LAUNCHD_LABEL.to_string()),
+    );
+    dict.insert(
+        "ProgramArguments".to_string(),
+        Value::Array(program_arguments),
+    );
+    dict.insert(
+        "WorkingDirectory".to_string(),
+        Value::String(working_dir.to_string()),
+    );
+    dict.insert(
+        "StartInterval".to_string(),
+        Value::Integer(every.num_seconds().into()),
+    );
+    dict.insert("RunAtLoad".to_string(), Value::Boolean(false));
+
+    let mut contents: Vec<u8> = Vec::new();
+    Value::Dictionary(dict)
+        .to_writer_xml(&mut contents)
+        .wrap_err("Unable to generate launchd plist")?;
+
+    Ok(ScheduleFile {
+        file_name: format!("{}.plist", LAUNCHD_LABEL),
+        contents: String::from_utf8_lossy(&contents).to_string(),
+    })
+}
+
+fn format_iso8601_duration(duration: &Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+
+    let mut output = format!(
+        "P{}",
+        if days > 0 {
+            format!("{}D", days)
+        } else {
+            String::new()
+        }
+    );
+    if hours > 0 || minutes > 0 || days == 0 {
+        output.push('T');
+        if hours > 0 {
+            output.push_str(&format!("{}H", hours));
+        }
+        // Task Scheduler needs at least a minute between repeats
+        if minutes > 0 || (days == 0 && hours == 0) {
+            output.push_str(&format!("{}M", minutes.max(1)));
+        }
+    }
+    output
+}
+
+fn escape_xml(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::schedule::format_iso8601_duration;
+
+    #[test]
+    fn format_iso8601_duration_days() {
+        // Arrange
+        let duration = Duration::try_days(1).unwrap();
+
+        // Act
+        let actual = format_iso8601_duration(&duration);
+
+        // Assert
+        assert_eq!(actual, "P1D");
+    }
+
+    #[test]
+    fn format_iso8601_duration_days_and_hours() {
+        // Arrange
+        let duration = Duration::try_hours(30).unwrap();
+
+        // Act
+        let actual = format_iso8601_duration(&duration);
+
+        // Assert
+        assert_eq!(actual, "P1DT6H");
+    }
+
+    #[test]
+    fn format_iso8601_duration_under_a_minute() {
+        // Arrange
+        let duration = Duration::try_seconds(20).unwrap();
+
+        // Act
+        let actual = format_iso8601_duration(&duration);
+
+        // Assert
+        assert_eq!(actual, "PT1M");
+    }
+}
@@ -0,0 +1,421 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use eyre::{eyre, Context, Report};
+use serde::{Deserialize, Serialize};
+
+use crate::data;
+
+/// Columns that must be in the header row, the rest can be left out.
+const REQUIRED_COLUMNS: [&str; 4] = ["app_name", "exe_name", "search_term", "search_method"];
+
+/// Separates the values in columns that hold more than one, like excludes and fallbacks.
+const LIST_SEPARATOR: char = ';';
+
+/// A row of a CSV file of apps. Empty cells are the same as leaving the column out.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CsvRow {
+    app_name: String,
+    exe_name: String,
+    params: String,
+    search_term: String,
+    search_method: String,
+    operating_system: String,
+    max_depth: String,
+    excludes: String,
+    follow_symlinks: String,
+    max_matches: String,
+    time_budget: String,
+    arch: String,
+    /// Searches to try in order, as method:term separated by semicolons.
+    fallbacks: String,
+    verify: String,
+    signer: String,
+    /// Variants for other operating systems, as os:method:term separated by semicolons.
+    variants: String,
+    /// The exe name of each variant, in the same order. Empty when a variant uses the app's.
+    variant_exe_names: String,
+    /// The params of each variant, in the same order. Empty when a variant uses the app's.
+    variant_params: String,
+}
+
+/// An app read from a CSV file, or why it couldn't be read, with the line it is on.
+#[derive(Debug)]
+pub struct CsvApp {
+    pub line: u64,
+    pub app: Result<data::App, String>,
+}
+
+pub fn is_csv_file(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false)
+}
+
+pub fn write(apps: &[data::App]) -> Result<String, Report> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for app in apps {
+        writer
+            .serialize(to_row(app))
+            .wrap_err(format!("Unable to write app '{}' as CSV", app.app_name))?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|error| eyre!("Unable to write apps as CSV - {}", error))?;
+    String::from_utf8(bytes).wrap_err("Unable to write apps as CSV")
+}
+
+/// Reads the apps from a CSV file with a header row. Rows that can't be turned into an app are
+/// returned with the reason, so the other rows can still be imported.
+pub fn parse(
+    contents: &str,
+    operating_system: &data::OperatingSystem,
+) -> Result<Vec<CsvApp>, Report> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes());
+    let headers = reader
+        .headers()
+        .wrap_err("CSV file has no header row")?
+        .clone();
+    for column in REQUIRED_COLUMNS {
+        if !headers.iter().any(|header| header == column) {
+            return Err(eyre!("CSV file has no '{}' column", column));
+        }
+    }
+    let has_variant_exe_names = headers.iter().any(|header| header == "variant_exe_names");
+
+    let mut apps = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                apps.push(CsvApp {
+                    line: get_line(contents, error.position()),
+                    app: Err(format!("Row is not valid CSV - {}", error)),
+                });
+                continue;
+            }
+        };
+        let line = get_line(contents, record.position());
+        if record.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+
+        let app = record
+            .deserialize::<CsvRow>(Some(&headers))
+            .map_err(|error| error.to_string())
+            .and_then(|row| to_app(&row, operating_system, has_variant_exe_names));
+        apps.push(CsvApp { line, app });
+    }
+
+    Ok(apps)
+}
+
+/// The line a record is on. The reader doesn't count blank lines, and a record's position can be
+/// before the blank lines ahead of it, so the line is worked out from the contents.
+fn get_line(contents: &str, position: Option<&csv::Position>) -> u64 {
+    let Some(start) = position.map(|position| position.byte() as usize) else {
+        return 0;
+    };
+    let before = contents.get(..start).unwrap_or_default();
+    let blank_lines = contents
+        .get(start..)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|character| *character == '\n' || *character == '\r')
+        .filter(|character| *character == '\n')
+        .count();
+    (before.matches('\n').count() + blank_lines) as u64 + 1
+}
+
+fn to_row(app: &data::App) -> CsvRow {
+    let options = &app.search_options;
+    CsvRow {
+        app_name: app.app_name.clone(),
+        exe_name: app.exe_name.clone(),
+        params: app.params.clone().unwrap_or_default(),
+        search_term: app.search_term.clone(),
+        search_method: app.search_method.to_string(),
+        operating_system: app.operating_system.to_string(),
+        max_depth: display_option_number(options.search_max_depth),
+        excludes: options.get_excludes().join(&LIST_SEPARATOR.to_string()),
+        follow_symlinks: options.search_follow_symlinks.to_string(),
+        max_matches: display_option_number(options.search_max_matches),
+        time_budget: display_option_number(options.search_time_budget),
+        arch: options.search_arch.to_string(),
+        fallbacks: app
+            .fallback_strategies
+            .iter()
+            .map(|strategy| format!("{}:{}", strategy.search_method, strategy.search_term))
+            .collect::<Vec<String>>()
+            .join(&LIST_SEPARATOR.to_string()),
+        verify: app.verify_options.verify_policy.to_string(),
+        signer: app.verify_options.verify_signer.clone().unwrap_or_default(),
+        variants: app
+            .variants
+            .iter()
+            .map(|variant| {
+                format!(
+                    "{}:{}:{}",
+                    variant.operating_system, variant.search_method, variant.search_term
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(&LIST_SEPARATOR.to_string()),
+        variant_exe_names: app
+            .variants
+            .iter()
+            .map(|variant| variant.exe_name.clone().unwrap_or_default())
+            .collect::<Vec<String>>()
+            .join(&LIST_SEPARATOR.to_string()),
+        variant_params: app
+            .variants
+            .iter()
+            .map(|variant| variant.params.clone().unwrap_or_default())
+            .collect::<Vec<String>>()
+            .join(&LIST_SEPARATOR.to_string()),
+    }
+}
+
+fn display_option_number(value: Option<u32>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn to_app(
+    row: &CsvRow,
+    operating_system: &data::OperatingSystem,
+    has_variant_exe_names: bool,
+) -> Result<data::App, String> {
+    let mut app = data::App::new(
+        row.app_name.clone(),
+        row.exe_name.clone(),
+        parse_option_string(&row.params),
+        row.search_term.clone(),
+        parse_value_enum("search_method", &row.search_method)?,
+        match row.operating_system.is_empty() {
+            true => operating_system.clone(),
+            false => parse_value_enum("operating_system", &row.operating_system)?,
+        },
+    );
+    app.search_options = data::SearchOptions {
+        search_max_depth: parse_option_number("max_depth", &row.max_depth)?,
+        search_excludes: parse_option_string(&row.excludes),
+        search_follow_symlinks: parse_bool("follow_symlinks", &row.follow_symlinks)?,
+        search_max_matches: parse_option_number("max_matches", &row.max_matches)?,
+        search_time_budget: parse_option_number("time_budget", &row.time_budget)?,
+        search_arch: match row.arch.is_empty() {
+            true => data::ArchPreference::default(),
+            false => parse_value_enum("arch", &row.arch)?,
+        },
+    };
+    app.fallback_strategies = split_list(&row.fallbacks)
+        .map(data::parse_search_strategy)
+        .collect::<Result<Vec<data::SearchStrategy>, String>>()?;
+    app.verify_options = data::VerifyOptions {
+        verify_policy: match row.verify.is_empty() {
+            true => data::VerifyPolicy::default(),
+            false => parse_value_enum("verify", &row.verify)?,
+        },
+        verify_signer: parse_option_string(&row.signer),
+    };
+    app.variants = split_list(&row.variants)
+        .enumerate()
+        .map(|(index, input)| {
+            let mut variant = data::parse_variant(input)?;
+            variant.params = get_list_item(&row.variant_params, index);
+            // Without the column, shortcuts and path lookups get their exe name from the term
+            // the way add --variant reads it
+            if has_variant_exe_names {
+                let (_, strategy) = input.split_once(':').unwrap_or_default();
+                variant.search_term = data::parse_search_strategy(strategy)?.search_term;
+                variant.exe_name = get_list_item(&row.variant_exe_names, index);
+            }
+            Ok(variant)
+        })
+        .collect::<Result<Vec<data::AppVariant>, String>>()?;
+
+    Ok(app)
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(LIST_SEPARATOR)
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+}
+
+/// The item at the index of a column that has one for each variant, empty items are None.
+fn get_list_item(value: &str, index: usize) -> Option<String> {
+    value
+        .split(LIST_SEPARATOR)
+        .nth(index)
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+}
+
+fn parse_option_string(value: &str) -> Option<String> {
+    match value.is_empty() {
+        true => None,
+        false => Some(value.to_string()),
+    }
+}
+
+fn parse_option_number(column: &str, value: &str) -> Result<Option<u32>, String> {
+    match value.is_empty() {
+        true => Ok(None),
+        false => value
+            .parse::<u32>()
+            .map(Some)
+            .map_err(|_| format!("'{}' is not a valid number for {}", value, column)),
+    }
+}
+
+fn parse_bool(column: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "" | "false" | "no" | "0" => Ok(false),
+        "true" | "yes" | "1" => Ok(true),
+        _ => Err(format!("'{}' is not true or false for {}", value, column)),
+    }
+}
+
+fn parse_value_enum<T: ValueEnum>(column: &str, value: &str) -> Result<T, String> {
+    T::from_str(value, true).map_err(|_| {
+        format!(
+            "'{}' is not a valid {}, expected one of {}",
+            value,
+            column,
+            T::value_variants()
+                .iter()
+                .filter_map(|variant| variant.to_possible_value())
+                .map(|possible_value| possible_value.get_name().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{parse_variant, App, AppVariant, OperatingSystem, SearchMethod, SearchStrategy},
+        spreadsheet::{parse, write},
+    };
+
+    #[test]
+    fn parse_reports_bad_rows_by_line() {
+        // Arrange
+        let contents = "app_name,exe_name,search_term,search_method,max_depth\n\
+            Rider,rider64.exe,%localappdata%\\Programs,folder-search,4\n\
+            \n\
+            Lemon,lemon.exe,C:\\Lemon,teleport,\n\
+            Lime,lime.exe,C:\\Lime,Shortcut,deep\n\
+            Kiwi,kiwi.exe\n\
+            Lemonade,lemonade.exe,C:\\Lemonade,folder-search,\n";
+
+        // Act
+        let actual = parse(contents, &OperatingSystem::Windows).unwrap();
+
+        // Assert
+        assert_eq!(actual.len(), 5);
+        assert_eq!(actual[0].line, 2);
+        let rider = actual[0].app.as_ref().unwrap();
+        assert_eq!(rider.search_method, SearchMethod::FolderSearch);
+        assert_eq!(rider.search_options.search_max_depth, Some(4));
+        assert_eq!(rider.operating_system, OperatingSystem::Windows);
+        assert_eq!(actual[1].line, 4);
+        assert!(actual[1].app.is_err());
+        assert_eq!(actual[2].line, 5);
+        assert!(actual[2].app.is_err());
+        assert_eq!(actual[3].line, 6);
+        assert!(actual[3].app.is_err());
+        assert_eq!(actual[4].line, 7);
+        assert!(actual[4].app.is_ok());
+    }
+
+    #[test]
+    fn parse_missing_required_column_fails() {
+        // Arrange
+        let contents = "app_name,exe_name,search_term\nRider,rider64.exe,C:\\Rider\n";
+
+        // Act
+        let actual = parse(contents, &OperatingSystem::Windows);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn write_and_parse_round_trip() {
+        // Arrange
+        let mut app = App::new(
+            "Terminal".to_string(),
+            "wt.exe".to_string(),
+            Some("--title \"Lemon, Lime\"".to_string()),
+            "Microsoft.WindowsTerminal".to_string(),
+            SearchMethod::PSGetApp,
+            OperatingSystem::Windows,
+        );
+        app.search_options.search_excludes = Some("Temp;*.old".to_string());
+        app.fallback_strategies = vec![SearchStrategy::new(
+            SearchMethod::FolderSearch,
+            "%programfiles%".to_string(),
+        )];
+        app.variants = vec![parse_variant("macos:shortcut:~/Applications/iTerm.app").unwrap()];
+
+        // Act
+        let contents = write(&[app.clone()]).unwrap();
+        let actual = parse(&contents, &OperatingSystem::Windows).unwrap();
+
+        // Assert
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].app, Ok(app));
+    }
+
+    #[test]
+    fn write_and_parse_round_trip_variants() {
+        // Arrange
+        let mut app = App::new(
+            "Editor".to_string(),
+            "code.exe".to_string(),
+            Some("--new-window".to_string()),
+            "%localappdata%\\Programs".to_string(),
+            SearchMethod::FolderSearch,
+            OperatingSystem::Windows,
+        );
+        app.variants = vec![
+            AppVariant {
+                operating_system: OperatingSystem::MacOS,
+                exe_name: None,
+                search_method: SearchMethod::Shortcut,
+                search_term: "~/Applications".to_string(),
+                params: Some("-n".to_string()),
+            },
+            AppVariant {
+                operating_system: OperatingSystem::Linux,
+                exe_name: Some("code".to_string()),
+                search_method: SearchMethod::FolderSearch,
+                search_term: "/usr/share".to_string(),
+                params: None,
+            },
+        ];
+        let contents_by_hand = "app_name,exe_name,search_term,search_method,variants\n\
+            Editor,code.exe,C:\\Code,folder-search,macos:shortcut:~/Applications/Code.app\n";
+
+        // Act
+        let contents = write(&[app.clone()]).unwrap();
+        let actual = parse(&contents, &OperatingSystem::Windows).unwrap();
+        let actual_by_hand = parse(contents_by_hand, &OperatingSystem::Windows).unwrap();
+
+        // Assert
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].app, Ok(app));
+        let variant = &actual_by_hand[0].app.as_ref().unwrap().variants[0];
+        assert_eq!(variant.exe_name, Some("Code.app".to_string()));
+        assert_eq!(variant.search_term, "~/Applications");
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Write},
+};
+
+use eyre::{eyre, Context, Report};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{constants, data};
+
+/// Stream Deck's action for opening an app or file.
+pub const OPEN_ACTION_UUID: &str = "com.elgato.streamdeck.system.open";
+
+/// The original 15 key Stream Deck, which profiles for the other models can be converted from.
+const DEVICE_MODEL: &str = "20GAA9901";
+const COLUMNS: usize = 5;
+const ROWS: usize = 3;
+
+/// Most apps a profile can hold, one per key.
+pub const MAX_APPS: usize = COLUMNS * ROWS;
+
+/// Builds a Stream Deck profile with a key for each app, which opens it with App Lemonator. Icons
+/// are PNGs by app name, apps without one only show their title.
+pub fn write_profile(
+    apps: &[data::App],
+    lemonator_path: &str,
+    icons: &HashMap<String, Vec<u8>>,
+) -> Result<Vec<u8>, Report> {
+    if apps.len() > MAX_APPS {
+        return Err(eyre!(
+            "A Stream Deck profile holds up to {} apps, there are {}. Use --app to pick the apps to export",
+            MAX_APPS,
+            apps.len()
+        ));
+    }
+
+    let manifest = get_manifest(apps, lemonator_path, icons);
+    let profile_folder = format!("{}.sdProfile", Uuid::new_v4().to_string().to_uppercase());
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.add_directory(format!("{}/", profile_folder), SimpleFileOptions::default())
+        .wrap_err("Unable to add the profile folder")?;
+    zip.start_file(
+        format!("{}/manifest.json", profile_folder),
+        SimpleFileOptions::default(),
+    )
+    .wrap_err("Unable to add the profile manifest")?;
+    zip.write_all(
+        serde_json::to_string_pretty(&manifest)
+            .wrap_err("Unable to write the profile manifest")?
+            .as_bytes(),
+    )
+    .wrap_err("Unable to write the profile manifest")?;
+
+    for (index, app) in apps.iter().enumerate() {
+        let Some(icon) = icons.get(&app.app_name) else {
+            continue;
+        };
+        zip.start_file(
+            format!("{}/{}", profile_folder, get_image_name(index)),
+            SimpleFileOptions::default(),
+        )
+        .wrap_err(format!("Unable to add the icon for app '{}'", app.app_name))?;
+        zip.write_all(icon).wrap_err(format!(
+            "Unable to write the icon for app '{}'",
+            app.app_name
+        ))?;
+    }
+
+    let cursor = zip.finish().wrap_err("Unable to finish the profile")?;
+    Ok(cursor.into_inner())
+}
+
+/// Reads the manifest back out of a profile, for checking what was written.
+pub fn read_profile_manifest(profile: &[u8]) -> Result<Value, Report> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(profile)).wrap_err("Stream Deck profile is not a zip file")?;
+    let manifest_name = archive
+        .file_names()
+        .find(|file_name| file_name.ends_with(".sdProfile/manifest.json"))
+        .map(|file_name| file_name.to_string())
+        .ok_or_else(|| eyre!("Stream Deck profile has no manifest"))?;
+
+    let mut contents = String::new();
+    archive
+        .by_name(&manifest_name)
+        .wrap_err("Unable to open the profile manifest")?
+        .read_to_string(&mut contents)
+        .wrap_err("Unable to read the profile manifest")?;
+    serde_json::from_str(&contents).wrap_err("Stream Deck profile manifest is not JSON")
+}
+
+fn get_manifest(
+    apps: &[data::App],
+    lemonator_path: &str,
+    icons: &HashMap<String, Vec<u8>>,
+) -> Value {
+    let mut actions = Map::new();
+    for (index, app) in apps.iter().enumerate() {
+        // Keys are named by column then row
+        let key = format!("{},{}", index % COLUMNS, index / COLUMNS);
+        let image = match icons.contains_key(&app.app_name) {
+            true => get_image_name(index),
+            false => String::new(),
+        };
+        actions.insert(key, get_open_action(app, lemonator_path, &image));
+    }
+
+    json!({
+        "Actions": actions,
+        "Device": {
+            "Model": DEVICE_MODEL,
+            "UUID": "",
+        },
+        "Name": "App Lemonator",
+        "Version": "1.0",
+    })
+}
+
+/// Images sit in the profile folder, named for the key they are on.
+fn get_image_name(index: usize) -> String {
+    format!("Images/{}_{}.png", index % COLUMNS, index / COLUMNS)
+}
+
+fn get_open_action(app: &data::App, lemonator_path: &str, image: &str) -> Value {
+    json!({
+        "Name": "Open",
+        "Settings": {
+            "openInBrowser": true,
+            "path": get_open_command(&app.app_name, lemonator_path),
+        },
+        "State": 0,
+        "States": [{
+            "FFamily": "",
+            "FSize": "",
+            "FStyle": "",
+            "FUnderline": "",
+            "Image": image,
+            "ShowTitle": true,
+            "Title": app.app_name,
+            "TitleAlignment": "bottom",
+            "TitleColor": "#ffffff",
+        }],
+        "UUID": OPEN_ACTION_UUID,
+    })
+}
+
+/// The command line that opens the app, quoting anything with spaces.
+fn get_open_command(app_name: &str, lemonator_path: &str) -> String {
+    let quote = |value: &str| match value.contains(' ') {
+        true => format!("\"{}\"", value),
+        false => value.to_string(),
+    };
+    format!("{} open {}", quote(lemonator_path), quote(app_name))
+}
+
+/// The path of the App Lemonator exe the keys run, falling back to the name on the PATH.
+pub fn get_lemonator_path(current_exe: &str) -> String {
+    match current_exe.is_empty() {
+        true => constants::APP_NAME.to_string(),
+        false => current_exe.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{Cursor, Read},
+    };
+
+    use zip::ZipArchive;
+
+    use crate::{
+        data::{App, OperatingSystem, SearchMethod},
+        streamdeck::{read_profile_manifest, write_profile, MAX_APPS, OPEN_ACTION_UUID},
+    };
+
+    fn read_profile_image(profile: &[u8], image: &str) -> Vec<u8> {
+        let mut archive = ZipArchive::new(Cursor::new(profile)).unwrap();
+        let image_name = archive
+            .file_names()
+            .find(|file_name| file_name.ends_with(&format!(".sdProfile/{}", image)))
+            .unwrap()
+            .to_string();
+        let mut contents = Vec::new();
+        archive
+            .by_name(&image_name)
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    fn new_app(app_name: &str) -> App {
+        App::new(
+            app_name.to_string(),
+            "lemon.exe".to_string(),
+            None,
+            "C:\\Lemon".to_string(),
+            SearchMethod::Shortcut,
+            OperatingSystem::Windows,
+        )
+    }
+
+    #[test]
+    fn write_profile_one_open_action_per_app() {
+        // Arrange
+        let apps: Vec<App> = ["Lemon", "Lime Editor", "Orange", "Grape", "Kiwi", "Pear"]
+            .iter()
+            .map(|app_name| new_app(app_name))
+            .collect();
+        let lemonator_path = "C:\\Program Files\\App Lemonator\\app-lemonator.exe";
+
+        // Act
+        let profile = write_profile(&apps, lemonator_path, &HashMap::new()).unwrap();
+        let actual = read_profile_manifest(&profile).unwrap();
+
+        // Assert
+        let actions = actual["Actions"].as_object().unwrap();
+        assert_eq!(actions.len(), 6);
+        assert_eq!(actions["1,0"]["UUID"], OPEN_ACTION_UUID);
+        assert_eq!(actions["1,0"]["States"][0]["Title"], "Lime Editor");
+        assert_eq!(
+            actions["1,0"]["Settings"]["path"],
+            "\"C:\\Program Files\\App Lemonator\\app-lemonator.exe\" open \"Lime Editor\""
+        );
+        assert_eq!(actions["0,1"]["States"][0]["Title"], "Pear");
+        assert_eq!(actions["0,1"]["States"][0]["Image"], "");
+    }
+
+    #[test]
+    fn write_profile_icons_on_their_keys() {
+        // Arrange
+        let apps = vec![new_app("Lemon"), new_app("Lime")];
+        let icons = HashMap::from([("Lime".to_string(), b"lime icon".to_vec())]);
+
+        // Act
+        let profile = write_profile(&apps, "app-lemonator", &icons).unwrap();
+        let manifest = read_profile_manifest(&profile).unwrap();
+
+        // Assert
+        let actions = manifest["Actions"].as_object().unwrap();
+        assert_eq!(actions["0,0"]["States"][0]["Image"], "");
+        let image = actions["1,0"]["States"][0]["Image"].as_str().unwrap();
+        assert_eq!(image, "Images/1_0.png");
+        assert_eq!(read_profile_image(&profile, image), b"lime icon");
+    }
+
+    #[test]
+    fn write_profile_too_many_apps_fails() {
+        // Arrange
+        let apps: Vec<App> = (0..=MAX_APPS)
+            .map(|index| new_app(&format!("Lemon {}", index)))
+            .collect();
+
+        // Act
+        let actual = write_profile(&apps, "app-lemonator", &HashMap::new());
+
+        // Assert
+        assert!(actual.is_err());
+    }
+}
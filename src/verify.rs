@@ -0,0 +1,399 @@
+use std::{env, fmt, fs::File, io, path::Path, process::Command};
+
+use eyre::{eyre, Context, Report};
+use log::debug;
+use owo_colors::OwoColorize;
+use sha2::{Digest, Sha256};
+
+use crate::{bundle, constants, data, finder, paths};
+
+/// A check the file about to be opened failed.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    HashChanged {
+        recorded: String,
+        actual: String,
+    },
+    SignerMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::HashChanged { recorded, actual } => write!(
+                f,
+                "SHA-256 changed from '{}' to '{}' without a new path or version",
+                recorded, actual
+            ),
+            Problem::SignerMismatch {
+                expected,
+                actual: Some(actual),
+            } => write!(f, "Signed by '{}', expected '{}'", actual, expected),
+            Problem::SignerMismatch {
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "Not signed, or the signature is invalid, expected '{}'",
+                expected
+            ),
+        }
+    }
+}
+
+/// What is known about the file about to be opened.
+pub struct FileCheck<'a> {
+    pub recorded_hash: Option<&'a str>,
+    pub actual_hash: &'a str,
+    /// The path or version differs from when the hash was recorded, so a new hash is expected.
+    pub file_changed: bool,
+    pub expected_signer: Option<&'a str>,
+    pub actual_signer: Option<&'a str>,
+}
+
+/// Hashes the file that will be run. For app bundles, that is the executable inside the bundle.
+pub fn hash_file(path: &str) -> Result<String, Report> {
+    let executable = bundle::get_executable_path(Path::new(path))
+        .ok_or_else(|| eyre!("Unable to find the executable in '{}'", path))?;
+    let mut file = File::open(&executable).wrap_err(format!(
+        "Unable to open '{}' to hash it",
+        executable.display()
+    ))?;
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .wrap_err(format!("Unable to hash '{}'", executable.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Reads who signed the file, the Authenticode signer on Windows or the codesign team ID on
+/// macOS. Files that are unsigned, or whose signature doesn't verify, have no signer.
+pub fn get_signer(path: &str) -> Option<String> {
+    if env::consts::OS == constants::OS_WINDOWS {
+        let stdout_strings = finder::run_powershell_cmd(&format!(
+            r#"Get-AuthenticodeSignature -LiteralPath "{}" | ForEach-Object {{ $_.Status; $_.SignerCertificate.Subject }}"#,
+            path
+        ))
+        .ok()?;
+        return get_authenticode_signer(&stdout_strings);
+    }
+
+    if env::consts::OS == constants::OS_MACOS {
+        let verified = Command::new("codesign")
+            .args(["--verify", "--strict", path])
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+        if !verified {
+            debug!("codesign could not verify '{}'", path);
+            return None;
+        }
+
+        // codesign writes the details to stderr
+        let output = Command::new("codesign")
+            .args(["--display", "--verbose=2", path])
+            .output()
+            .ok()?;
+        return get_codesign_team_id(&String::from_utf8_lossy(&output.stderr));
+    }
+
+    None
+}
+
+/// Reads the status and certificate subject lines written by Get-AuthenticodeSignature,
+/// returning the common name of the signer when the signature is valid.
+fn get_authenticode_signer(stdout_strings: &[String]) -> Option<String> {
+    let mut lines = stdout_strings
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty());
+    if lines.next()? != "Valid" {
+        return None;
+    }
+
+    let subject = lines.next()?;
+    let mut remaining = subject;
+    while let Some(index) = remaining.find("CN=") {
+        let at_start = index == 0 || remaining[..index].trim_end().ends_with(',');
+        let value = &remaining[index + 3..];
+        if at_start {
+            let common_name = match value.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                None => value.split(',').next().unwrap_or_default(),
+            };
+            return Some(common_name.trim().to_string());
+        }
+        remaining = value;
+    }
+    None
+}
+
+fn get_codesign_team_id(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("TeamIdentifier="))
+        .map(|team_id| team_id.trim())
+        .filter(|team_id| !team_id.is_empty() && *team_id != "not set")
+        .map(|team_id| team_id.to_string())
+}
+
+/// Whether the file is at a different path to when the app was last updated. A new version at
+/// the same path is still checked against the recorded hash, until update records the new one.
+pub fn is_file_changed(app: &data::App, app_file_version: &data::FileVersion) -> bool {
+    !app.app_path
+        .as_deref()
+        .map(|app_path| paths::is_same_path(app_path, &app_file_version.path))
+        .unwrap_or(false)
+}
+
+pub fn find_problems(check: &FileCheck) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    if let Some(recorded_hash) = check.recorded_hash {
+        if !check.file_changed && !recorded_hash.eq_ignore_ascii_case(check.actual_hash) {
+            problems.push(Problem::HashChanged {
+                recorded: recorded_hash.to_string(),
+                actual: check.actual_hash.to_string(),
+            });
+        }
+    }
+
+    if let Some(expected_signer) = check.expected_signer {
+        let signer_matches = check
+            .actual_signer
+            .map(|actual_signer| {
+                actual_signer
+                    .trim()
+                    .eq_ignore_ascii_case(expected_signer.trim())
+            })
+            .unwrap_or(false);
+        if !signer_matches {
+            problems.push(Problem::SignerMismatch {
+                expected: expected_signer.to_string(),
+                actual: check.actual_signer.map(|signer| signer.to_string()),
+            });
+        }
+    }
+
+    problems
+}
+
+/// Applies the policy to the problems found, returning the warnings to show when the app can
+/// still be opened.
+pub fn apply_policy(
+    policy: &data::VerifyPolicy,
+    app_name: &str,
+    problems: &[Problem],
+) -> Result<Vec<String>, Report> {
+    if problems.is_empty() || *policy == data::VerifyPolicy::Off {
+        return Ok(Vec::new());
+    }
+
+    let details = problems
+        .iter()
+        .map(|problem| problem.to_string())
+        .collect::<Vec<String>>();
+    match policy {
+        data::VerifyPolicy::Refuse => Err(eyre!(
+            "Refusing to open app '{}', verification failed - {}",
+            app_name,
+            details.join("; ")
+        )),
+        _ => Ok(details
+            .iter()
+            .map(|detail| {
+                format!("Verification of app '{}' failed - {}", app_name, detail)
+                    .yellow()
+                    .to_string()
+            })
+            .collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{App, FileVersion, OperatingSystem, SearchMethod, VerifyPolicy},
+        verify::{
+            apply_policy, find_problems, get_authenticode_signer, get_codesign_team_id, hash_file,
+            is_file_changed, FileCheck, Problem,
+        },
+    };
+
+    fn new_check<'a>(recorded_hash: Option<&'a str>, file_changed: bool) -> FileCheck<'a> {
+        FileCheck {
+            recorded_hash,
+            actual_hash: "abc123",
+            file_changed,
+            expected_signer: None,
+            actual_signer: None,
+        }
+    }
+
+    #[test]
+    fn hash_file_app_bundle_hashes_executable() {
+        // Arrange
+        let file = format!(
+            "{}/tests/fixtures/arch/macho_universal",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let bundle = format!(
+            "{}/tests/fixtures/bundles/Applications/Utilities/Lime.app",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        // Act
+        let actual_file = hash_file(&file).unwrap();
+        let actual_bundle = hash_file(&bundle).unwrap();
+
+        // Assert
+        assert_eq!(
+            actual_file,
+            "6b3eec5175bb4d69d045a61bb0be4318ec0ce3fe8222cfdcc9464c6b73fc32a3"
+        );
+        assert_eq!(actual_bundle, actual_file);
+    }
+
+    #[test]
+    fn is_file_changed_only_when_moved() {
+        // Arrange
+        let mut app = App::new(
+            "Lemon".to_string(),
+            "lemon.exe".to_string(),
+            None,
+            "C:\\Lemon".to_string(),
+            SearchMethod::Shortcut,
+            OperatingSystem::Windows,
+        );
+        app.app_path = Some("C:\\Lemon\\lemon.exe".to_string());
+        app.app_version = Some("1.2.0.0".to_string());
+        let same = FileVersion::new(
+            String::new(),
+            "C:\\Lemon\\lemon.exe".to_string(),
+            "1.2.0.0".to_string(),
+        );
+        let updated_in_place = FileVersion::new(
+            String::new(),
+            "C:\\Lemon\\lemon.exe".to_string(),
+            "1.3.0.0".to_string(),
+        );
+        let moved = FileVersion::new(
+            String::new(),
+            "C:\\Lemon 1.3\\lemon.exe".to_string(),
+            "1.3.0.0".to_string(),
+        );
+
+        // Act
+        let actual_same = is_file_changed(&app, &same);
+        let actual_updated_in_place = is_file_changed(&app, &updated_in_place);
+        let actual_moved = is_file_changed(&app, &moved);
+
+        // Assert
+        assert!(!actual_same);
+        assert!(!actual_updated_in_place);
+        assert!(actual_moved);
+    }
+
+    #[test]
+    fn find_problems_hash_changed_only_when_file_unchanged() {
+        // Arrange
+        let unexpected = new_check(Some("def456"), false);
+        let expected = new_check(Some("def456"), true);
+        let not_recorded = new_check(None, false);
+
+        // Act
+        let actual_unexpected = find_problems(&unexpected);
+        let actual_expected = find_problems(&expected);
+        let actual_not_recorded = find_problems(&not_recorded);
+
+        // Assert
+        assert_eq!(
+            actual_unexpected,
+            vec![Problem::HashChanged {
+                recorded: "def456".to_string(),
+                actual: "abc123".to_string()
+            }]
+        );
+        assert!(actual_expected.is_empty());
+        assert!(actual_not_recorded.is_empty());
+    }
+
+    #[test]
+    fn find_problems_signer_checked_even_when_file_changed() {
+        // Arrange
+        let mut matching = new_check(None, true);
+        matching.expected_signer = Some("Example Ltd");
+        matching.actual_signer = Some("example ltd");
+        let mut unsigned = new_check(None, true);
+        unsigned.expected_signer = Some("Example Ltd");
+
+        // Act
+        let actual_matching = find_problems(&matching);
+        let actual_unsigned = find_problems(&unsigned);
+
+        // Assert
+        assert!(actual_matching.is_empty());
+        assert_eq!(
+            actual_unsigned,
+            vec![Problem::SignerMismatch {
+                expected: "Example Ltd".to_string(),
+                actual: None
+            }]
+        );
+    }
+
+    #[test]
+    fn apply_policy_warn_and_refuse() {
+        // Arrange
+        let problems = vec![Problem::HashChanged {
+            recorded: "def456".to_string(),
+            actual: "abc123".to_string(),
+        }];
+
+        // Act
+        let actual_off = apply_policy(&VerifyPolicy::Off, "Lemon", &problems).unwrap();
+        let actual_warn = apply_policy(&VerifyPolicy::Warn, "Lemon", &problems).unwrap();
+        let actual_refuse = apply_policy(&VerifyPolicy::Refuse, "Lemon", &problems);
+
+        // Assert
+        assert!(actual_off.is_empty());
+        assert_eq!(actual_warn.len(), 1);
+        assert!(actual_refuse.is_err());
+    }
+
+    #[test]
+    fn get_authenticode_signer_valid_and_invalid() {
+        // Arrange
+        let valid = vec![
+            "Valid".to_string(),
+            r#"CN="Example, Inc.", O="Example, Inc.", L=Redmond, C=US"#.to_string(),
+        ];
+        let not_signed = vec!["NotSigned".to_string(), String::new()];
+
+        // Act
+        let actual_valid = get_authenticode_signer(&valid);
+        let actual_not_signed = get_authenticode_signer(&not_signed);
+
+        // Assert
+        assert_eq!(actual_valid, Some("Example, Inc.".to_string()));
+        assert_eq!(actual_not_signed, None);
+    }
+
+    #[test]
+    fn get_codesign_team_id_from_display_output() {
+        // Arrange
+        let signed = "Executable=/Applications/Lime.app/Contents/MacOS/Lime\nAuthority=Developer ID Application: Example Ltd (ABCDE12345)\nTeamIdentifier=ABCDE12345\n";
+        let ad_hoc = "Executable=/Applications/Lime.app/Contents/MacOS/Lime\nSignature=adhoc\nTeamIdentifier=not set\n";
+
+        // Act
+        let actual_signed = get_codesign_team_id(signed);
+        let actual_ad_hoc = get_codesign_team_id(ad_hoc);
+
+        // Assert
+        assert_eq!(actual_signed, Some("ABCDE12345".to_string()));
+        assert_eq!(actual_ad_hoc, None);
+    }
+}